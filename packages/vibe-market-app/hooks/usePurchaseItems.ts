@@ -58,9 +58,12 @@ export const useNftBucketAddresses = (
         if (!nftBucket) {
           break
         }
-        nftBucketAddresses.push(bucketAddress)
+        // Sold buckets stay linked until compacted
+        if (!nftBucket.data.tombstoned) {
+          nftBucketAddresses.push(bucketAddress)
+          counter += 1
+        }
         bucketAddress = new PublicKey(nftBucket.data.nextListItem)
-        counter += 1
       }
       setNftBucketAddresses(nftBucketAddresses)
       setLoading(false)
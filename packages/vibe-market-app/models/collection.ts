@@ -12,6 +12,7 @@ export interface CollectionAccount {
   listHead: string
   listTail: string
   title: string
+  numInventoryPages: number
 }
 
 export class Collection extends BaseAnchorAccount<CollectionAccount> {}
@@ -30,7 +31,8 @@ export class CollectionManager extends BaseAnchorAccountManager<
     typeof entity.data.index === "number" &&
     typeof entity.data.listHead === "string" &&
     typeof entity.data.listTail === "string" &&
-    typeof entity.data.title === "string"
+    typeof entity.data.title === "string" &&
+    typeof entity.data.numInventoryPages === "number"
 
   toDomain = async (account: any, publicKey: PublicKey) => {
     const accountData = { ...account }
@@ -9,6 +9,7 @@ export const AccountType = "globalState"
 export interface GlobalStateAccount {
   nonce: number
   numMarkets: number
+  feeRecipient: string
 }
 
 export class GlobalState extends BaseAnchorAccount<GlobalStateAccount> {}
@@ -24,9 +25,12 @@ export class GlobalStateManager extends BaseAnchorAccountManager<
   isValid = (entity: any): entity is GlobalState =>
     entity instanceof GlobalState &&
     typeof entity.data.nonce === "number" &&
-    typeof entity.data.numMarkets === "number"
+    typeof entity.data.numMarkets === "number" &&
+    typeof entity.data.feeRecipient === "string"
 
   toDomain = async (account: any, publicKey: PublicKey) => {
-    return new GlobalState(publicKey, account)
+    const accountData = { ...account }
+    accountData.feeRecipient = accountData.feeRecipient.toString()
+    return new GlobalState(publicKey, accountData)
   }
 }
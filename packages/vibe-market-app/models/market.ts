@@ -12,7 +12,7 @@ export interface MarketAccount {
   whitelist: string[]
   numCollections: number
   numPriceModels: number
-  title: number
+  title: string
 }
 
 export class Market extends BaseAnchorAccount<MarketAccount> {}
@@ -36,9 +36,13 @@ export class MarketManager extends BaseAnchorAccountManager<
 
   toDomain = async (account: any, publicKey: PublicKey) => {
     const accountData = { ...account }
-    accountData.whitelist = _.map(accountData.whitelist, (publicKey) =>
-      publicKey.toString()
+    accountData.whitelist = _.map(
+      _.take(accountData.whitelist, accountData.whitelistLen),
+      (publicKey) => publicKey.toString()
     )
+    accountData.title = Buffer.from(
+      _.take(accountData.title, accountData.titleLen)
+    ).toString()
     return new Market(publicKey, accountData)
   }
 }
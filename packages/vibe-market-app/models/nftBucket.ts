@@ -13,6 +13,8 @@ export interface NftBucketAccount {
   prevListItem: string
   nextListItem: string
   payer: string
+  inventoryPage: string
+  tombstoned: number
 }
 
 export class NftBucket extends BaseAnchorAccount<NftBucketAccount> {}
@@ -32,7 +34,9 @@ export class NftBucketManager extends BaseAnchorAccountManager<
     typeof entity.data.priceModel === "string" &&
     typeof entity.data.prevListItem === "string" &&
     typeof entity.data.nextListItem === "string" &&
-    typeof entity.data.payer === "string"
+    typeof entity.data.payer === "string" &&
+    typeof entity.data.inventoryPage === "string" &&
+    typeof entity.data.tombstoned === "number"
 
   toDomain = async (account: any, publicKey: PublicKey) => {
     const accountData = { ...account }
@@ -41,6 +45,7 @@ export class NftBucketManager extends BaseAnchorAccountManager<
     accountData.prevListItem = accountData.prevListItem.toString()
     accountData.nextListItem = accountData.nextListItem.toString()
     accountData.payer = accountData.payer.toString()
+    accountData.inventoryPage = accountData.inventoryPage.toString()
     return new NftBucket(publicKey, accountData)
   }
 }
@@ -35,10 +35,13 @@ export class PriceModelManager extends BaseAnchorAccountManager<
   toDomain = async (account: any, publicKey: PublicKey) => {
     const accountData = { ...account }
     accountData.market = accountData.market.toString()
-    accountData.salePrices = _.forEach(accountData.salePrices, (salePrice) => {
-      salePrice.mint = salePrice.mint.toString()
-      salePrice.amount = salePrice.amount.toNumber()
-    })
+    accountData.salePrices = _.forEach(
+      _.take(accountData.salePrices, accountData.numSalePrices),
+      (salePrice) => {
+        salePrice.mint = salePrice.mint.toString()
+        salePrice.amount = salePrice.amount.toNumber()
+      }
+    )
     return new PriceModel(publicKey, accountData)
   }
 }
@@ -19,6 +19,14 @@ import {
 import { HToken, HTokenManager } from "../models/tokenAccount"
 import { getClusterConstants, setCluster } from "../constants"
 import { getVibeMarketProgram } from "../solana/getPrograms"
+import {
+  getInventoryPageSlots,
+  INVENTORY_PAGE_SLOTS,
+} from "../solana/inventoryPages"
+import {
+  getInventoryPageAddress,
+  getNftBucketAddress,
+} from "../solana/seedAddresses"
 import config from "./batchAddNftConfig.json"
 
 const BATCH_SIZE = 3
@@ -102,6 +110,12 @@ const main = async () => {
   const listHead = await vibeMarketProgram.account.nftBucket.fetch(
     collection.listHead
   )
+  let numInventoryPages = collection.numInventoryPages
+  const inventoryPages = await getInventoryPageSlots(
+    vibeMarketProgram,
+    collectionAddress,
+    numInventoryPages
+  )
 
   console.log("Fetching token accounts...")
   const tokenAccountManager = new HTokenManager(connection)
@@ -137,8 +151,39 @@ const main = async () => {
     const signers: Signer[] = [keypair]
     for (let i = 0; i < processingTokenAccounts.length; i++) {
       const tokenAccount = processingTokenAccounts[i]
-      const nftBucket = Keypair.generate()
       const nftMintAddress = new PublicKey(tokenAccount.data.mint)
+      const [nftBucketAddress] = await getNftBucketAddress(
+        collectionAddress,
+        nftMintAddress
+      )
+
+      // Each NFT takes a slot in the first page with one free, or a new page
+      let inventoryPage = _.find(inventoryPages, (page) => page.numFree > 0)
+      if (!inventoryPage) {
+        const [inventoryPageAddress] = await getInventoryPageAddress(
+          collectionAddress,
+          numInventoryPages
+        )
+        transaction.add(
+          vibeMarketProgram.instruction.initInventoryPage({
+            accounts: {
+              admin: wallet.publicKey,
+              market: ADDRESS_VIBE_MARKET,
+              collection: collectionAddress,
+              inventoryPage: inventoryPageAddress,
+              systemProgram: SystemProgram.programId,
+            },
+          })
+        )
+        inventoryPage = {
+          address: inventoryPageAddress,
+          numFree: INVENTORY_PAGE_SLOTS,
+        }
+        inventoryPages.push(inventoryPage)
+        numInventoryPages += 1
+      }
+      inventoryPage.numFree -= 1
+
       const programAssociatedAddress = await Token.getAssociatedTokenAddress(
         ASSOCIATED_TOKEN_PROGRAM_ID,
         TOKEN_PROGRAM_ID,
@@ -152,9 +197,10 @@ const main = async () => {
             admin: wallet.publicKey,
             market: ADDRESS_VIBE_MARKET,
             collection: collectionAddress,
-            listHead: listHeadAddress,
+            prevListItem: listHeadAddress,
             nextListItem: nextListItem,
-            newItem: nftBucket.publicKey,
+            newItem: nftBucketAddress,
+            inventoryPage: inventoryPage.address,
             priceModel: priceModelAddress,
             adminNftAccount: tokenAccount.publicKey,
             adminNftMint: nftMintAddress,
@@ -166,8 +212,7 @@ const main = async () => {
           },
         })
       )
-      signers.push(nftBucket)
-      nextListItem = nftBucket.publicKey
+      nextListItem = nftBucketAddress
     }
     const txSig = await provider.send(transaction, signers)
     console.log(`Tx succeeded: ${txSig}`)
@@ -0,0 +1,34 @@
+import _ from "lodash"
+import { PublicKey } from "@solana/web3.js"
+import { Program } from "@project-serum/anchor"
+import { VibeMarket } from "./vibeMarket"
+import { getInventoryPageAddress } from "./seedAddresses"
+
+export const INVENTORY_PAGE_SLOTS = 64
+
+export type InventoryPageSlots = {
+  address: PublicKey
+  numFree: number
+}
+
+// Every inventory page of a collection in index order, with its free slots
+export const getInventoryPageSlots = async (
+  program: Program<VibeMarket>,
+  collectionAddress: PublicKey,
+  numInventoryPages: number
+): Promise<InventoryPageSlots[]> => {
+  const addresses = await Promise.all(
+    _.map(_.range(numInventoryPages), async (index) => {
+      const retval = await getInventoryPageAddress(collectionAddress, index)
+      return retval[0]
+    })
+  )
+  const pages = await program.account.inventoryPage.fetchMultiple(addresses)
+  return _.map(addresses, (address, i) => {
+    const page: any = pages[i]
+    if (!page) {
+      throw new Error(`Inventory page ${address.toString()} not found`)
+    }
+    return { address, numFree: INVENTORY_PAGE_SLOTS - page.numOccupied }
+  })
+}
@@ -1,11 +1,17 @@
+import _ from "lodash"
 import {
   PublicKey,
-  Keypair,
   SystemProgram,
   SYSVAR_RENT_PUBKEY,
+  TransactionInstruction,
 } from "@solana/web3.js"
 import { Token } from "@solana/spl-token"
 import { getClusterConstants } from "../../constants"
+import { getInventoryPageSlots } from "../inventoryPages"
+import {
+  getInventoryPageAddress,
+  getNftBucketAddress,
+} from "../seedAddresses"
 import type { IAnchorAccountCacheContext } from "../../contexts/AnchorAccountsCacheProvider"
 
 const addNft = async (
@@ -49,7 +55,41 @@ const addNft = async (
     throw new Error("Token account not found")
   }
   const nftMint = new PublicKey(tokenAccount.data.mint)
-  const nftBucket = Keypair.generate()
+  const [nftBucketAddress] = await getNftBucketAddress(
+    collectionPublicKey,
+    nftMint
+  )
+
+  // The NFT takes a slot in the first page with one free, or a new page
+  const vibeMarketProgram = anchorAccountCache.vibeMarketProgram
+  const { numInventoryPages } = collection.data
+  const inventoryPages = await getInventoryPageSlots(
+    vibeMarketProgram,
+    collectionPublicKey,
+    numInventoryPages
+  )
+  const freePage = _.find(inventoryPages, (page) => page.numFree > 0)
+  const instructions: TransactionInstruction[] = []
+  let inventoryPageAddress: PublicKey
+  if (freePage) {
+    inventoryPageAddress = freePage.address
+  } else {
+    ;[inventoryPageAddress] = await getInventoryPageAddress(
+      collectionPublicKey,
+      numInventoryPages
+    )
+    instructions.push(
+      vibeMarketProgram.instruction.initInventoryPage({
+        accounts: {
+          admin: walletPublicKey,
+          market: ADDRESS_VIBE_MARKET,
+          collection: collectionPublicKey,
+          inventoryPage: inventoryPageAddress,
+          systemProgram: SystemProgram.programId,
+        },
+      })
+    )
+  }
 
   const adminAssociatedAddress = await Token.getAssociatedTokenAddress(
     PROGRAM_ASSOCIATED_TOKEN,
@@ -65,14 +105,15 @@ const addNft = async (
     true
   )
 
-  await anchorAccountCache.vibeMarketProgram.rpc.addNft({
+  await vibeMarketProgram.rpc.addNft({
     accounts: {
       admin: walletPublicKey,
       market: ADDRESS_VIBE_MARKET,
       collection: collectionPublicKey,
-      listHead: listHeadPublicKey,
+      prevListItem: listHeadPublicKey,
       nextListItem: nextListItemPublicKey,
-      newItem: nftBucket.publicKey,
+      newItem: nftBucketAddress,
+      inventoryPage: inventoryPageAddress,
       priceModel: priceModelPublicKey,
       adminNftAccount: adminAssociatedAddress,
       adminNftMint: nftMint,
@@ -82,7 +123,7 @@ const addNft = async (
       systemProgram: SystemProgram.programId,
       rent: SYSVAR_RENT_PUBKEY,
     },
-    signers: [nftBucket],
+    instructions,
   })
 }
 
@@ -11,7 +11,10 @@ const closeCollection = async (
   if (!anchorAccountCache.isEnabled) {
     throw new Error("Application is not connected")
   }
-  const { ADDRESS_VIBE_MARKET } = getClusterConstants("ADDRESS_VIBE_MARKET")
+  const { ADDRESS_VIBE_MARKET, PROGRAM_TOKEN } = getClusterConstants(
+    "ADDRESS_VIBE_MARKET",
+    "PROGRAM_TOKEN"
+  )
   const [listHeadAddress] = await getListHeadAddress(collectionAddress)
   const [listTailAddress] = await getListTailAddress(collectionAddress)
   const listHead = await anchorAccountCache.fetch("nftBucket", listHeadAddress)
@@ -21,6 +24,13 @@ const closeCollection = async (
   }
   const rentRefundAddress = new PublicKey(listHead.data.payer)
 
+  // Every treasury the collection has been paid into must be closed with it
+  const { value: treasuryAccounts } =
+    await anchorAccountCache.vibeMarketProgram.provider.connection.getTokenAccountsByOwner(
+      collectionAddress,
+      { programId: PROGRAM_TOKEN }
+    )
+
   await anchorAccountCache.vibeMarketProgram.rpc.closeCollection({
     accounts: {
       admin: walletPublicKey,
@@ -29,7 +39,13 @@ const closeCollection = async (
       collection: collectionAddress,
       listHead: listHeadAddress,
       listTail: listTailAddress,
+      tokenProgram: PROGRAM_TOKEN,
     },
+    remainingAccounts: treasuryAccounts.map(({ pubkey }) => ({
+      pubkey,
+      isWritable: true,
+      isSigner: false,
+    })),
   })
 }
 
@@ -27,30 +27,22 @@ const createCollection = async (
     throw new Error(`market account not found: ${ADDRESS_VIBE_MARKET}`)
   }
 
-  const [collectionAddress, collectionAddressNonce] =
-    await getCollectionAddress(ADDRESS_VIBE_MARKET, market.data.numCollections)
-  const [listHeadAddress, listHeadAddressNonce] = await getListHeadAddress(
-    collectionAddress
-  )
-  const [listTailAddress, listTailAddressNonce] = await getListTailAddress(
-    collectionAddress
-  )
-  await anchorAccountCache.vibeMarketProgram.rpc.initCollection(
-    collectionAddressNonce,
-    listHeadAddressNonce,
-    listTailAddressNonce,
-    title,
-    {
-      accounts: {
-        admin: walletPublicKey,
-        market: market.publicKey,
-        collection: collectionAddress,
-        listHead: listHeadAddress,
-        listTail: listTailAddress,
-        systemProgram: SystemProgram.programId,
-      },
-    }
+  const [collectionAddress] = await getCollectionAddress(
+    ADDRESS_VIBE_MARKET,
+    market.data.numCollections
   )
+  const [listHeadAddress] = await getListHeadAddress(collectionAddress)
+  const [listTailAddress] = await getListTailAddress(collectionAddress)
+  await anchorAccountCache.vibeMarketProgram.rpc.initCollection(title, {
+    accounts: {
+      admin: walletPublicKey,
+      market: market.publicKey,
+      collection: collectionAddress,
+      listHead: listHeadAddress,
+      listTail: listTailAddress,
+      systemProgram: SystemProgram.programId,
+    },
+  })
 }
 
 export default createCollection
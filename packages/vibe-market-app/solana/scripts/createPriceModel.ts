@@ -30,8 +30,10 @@ const createPriceModel = async (
     throw new Error(`market account not found: ${ADDRESS_VIBE_MARKET}`)
   }
 
-  const [priceModelAddress, priceModelAddressNonce] =
-    await getPriceModelAddress(ADDRESS_VIBE_MARKET, market.data.numPriceModels)
+  const [priceModelAddress] = await getPriceModelAddress(
+    ADDRESS_VIBE_MARKET,
+    market.data.numPriceModels
+  )
 
   const filteredSalePrices = _.filter(
     salePrices,
@@ -55,7 +57,6 @@ const createPriceModel = async (
   }
 
   await anchorAccountCache.vibeMarketProgram.rpc.initPriceModel(
-    priceModelAddressNonce,
     adjustedSalePrices,
     {
       accounts: {
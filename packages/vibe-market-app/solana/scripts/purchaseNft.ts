@@ -9,6 +9,7 @@ import {
 } from "@solana/web3.js"
 import { Token, AccountLayout } from "@solana/spl-token"
 import { getClusterConstants } from "../../constants"
+import { getGlobalStateAddress } from "../seedAddresses"
 import type { IAnchorAccountCacheContext } from "../../contexts/AnchorAccountsCacheProvider"
 
 const purchaseNft = async (
@@ -43,6 +44,16 @@ const purchaseNft = async (
     throw new Error("Nft bucket not found")
   }
 
+  const [globalStateAddress] = await getGlobalStateAddress()
+  const globalState = await anchorAccountCache.fetch(
+    "globalState",
+    globalStateAddress
+  )
+  if (!globalState) {
+    throw new Error("Global state not found")
+  }
+  const feeRecipientAddress = new PublicKey(globalState.data.feeRecipient)
+
   const nftMintAddress = new PublicKey(nftBucket.data.nftMint)
 
   const userNftAccountAddress = await Token.getAssociatedTokenAddress(
@@ -51,11 +62,20 @@ const purchaseNft = async (
     nftMintAddress,
     walletPublicKey
   )
+  // Proceeds are paid into the collection's treasury
   const programCreditAccountAddress = await Token.getAssociatedTokenAddress(
     PROGRAM_ASSOCIATED_TOKEN,
     PROGRAM_TOKEN,
     paymentMintPublicKey,
-    ADDRESS_VIBE_MARKET,
+    collectionPublicKey,
+    true
+  )
+  // The protocol fee is waived while the fee recipient has no account here
+  const feeCreditAccountAddress = await Token.getAssociatedTokenAddress(
+    PROGRAM_ASSOCIATED_TOKEN,
+    PROGRAM_TOKEN,
+    paymentMintPublicKey,
+    feeRecipientAddress,
     true
   )
   const programNftAccountAddress = await Token.getAssociatedTokenAddress(
@@ -122,18 +142,20 @@ const purchaseNft = async (
       accounts: {
         owner: walletPublicKey,
         rentRefund: new PublicKey(nftBucket.data.payer),
+        globalState: globalStateAddress,
         priceModel: priceModelAddress,
         market: ADDRESS_VIBE_MARKET,
         collection: collectionPublicKey,
         purchaseListItem: nftBucketPublicKey,
+        inventoryPage: new PublicKey(nftBucket.data.inventoryPage),
         debitMint: paymentMintPublicKey,
         debitAccount: paymentAccountAddress,
         programCreditAccount: programCreditAccountAddress,
+        feeRecipient: feeRecipientAddress,
+        feeCreditAccount: feeCreditAccountAddress,
         programNftAccount: programNftAccountAddress,
         programNftMint: nftMintAddress,
         ownerNftAccount: userNftAccountAddress,
-        prevListItem: new PublicKey(nftBucket.data.prevListItem),
-        nextListItem: new PublicKey(nftBucket.data.nextListItem),
         associatedTokenProgram: PROGRAM_ASSOCIATED_TOKEN,
         tokenProgram: PROGRAM_TOKEN,
         systemProgram: SystemProgram.programId,
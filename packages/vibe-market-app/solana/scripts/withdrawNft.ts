@@ -56,11 +56,10 @@ const withdrawNft = async (
       market: ADDRESS_VIBE_MARKET,
       collection: collectionPublicKey,
       withdrawListItem: withdrawNftBucket.publicKey,
+      inventoryPage: new PublicKey(withdrawNftBucket.data.inventoryPage),
       programNftAccount: programNftAccountAddress,
       programNftMint: nftMintPublicKey,
       adminNftAccount: adminNftAccountAddress,
-      prevListItem: new PublicKey(withdrawNftBucket.data.prevListItem),
-      nextListItem: new PublicKey(withdrawNftBucket.data.nextListItem),
       associatedTokenProgram: PROGRAM_ASSOCIATED_TOKEN,
       tokenProgram: PROGRAM_TOKEN,
       systemProgram: SystemProgram.programId,
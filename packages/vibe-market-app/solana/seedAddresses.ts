@@ -50,6 +50,33 @@ export const getListTailAddress = (collectionAddress: PublicKey) => {
   )
 }
 
+export const getInventoryPageAddress = (
+  collectionAddress: PublicKey,
+  index: number
+) => {
+  const { PROGRAM_VIBE_MARKET } = getClusterConstants("PROGRAM_VIBE_MARKET")
+  const indexBuffer = Uint8Array.from(new anchor.BN(index).toArray("le", 4))
+  return PublicKey.findProgramAddress(
+    [collectionAddress.toBuffer(), indexBuffer, Buffer.from("inventory_page")],
+    PROGRAM_VIBE_MARKET
+  )
+}
+
+export const getNftBucketAddress = (
+  collectionAddress: PublicKey,
+  nftMintAddress: PublicKey
+) => {
+  const { PROGRAM_VIBE_MARKET } = getClusterConstants("PROGRAM_VIBE_MARKET")
+  return PublicKey.findProgramAddress(
+    [
+      collectionAddress.toBuffer(),
+      nftMintAddress.toBuffer(),
+      Buffer.from("nft_bucket"),
+    ],
+    PROGRAM_VIBE_MARKET
+  )
+}
+
 export const getPriceModelAddress = (
   marketAddress: PublicKey,
   index: number
@@ -10,6 +10,11 @@ export type VibeMarket = {
           isMut: false
           isSigner: true
         },
+        {
+          name: "programData"
+          isMut: false
+          isSigner: false
+        },
         {
           name: "globalState"
           isMut: true
@@ -21,10 +26,79 @@ export type VibeMarket = {
           isSigner: false
         }
       ]
+      args: []
+    },
+    {
+      name: "updateProtocolFee"
+      accounts: [
+        {
+          name: "authority"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "globalState"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "feeRecipient"
+          isMut: false
+          isSigner: false
+        }
+      ]
       args: [
         {
-          name: "nonce"
-          type: "u8"
+          name: "feeBasisPoints"
+          type: "u16"
+        }
+      ]
+    },
+    {
+      name: "setGlobalAuthority"
+      accounts: [
+        {
+          name: "authority"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "globalState"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "newAuthority"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "updateMarketCreation"
+      accounts: [
+        {
+          name: "authority"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "globalState"
+          isMut: true
+          isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "marketCreation"
+          type: {
+            defined: "MarketCreation"
+          }
+        },
+        {
+          name: "marketCreationFee"
+          type: "u64"
         }
       ]
     },
@@ -41,6 +115,11 @@ export type VibeMarket = {
           isMut: true
           isSigner: false
         },
+        {
+          name: "feeRecipient"
+          isMut: true
+          isSigner: false
+        },
         {
           name: "market"
           isMut: true
@@ -53,10 +132,6 @@ export type VibeMarket = {
         }
       ]
       args: [
-        {
-          name: "nonce"
-          type: "u8"
-        },
         {
           name: "whitelist"
           type: {
@@ -111,6 +186,54 @@ export type VibeMarket = {
       ]
       args: []
     },
+    {
+      name: "setMarketPaused"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "paused"
+          type: "bool"
+        }
+      ]
+    },
+    {
+      name: "updateMarketMetadata"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "title"
+          type: "string"
+        },
+        {
+          name: "metadata"
+          type: {
+            defined: "Metadata"
+          }
+        }
+      ]
+    },
     {
       name: "initCollection"
       accounts: [
@@ -146,18 +269,6 @@ export type VibeMarket = {
         }
       ]
       args: [
-        {
-          name: "collectionNonce"
-          type: "u8"
-        },
-        {
-          name: "listHeadNonce"
-          type: "u8"
-        },
-        {
-          name: "listTailNonce"
-          type: "u8"
-        },
         {
           name: "title"
           type: "string"
@@ -165,7 +276,7 @@ export type VibeMarket = {
       ]
     },
     {
-      name: "initPriceModel"
+      name: "setCollectionPaused"
       accounts: [
         {
           name: "admin"
@@ -174,37 +285,58 @@ export type VibeMarket = {
         },
         {
           name: "market"
-          isMut: true
+          isMut: false
           isSigner: false
         },
         {
-          name: "priceModel"
+          name: "collection"
           isMut: true
           isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "paused"
+          type: "bool"
+        }
+      ]
+    },
+    {
+      name: "setCollectionSaleWindow"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
         },
         {
-          name: "systemProgram"
+          name: "market"
           isMut: false
           isSigner: false
+        },
+        {
+          name: "collection"
+          isMut: true
+          isSigner: false
         }
       ]
       args: [
         {
-          name: "nonce"
-          type: "u8"
+          name: "saleStart"
+          type: {
+            option: "i64"
+          }
         },
         {
-          name: "salePrices"
+          name: "saleEnd"
           type: {
-            vec: {
-              defined: "SalePrice"
-            }
+            option: "i64"
           }
         }
       ]
     },
     {
-      name: "addNft"
+      name: "setNftSaleWindow"
       accounts: [
         {
           name: "admin"
@@ -218,218 +350,314 @@ export type VibeMarket = {
         },
         {
           name: "collection"
-          isMut: false
+          isMut: true
           isSigner: false
         },
         {
-          name: "listHead"
+          name: "listItem"
           isMut: true
           isSigner: false
         },
         {
-          name: "nextListItem"
+          name: "inventoryPage"
           isMut: true
           isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "saleStart"
+          type: {
+            option: "i64"
+          }
         },
         {
-          name: "newItem"
-          isMut: true
+          name: "saleEnd"
+          type: {
+            option: "i64"
+          }
+        }
+      ]
+    },
+    {
+      name: "setNftExpiry"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
           isSigner: true
         },
         {
-          name: "priceModel"
+          name: "market"
           isMut: false
           isSigner: false
         },
         {
-          name: "adminNftAccount"
+          name: "collection"
           isMut: true
           isSigner: false
         },
         {
-          name: "adminNftMint"
-          isMut: false
+          name: "listItem"
+          isMut: true
           isSigner: false
         },
         {
-          name: "programNftAccount"
+          name: "inventoryPage"
           isMut: true
           isSigner: false
-        },
+        }
+      ]
+      args: [
         {
-          name: "associatedTokenProgram"
+          name: "expiresAt"
+          type: {
+            option: "i64"
+          }
+        }
+      ]
+    },
+    {
+      name: "setMysteryPriceModel"
+      accounts: [
+        {
+          name: "admin"
           isMut: false
-          isSigner: false
+          isSigner: true
         },
         {
-          name: "tokenProgram"
+          name: "market"
           isMut: false
           isSigner: false
         },
         {
-          name: "systemProgram"
-          isMut: false
+          name: "collection"
+          isMut: true
           isSigner: false
         },
         {
-          name: "rent"
+          name: "priceModel"
           isMut: false
           isSigner: false
         }
       ]
-      args: []
+      args: [
+        {
+          name: "enabled"
+          type: "bool"
+        }
+      ]
     },
     {
-      name: "withdrawNft"
+      name: "updateCollectionMetadata"
       accounts: [
         {
           name: "admin"
-          isMut: false
+          isMut: true
           isSigner: true
         },
         {
-          name: "rentRefund"
-          isMut: true
+          name: "market"
+          isMut: false
           isSigner: false
         },
         {
-          name: "priceModel"
-          isMut: false
+          name: "collection"
+          isMut: true
           isSigner: false
         },
         {
-          name: "market"
+          name: "systemProgram"
           isMut: false
           isSigner: false
         },
         {
-          name: "collection"
+          name: "rent"
           isMut: false
           isSigner: false
-        },
+        }
+      ]
+      args: [
         {
-          name: "withdrawListItem"
-          isMut: true
-          isSigner: false
+          name: "title"
+          type: "string"
         },
         {
-          name: "programNftAccount"
-          isMut: true
-          isSigner: false
+          name: "metadata"
+          type: {
+            defined: "Metadata"
+          }
+        }
+      ]
+    },
+    {
+      name: "initInventoryPage"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
         },
         {
-          name: "programNftMint"
+          name: "market"
           isMut: false
           isSigner: false
         },
         {
-          name: "adminNftAccount"
+          name: "collection"
           isMut: true
           isSigner: false
         },
         {
-          name: "prevListItem"
+          name: "inventoryPage"
           isMut: true
           isSigner: false
         },
         {
-          name: "nextListItem"
-          isMut: true
+          name: "systemProgram"
+          isMut: false
           isSigner: false
-        },
+        }
+      ]
+      args: []
+    },
+    {
+      name: "closeInventoryPage"
+      accounts: [
         {
-          name: "associatedTokenProgram"
+          name: "admin"
           isMut: false
-          isSigner: false
+          isSigner: true
         },
         {
-          name: "tokenProgram"
+          name: "market"
           isMut: false
           isSigner: false
         },
         {
-          name: "systemProgram"
-          isMut: false
+          name: "collection"
+          isMut: true
           isSigner: false
         },
         {
-          name: "rent"
-          isMut: false
+          name: "inventoryPage"
+          isMut: true
           isSigner: false
         }
       ]
       args: []
     },
     {
-      name: "purchaseNft"
+      name: "initPriceModel"
       accounts: [
         {
-          name: "owner"
+          name: "admin"
           isMut: false
           isSigner: true
         },
         {
-          name: "rentRefund"
+          name: "market"
           isMut: true
           isSigner: false
         },
         {
           name: "priceModel"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
           isMut: false
           isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "salePrices"
+          type: {
+            vec: {
+              defined: "SalePrice"
+            }
+          }
+        }
+      ]
+    },
+    {
+      name: "closePriceModel"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
         },
         {
           name: "market"
-          isMut: false
+          isMut: true
           isSigner: false
         },
         {
-          name: "collection"
-          isMut: false
+          name: "priceModel"
+          isMut: true
           isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "addNft"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
         },
         {
-          name: "purchaseListItem"
+          name: "market"
           isMut: true
           isSigner: false
         },
         {
-          name: "debitMint"
-          isMut: false
+          name: "collection"
+          isMut: true
           isSigner: false
         },
         {
-          name: "debitAccount"
+          name: "prevListItem"
           isMut: true
           isSigner: false
         },
         {
-          name: "programCreditAccount"
+          name: "nextListItem"
           isMut: true
           isSigner: false
         },
         {
-          name: "programNftAccount"
+          name: "adminNftAccount"
           isMut: true
           isSigner: false
         },
         {
-          name: "programNftMint"
+          name: "adminNftMint"
           isMut: false
           isSigner: false
         },
         {
-          name: "ownerNftAccount"
+          name: "newItem"
           isMut: true
           isSigner: false
         },
         {
-          name: "prevListItem"
+          name: "inventoryPage"
           isMut: true
           isSigner: false
         },
         {
-          name: "nextListItem"
+          name: "priceModel"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "programNftAccount"
           isMut: true
           isSigner: false
         },
@@ -457,7 +685,7 @@ export type VibeMarket = {
       args: []
     },
     {
-      name: "withdrawLiquidity"
+      name: "insertNftAfter"
       accounts: [
         {
           name: "admin"
@@ -465,27 +693,52 @@ export type VibeMarket = {
           isSigner: true
         },
         {
-          name: "globalState"
-          isMut: false
+          name: "market"
+          isMut: true
           isSigner: false
         },
         {
-          name: "market"
-          isMut: false
+          name: "collection"
+          isMut: true
           isSigner: false
         },
         {
-          name: "withdrawMint"
+          name: "prevListItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "nextListItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "adminNftAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "adminNftMint"
           isMut: false
           isSigner: false
         },
         {
-          name: "programDebitAccount"
+          name: "newItem"
           isMut: true
           isSigner: false
         },
         {
-          name: "adminCreditAccount"
+          name: "inventoryPage"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "priceModel"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "programNftAccount"
           isMut: true
           isSigner: false
         },
@@ -510,15 +763,77 @@ export type VibeMarket = {
           isSigner: false
         }
       ]
+      args: []
+    },
+    {
+      name: "moveItem"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "collection"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "listItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "prevListItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "nextListItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "newPrevListItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "newNextListItem"
+          isMut: true
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "listPage"
+      accounts: [
+        {
+          name: "collection"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "cursor"
+          isMut: false
+          isSigner: false
+        }
+      ]
       args: [
         {
-          name: "amount"
-          type: "u64"
+          name: "limit"
+          type: "u8"
         }
       ]
     },
     {
-      name: "closeCollection"
+      name: "verifyCollection"
       accounts: [
         {
           name: "admin"
@@ -531,940 +846,5415 @@ export type VibeMarket = {
           isSigner: false
         },
         {
-          name: "rentRefund"
+          name: "collection"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "cursor"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "repairLink"
+      accounts: [
+        {
+          name: "admin"
           isMut: false
           isSigner: true
         },
+        {
+          name: "market"
+          isMut: false
+          isSigner: false
+        },
         {
           name: "collection"
-          isMut: true
+          isMut: false
           isSigner: false
         },
         {
-          name: "listHead"
+          name: "prevListItem"
           isMut: true
           isSigner: false
         },
         {
-          name: "listTail"
+          name: "nextListItem"
           isMut: true
           isSigner: false
         }
       ]
       args: []
-    }
-  ]
-  accounts: [
-    {
-      name: "globalState"
-      type: {
-        kind: "struct"
-        fields: [
-          {
-            name: "nonce"
-            type: "u8"
-          },
-          {
-            name: "numMarkets"
-            type: "u32"
-          }
-        ]
-      }
     },
     {
-      name: "market"
-      type: {
-        kind: "struct"
-        fields: [
-          {
-            name: "nonce"
-            type: "u8"
-          },
-          {
-            name: "index"
-            type: "u32"
-          },
-          {
-            name: "whitelist"
-            type: {
-              vec: "publicKey"
-            }
-          },
-          {
-            name: "numCollections"
-            type: "u32"
-          },
-          {
-            name: "numPriceModels"
-            type: "u32"
-          },
-          {
-            name: "title"
-            type: "string"
-          }
-        ]
-      }
-    },
-    {
-      name: "collection"
-      type: {
-        kind: "struct"
-        fields: [
-          {
-            name: "nonce"
-            type: "u8"
-          },
-          {
-            name: "index"
-            type: "u32"
-          },
-          {
-            name: "listHead"
-            type: "publicKey"
-          },
-          {
-            name: "listTail"
-            type: "publicKey"
-          },
-          {
-            name: "title"
-            type: "string"
-          }
-        ]
-      }
-    },
-    {
-      name: "nftBucket"
-      type: {
-        kind: "struct"
-        fields: [
-          {
-            name: "nonce"
-            type: "u8"
-          },
-          {
-            name: "nftMint"
-            type: "publicKey"
-          },
-          {
-            name: "priceModel"
-            type: "publicKey"
-          },
-          {
-            name: "prevListItem"
-            type: "publicKey"
-          },
-          {
-            name: "nextListItem"
-            type: "publicKey"
-          },
-          {
-            name: "payer"
-            type: "publicKey"
-          }
-        ]
-      }
-    },
-    {
-      name: "priceModel"
-      type: {
-        kind: "struct"
-        fields: [
-          {
-            name: "nonce"
-            type: "u8"
-          },
-          {
-            name: "index"
-            type: "u32"
-          },
-          {
-            name: "market"
-            type: "publicKey"
-          },
-          {
-            name: "salePrices"
-            type: {
-              vec: {
-                defined: "SalePrice"
-              }
-            }
-          }
-        ]
-      }
-    }
-  ]
-  types: [
-    {
-      name: "SalePrice"
-      type: {
-        kind: "struct"
-        fields: [
-          {
-            name: "mint"
-            type: "publicKey"
-          },
-          {
-            name: "amount"
-            type: "u64"
-          }
-        ]
-      }
-    }
-  ]
-  errors: [
-    {
-      code: 300
-      name: "Unauthorized"
-      msg: "Instruction invoked without a valid admin."
-    },
-    {
-      code: 301
-      name: "CannotRemoveSelf"
-      msg: "Signing account cannot remove itself."
-    },
-    {
-      code: 302
-      name: "InvalidPurchaseMint"
-      msg: "Cannot purchase from selected collection with specified Mint."
-    },
-    {
-      code: 303
-      name: "Overflow"
-      msg: "Overflow when applying an arithmetic operation."
-    },
-    {
-      code: 304
-      name: "AdminNotFound"
-      msg: "Admin address was not found in market whitelist."
-    },
-    {
-      code: 305
-      name: "AdminOutOfBounds"
-      msg: "Admin whitelist exceeded max length of 16."
-    },
-    {
-      code: 306
-      name: "CollectionNonEmpty"
-      msg: "Collections cannot be closed until all NFTs are removed."
-    }
-  ]
-}
-
-export const IDL: VibeMarket = {
-  version: "0.0.0",
-  name: "vibe_market",
-  instructions: [
-    {
-      name: "initGlobalState",
+      name: "withdrawNft"
       accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "admin"
+          isMut: false
+          isSigner: true
         },
         {
-          name: "globalState",
-          isMut: true,
-          isSigner: false,
+          name: "rentRefund"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "systemProgram",
-          isMut: false,
-          isSigner: false,
+          name: "priceModel"
+          isMut: true
+          isSigner: false
         },
-      ],
-      args: [
         {
-          name: "nonce",
-          type: "u8",
+          name: "market"
+          isMut: true
+          isSigner: false
         },
-      ],
-    },
-    {
-      name: "initMarket",
-      accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "collection"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "globalState",
-          isMut: true,
-          isSigner: false,
+          name: "withdrawListItem"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "market",
-          isMut: true,
-          isSigner: false,
+          name: "inventoryPage"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "systemProgram",
-          isMut: false,
-          isSigner: false,
+          name: "programNftAccount"
+          isMut: true
+          isSigner: false
         },
-      ],
-      args: [
         {
-          name: "nonce",
-          type: "u8",
+          name: "programNftMint"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "whitelist",
-          type: {
-            vec: "publicKey",
-          },
+          name: "adminNftAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "title",
-          type: "string",
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
         },
-      ],
-    },
-    {
-      name: "addAdmin",
-      accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "market",
-          isMut: true,
-          isSigner: false,
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "addAdmin",
-          isMut: false,
-          isSigner: false,
-        },
-      ],
-      args: [],
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
     },
     {
-      name: "removeAdmin",
+      name: "quotePurchase"
       accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "globalState"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "market",
-          isMut: true,
-          isSigner: false,
+          name: "priceModel"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "removeAdmin",
-          isMut: false,
-          isSigner: false,
+          name: "market"
+          isMut: false
+          isSigner: false
         },
-      ],
-      args: [],
+        {
+          name: "collection"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "listItem"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
     },
     {
-      name: "initCollection",
+      name: "purchaseNft"
       accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "owner"
+          isMut: false
+          isSigner: true
         },
         {
-          name: "market",
-          isMut: true,
-          isSigner: false,
+          name: "rentRefund"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "collection",
-          isMut: true,
-          isSigner: false,
+          name: "globalState"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "listHead",
-          isMut: true,
-          isSigner: false,
+          name: "priceModel"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "listTail",
-          isMut: true,
-          isSigner: false,
+          name: "market"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "systemProgram",
-          isMut: false,
-          isSigner: false,
+          name: "collection"
+          isMut: true
+          isSigner: false
         },
-      ],
-      args: [
         {
-          name: "collectionNonce",
-          type: "u8",
+          name: "purchaseListItem"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "listHeadNonce",
-          type: "u8",
+          name: "inventoryPage"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "listTailNonce",
-          type: "u8",
+          name: "debitMint"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "title",
-          type: "string",
+          name: "debitAccount"
+          isMut: true
+          isSigner: false
         },
-      ],
-    },
-    {
-      name: "initPriceModel",
-      accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "programCreditAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "market",
-          isMut: true,
-          isSigner: false,
+          name: "feeRecipient"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "priceModel",
-          isMut: true,
-          isSigner: false,
+          name: "feeCreditAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "systemProgram",
-          isMut: false,
-          isSigner: false,
+          name: "programNftAccount"
+          isMut: true
+          isSigner: false
         },
-      ],
-      args: [
         {
-          name: "nonce",
-          type: "u8",
+          name: "programNftMint"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "salePrices",
-          type: {
-            vec: {
-              defined: "SalePrice",
-            },
-          },
+          name: "ownerNftAccount"
+          isMut: true
+          isSigner: false
         },
-      ],
-    },
-    {
-      name: "addNft",
-      accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "market",
-          isMut: false,
-          isSigner: false,
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "collection",
-          isMut: false,
-          isSigner: false,
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "listHead",
-          isMut: true,
-          isSigner: false,
-        },
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "crankExpired"
+      accounts: [
         {
-          name: "nextListItem",
-          isMut: true,
-          isSigner: false,
+          name: "cranker"
+          isMut: true
+          isSigner: true
         },
         {
-          name: "newItem",
-          isMut: true,
-          isSigner: true,
+          name: "rentRefund"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "priceModel",
-          isMut: false,
-          isSigner: false,
+          name: "priceModel"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "adminNftAccount",
-          isMut: true,
-          isSigner: false,
+          name: "market"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "adminNftMint",
-          isMut: false,
-          isSigner: false,
+          name: "collection"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "programNftAccount",
-          isMut: true,
-          isSigner: false,
+          name: "expiredListItem"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "associatedTokenProgram",
-          isMut: false,
-          isSigner: false,
+          name: "prevListItem"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "tokenProgram",
-          isMut: false,
-          isSigner: false,
+          name: "nextListItem"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "systemProgram",
-          isMut: false,
-          isSigner: false,
+          name: "inventoryPage"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "rent",
-          isMut: false,
-          isSigner: false,
+          name: "programNftAccount"
+          isMut: true
+          isSigner: false
         },
-      ],
-      args: [],
-    },
-    {
-      name: "withdrawNft",
-      accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "programNftMint"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "rentRefund",
-          isMut: true,
-          isSigner: false,
+          name: "payerNftAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "priceModel",
-          isMut: false,
-          isSigner: false,
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "market",
-          isMut: false,
-          isSigner: false,
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "collection",
-          isMut: false,
-          isSigner: false,
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "withdrawListItem",
-          isMut: true,
-          isSigner: false,
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "commitRandomPurchase"
+      accounts: [
+        {
+          name: "owner"
+          isMut: true
+          isSigner: true
         },
         {
-          name: "programNftAccount",
-          isMut: true,
-          isSigner: false,
+          name: "globalState"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "programNftMint",
-          isMut: false,
-          isSigner: false,
+          name: "market"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "adminNftAccount",
-          isMut: true,
-          isSigner: false,
+          name: "collection"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "prevListItem",
-          isMut: true,
-          isSigner: false,
+          name: "priceModel"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "nextListItem",
-          isMut: true,
-          isSigner: false,
+          name: "mysteryCommit"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "associatedTokenProgram",
-          isMut: false,
-          isSigner: false,
+          name: "debitMint"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "tokenProgram",
-          isMut: false,
-          isSigner: false,
+          name: "debitAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "systemProgram",
-          isMut: false,
-          isSigner: false,
+          name: "programCreditAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "rent",
-          isMut: false,
-          isSigner: false,
+          name: "feeRecipient"
+          isMut: false
+          isSigner: false
         },
-      ],
-      args: [],
-    },
-    {
-      name: "purchaseNft",
-      accounts: [
         {
-          name: "owner",
-          isMut: false,
-          isSigner: true,
+          name: "feeCreditAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "rentRefund",
-          isMut: true,
-          isSigner: false,
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "priceModel",
-          isMut: false,
-          isSigner: false,
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "market",
-          isMut: false,
-          isSigner: false,
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "collection",
-          isMut: false,
-          isSigner: false,
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "revealRandomPurchase"
+      accounts: [
+        {
+          name: "cranker"
+          isMut: true
+          isSigner: true
         },
         {
-          name: "purchaseListItem",
-          isMut: true,
-          isSigner: false,
+          name: "owner"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "debitMint",
-          isMut: false,
-          isSigner: false,
+          name: "rentRefund"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "debitAccount",
-          isMut: true,
-          isSigner: false,
+          name: "priceModel"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "programCreditAccount",
-          isMut: true,
-          isSigner: false,
+          name: "market"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "programNftAccount",
-          isMut: true,
-          isSigner: false,
+          name: "collection"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "programNftMint",
-          isMut: false,
-          isSigner: false,
+          name: "mysteryCommit"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "ownerNftAccount",
-          isMut: true,
-          isSigner: false,
+          name: "purchaseListItem"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "prevListItem",
-          isMut: true,
-          isSigner: false,
+          name: "programNftAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "nextListItem",
-          isMut: true,
-          isSigner: false,
+          name: "programNftMint"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "associatedTokenProgram",
-          isMut: false,
-          isSigner: false,
+          name: "ownerNftAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "tokenProgram",
-          isMut: false,
-          isSigner: false,
+          name: "slotHashes"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "systemProgram",
-          isMut: false,
-          isSigner: false,
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "rent",
-          isMut: false,
-          isSigner: false,
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
         },
-      ],
-      args: [],
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
     },
     {
-      name: "withdrawLiquidity",
+      name: "refundRandomPurchase"
       accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "owner"
+          isMut: true
+          isSigner: true
         },
         {
-          name: "globalState",
-          isMut: false,
-          isSigner: false,
+          name: "market"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "market",
-          isMut: false,
-          isSigner: false,
+          name: "collection"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "withdrawMint",
-          isMut: false,
-          isSigner: false,
+          name: "mysteryCommit"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "programDebitAccount",
-          isMut: true,
-          isSigner: false,
+          name: "debitAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "adminCreditAccount",
-          isMut: true,
-          isSigner: false,
+          name: "programCreditAccount"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "associatedTokenProgram",
-          isMut: false,
-          isSigner: false,
+          name: "slotHashes"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "tokenProgram",
-          isMut: false,
-          isSigner: false,
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "expireRandomPurchase"
+      accounts: [
+        {
+          name: "owner"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "systemProgram",
-          isMut: false,
-          isSigner: false,
+          name: "market"
+          isMut: false
+          isSigner: false
         },
         {
-          name: "rent",
-          isMut: false,
-          isSigner: false,
+          name: "collection"
+          isMut: false
+          isSigner: false
         },
-      ],
+        {
+          name: "mysteryCommit"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "slotHashes"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "compactListItem"
+      accounts: [
+        {
+          name: "rentRefund"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "listItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "prevListItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "nextListItem"
+          isMut: true
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "withdrawLiquidity"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "globalState"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "withdrawMint"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "programDebitAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "adminCreditAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
       args: [
         {
-          name: "amount",
-          type: "u64",
+          name: "amount"
+          type: "u64"
+        }
+      ]
+    },
+    {
+      name: "withdrawCollectionLiquidity"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
         },
-      ],
+        {
+          name: "market"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "collection"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "withdrawMint"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "programDebitAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "adminCreditAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "amount"
+          type: "u64"
+        }
+      ]
     },
     {
-      name: "closeCollection",
+      name: "adoptNft"
       accounts: [
         {
-          name: "admin",
-          isMut: false,
-          isSigner: true,
+          name: "admin"
+          isMut: false
+          isSigner: true
         },
         {
-          name: "market",
-          isMut: false,
-          isSigner: false,
+          name: "market"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "rentRefund",
-          isMut: false,
-          isSigner: true,
+          name: "collection"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "collection",
-          isMut: true,
-          isSigner: false,
+          name: "prevListItem"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "listHead",
-          isMut: true,
-          isSigner: false,
+          name: "nextListItem"
+          isMut: true
+          isSigner: false
         },
         {
-          name: "listTail",
-          isMut: true,
-          isSigner: false,
+          name: "programNftAccount"
+          isMut: false
+          isSigner: false
         },
-      ],
-      args: [],
+        {
+          name: "nftMint"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "newItem"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "inventoryPage"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "priceModel"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "returnCollectionTokens"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "collection"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "mint"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "nftBucket"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "programDebitAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "recipient"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "recipientCreditAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "amount"
+          type: "u64"
+        }
+      ]
+    },
+    {
+      name: "returnMarketTokens"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "globalState"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "mint"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "programDebitAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "recipient"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "recipientCreditAccount"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "associatedTokenProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: [
+        {
+          name: "amount"
+          type: "u64"
+        }
+      ]
+    },
+    {
+      name: "trackMarketTreasury"
+      accounts: [
+        {
+          name: "globalState"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "treasury"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "closeCollection"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "rentRefund"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "collection"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "listHead"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "listTail"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "closeMarket"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "rentRefund"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "globalState"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "tokenProgram"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "migrateGlobalState"
+      accounts: [
+        {
+          name: "admin"
+          isMut: true
+          isSigner: true
+        },
+        {
+          name: "programData"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "globalState"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "migrateMarket"
+      accounts: [
+        {
+          name: "admin"
+          isMut: true
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "migrateCollection"
+      accounts: [
+        {
+          name: "admin"
+          isMut: true
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "collection"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "listHead"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "listTail"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "countLegacyBuckets"
+      accounts: [
+        {
+          name: "admin"
+          isMut: false
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "collection"
+          isMut: true
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "migratePriceModel"
+      accounts: [
+        {
+          name: "admin"
+          isMut: true
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "priceModel"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    },
+    {
+      name: "migrateNftBucket"
+      accounts: [
+        {
+          name: "admin"
+          isMut: true
+          isSigner: true
+        },
+        {
+          name: "market"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "collection"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "nftBucket"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "programNftAccount"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "linkedListItem"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "priceModel"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "inventoryPage"
+          isMut: true
+          isSigner: false
+        },
+        {
+          name: "systemProgram"
+          isMut: false
+          isSigner: false
+        },
+        {
+          name: "rent"
+          isMut: false
+          isSigner: false
+        }
+      ]
+      args: []
+    }
+  ]
+  accounts: [
+    {
+      name: "globalState"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "version"
+            type: "u8"
+          },
+          {
+            name: "nonce"
+            type: "u8"
+          },
+          {
+            name: "numMarkets"
+            type: "u32"
+          },
+          {
+            name: "authority"
+            type: "publicKey"
+          },
+          {
+            name: "feeBasisPoints"
+            type: "u16"
+          },
+          {
+            name: "feeRecipient"
+            type: "publicKey"
+          },
+          {
+            name: "marketCreation"
+            type: {
+              defined: "MarketCreation"
+            }
+          },
+          {
+            name: "marketCreationFee"
+            type: "u64"
+          },
+          {
+            name: "reserved"
+            type: {
+              array: [
+                "u8",
+                64
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "market"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "version"
+            type: "u8"
+          },
+          {
+            name: "nonce"
+            type: "u8"
+          },
+          {
+            name: "index"
+            type: "u32"
+          },
+          {
+            name: "whitelist"
+            type: {
+              array: [
+                "publicKey",
+                16
+              ]
+            }
+          },
+          {
+            name: "whitelistLen"
+            type: "u8"
+          },
+          {
+            name: "numCollections"
+            type: "u32"
+          },
+          {
+            name: "numPriceModels"
+            type: "u32"
+          },
+          {
+            name: "title"
+            type: {
+              array: [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            name: "titleLen"
+            type: "u8"
+          },
+          {
+            name: "paused"
+            type: "u8"
+          },
+          {
+            name: "numOpenCollections"
+            type: "u32"
+          },
+          {
+            name: "numOpenPriceModels"
+            type: "u32"
+          },
+          {
+            name: "metadata"
+            type: {
+              defined: "MarketMetadata"
+            }
+          },
+          {
+            name: "stats"
+            type: {
+              defined: "SalesStats"
+            }
+          },
+          {
+            name: "unpausedSlot"
+            type: "u64"
+          },
+          {
+            name: "numTreasuryAccounts"
+            type: "u32"
+          },
+          {
+            name: "reserved"
+            type: {
+              array: [
+                "u8",
+                116
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "collection"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "version"
+            type: "u8"
+          },
+          {
+            name: "nonce"
+            type: "u8"
+          },
+          {
+            name: "index"
+            type: "u32"
+          },
+          {
+            name: "listHead"
+            type: "publicKey"
+          },
+          {
+            name: "listTail"
+            type: "publicKey"
+          },
+          {
+            name: "title"
+            type: "string"
+          },
+          {
+            name: "paused"
+            type: "bool"
+          },
+          {
+            name: "metadata"
+            type: {
+              defined: "Metadata"
+            }
+          },
+          {
+            name: "numInventoryPages"
+            type: "u32"
+          },
+          {
+            name: "saleStart"
+            type: {
+              option: "i64"
+            }
+          },
+          {
+            name: "saleEnd"
+            type: {
+              option: "i64"
+            }
+          },
+          {
+            name: "mysteryPriceModel"
+            type: {
+              option: "publicKey"
+            }
+          },
+          {
+            name: "stats"
+            type: {
+              defined: "SalesStats"
+            }
+          },
+          {
+            name: "numTreasuryAccounts"
+            type: "u32"
+          },
+          {
+            name: "inventoryVersion"
+            type: "u64"
+          },
+          {
+            name: "pagesClosedVersion"
+            type: "u64"
+          },
+          {
+            name: "unpausedSlot"
+            type: "u64"
+          },
+          {
+            name: "legacyCursor"
+            type: "publicKey"
+          },
+          {
+            name: "numLegacyBuckets"
+            type: "u32"
+          }
+        ]
+      }
+    },
+    {
+      name: "nftBucket"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "version"
+            type: "u8"
+          },
+          {
+            name: "nonce"
+            type: "u8"
+          },
+          {
+            name: "nftMint"
+            type: "publicKey"
+          },
+          {
+            name: "priceModel"
+            type: "publicKey"
+          },
+          {
+            name: "prevListItem"
+            type: "publicKey"
+          },
+          {
+            name: "nextListItem"
+            type: "publicKey"
+          },
+          {
+            name: "payer"
+            type: "publicKey"
+          },
+          {
+            name: "inventoryPage"
+            type: "publicKey"
+          },
+          {
+            name: "inventorySlot"
+            type: "u32"
+          },
+          {
+            name: "tombstoned"
+            type: "u8"
+          },
+          {
+            name: "saleStart"
+            type: "i64"
+          },
+          {
+            name: "saleEnd"
+            type: "i64"
+          },
+          {
+            name: "expiresAt"
+            type: "i64"
+          },
+          {
+            name: "collection"
+            type: "publicKey"
+          },
+          {
+            name: "reserved"
+            type: {
+              array: [
+                "u8",
+                32
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "mysteryCommit"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "nonce"
+            type: "u8"
+          },
+          {
+            name: "owner"
+            type: "publicKey"
+          },
+          {
+            name: "collection"
+            type: "publicKey"
+          },
+          {
+            name: "commitSlot"
+            type: "u64"
+          },
+          {
+            name: "debitMint"
+            type: "publicKey"
+          },
+          {
+            name: "amount"
+            type: "u64"
+          },
+          {
+            name: "commitTimestamp"
+            type: "i64"
+          },
+          {
+            name: "inventoryVersion"
+            type: "u64"
+          }
+        ]
+      }
+    },
+    {
+      name: "inventoryPage"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "collection"
+            type: "publicKey"
+          },
+          {
+            name: "index"
+            type: "u32"
+          },
+          {
+            name: "nonce"
+            type: "u8"
+          },
+          {
+            name: "numOccupied"
+            type: "u32"
+          },
+          {
+            name: "freeHead"
+            type: "u32"
+          },
+          {
+            name: "slots"
+            type: {
+              array: [
+                {
+                  defined: "InventorySlot"
+                },
+                64
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "priceModel"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "version"
+            type: "u8"
+          },
+          {
+            name: "nonce"
+            type: "u8"
+          },
+          {
+            name: "index"
+            type: "u32"
+          },
+          {
+            name: "market"
+            type: "publicKey"
+          },
+          {
+            name: "salePrices"
+            type: {
+              array: [
+                {
+                  defined: "SalePrice"
+                },
+                8
+              ]
+            }
+          },
+          {
+            name: "numSalePrices"
+            type: "u8"
+          },
+          {
+            name: "numListings"
+            type: "u32"
+          },
+          {
+            name: "reserved"
+            type: {
+              array: [
+                "u8",
+                64
+              ]
+            }
+          }
+        ]
+      }
+    }
+  ]
+  types: [
+    {
+      name: "MarketMetadata"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "description"
+            type: {
+              array: [
+                "u8",
+                256
+              ]
+            }
+          },
+          {
+            name: "descriptionLen"
+            type: "u16"
+          },
+          {
+            name: "imageUri"
+            type: {
+              array: [
+                "u8",
+                256
+              ]
+            }
+          },
+          {
+            name: "imageUriLen"
+            type: "u16"
+          },
+          {
+            name: "externalUrl"
+            type: {
+              array: [
+                "u8",
+                256
+              ]
+            }
+          },
+          {
+            name: "externalUrlLen"
+            type: "u16"
+          },
+          {
+            name: "sortPriority"
+            type: "u32"
+          }
+        ]
+      }
+    },
+    {
+      name: "InventorySlot"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "nftBucket"
+            type: "publicKey"
+          },
+          {
+            name: "nftMint"
+            type: "publicKey"
+          },
+          {
+            name: "nextFree"
+            type: "u32"
+          },
+          {
+            name: "drawWindow"
+            type: {
+              defined: "DrawWindow"
+            }
+          },
+          {
+            name: "priorDrawWindow"
+            type: {
+              defined: "DrawWindow"
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "DrawWindow"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "start"
+            type: "i64"
+          },
+          {
+            name: "end"
+            type: "i64"
+          },
+          {
+            name: "version"
+            type: "u64"
+          }
+        ]
+      }
+    },
+    {
+      name: "Metadata"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "description"
+            type: "string"
+          },
+          {
+            name: "imageUri"
+            type: "string"
+          },
+          {
+            name: "externalUrl"
+            type: "string"
+          },
+          {
+            name: "sortPriority"
+            type: "u32"
+          }
+        ]
+      }
+    },
+    {
+      name: "SalesStats"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "numListed"
+            type: "u32"
+          },
+          {
+            name: "numSold"
+            type: "u32"
+          },
+          {
+            name: "volume"
+            type: {
+              array: [
+                {
+                  defined: "MintVolume"
+                },
+                8
+              ]
+            }
+          },
+          {
+            name: "numVolume"
+            type: "u8"
+          },
+          {
+            name: "lastSale"
+            type: {
+              defined: "LastSale"
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "MintVolume"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "mint"
+            type: "publicKey"
+          },
+          {
+            name: "amount"
+            type: "u64"
+          }
+        ]
+      }
+    },
+    {
+      name: "LastSale"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "nftMint"
+            type: "publicKey"
+          },
+          {
+            name: "debitMint"
+            type: "publicKey"
+          },
+          {
+            name: "amount"
+            type: "u64"
+          },
+          {
+            name: "buyer"
+            type: "publicKey"
+          },
+          {
+            name: "timestamp"
+            type: "i64"
+          }
+        ]
+      }
+    },
+    {
+      name: "SalePrice"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "mint"
+            type: "publicKey"
+          },
+          {
+            name: "amount"
+            type: "u64"
+          }
+        ]
+      }
+    },
+    {
+      name: "PriceQuote"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "mint"
+            type: "publicKey"
+          },
+          {
+            name: "amount"
+            type: "u64"
+          },
+          {
+            name: "protocolFee"
+            type: "u64"
+          },
+          {
+            name: "proceeds"
+            type: "u64"
+          }
+        ]
+      }
+    },
+    {
+      name: "ListPageData"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "items"
+            type: {
+              vec: {
+                defined: "ListPageItem"
+              }
+            }
+          },
+          {
+            name: "nextCursor"
+            type: {
+              option: "publicKey"
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "ListPageItem"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "nftBucket"
+            type: "publicKey"
+          },
+          {
+            name: "nftMint"
+            type: "publicKey"
+          }
+        ]
+      }
+    },
+    {
+      name: "ListVerification"
+      type: {
+        kind: "struct"
+        fields: [
+          {
+            name: "count"
+            type: "u32"
+          },
+          {
+            name: "numTombstoned"
+            type: "u32"
+          },
+          {
+            name: "inconsistency"
+            type: {
+              option: {
+                defined: "ListInconsistency"
+              }
+            }
+          },
+          {
+            name: "nextCursor"
+            type: {
+              option: "publicKey"
+            }
+          }
+        ]
+      }
+    },
+    {
+      name: "Draw"
+      type: {
+        kind: "enum"
+        variants: [
+          {
+            name: "Empty"
+          },
+          {
+            name: "Unknown"
+          },
+          {
+            name: "Slot"
+            fields: [
+              {
+                name: "page_position"
+                type: {
+                  defined: "usize"
+                }
+              },
+              {
+                name: "slot_index"
+                type: "u32"
+              },
+              {
+                name: "nft_bucket"
+                type: {
+                  option: "publicKey"
+                }
+              }
+            ]
+          }
+        ]
+      }
+    },
+    {
+      name: "MarketCreation"
+      type: {
+        kind: "enum"
+        variants: [
+          {
+            name: "Open"
+          },
+          {
+            name: "Permissioned"
+          },
+          {
+            name: "FeeGated"
+          }
+        ]
+      }
+    },
+    {
+      name: "ListInconsistency"
+      type: {
+        kind: "enum"
+        variants: [
+          {
+            name: "InvalidItem"
+            fields: [
+              {
+                name: "list_item"
+                type: "publicKey"
+              }
+            ]
+          },
+          {
+            name: "BrokenPrevLink"
+            fields: [
+              {
+                name: "list_item"
+                type: "publicKey"
+              },
+              {
+                name: "expected"
+                type: "publicKey"
+              },
+              {
+                name: "found"
+                type: "publicKey"
+              }
+            ]
+          }
+        ]
+      }
+    }
+  ]
+  events: [
+    {
+      name: "NftAdopted"
+      fields: [
+        {
+          name: "admin"
+          type: "publicKey"
+          index: false
+        },
+        {
+          name: "market"
+          type: "publicKey"
+          index: false
+        },
+        {
+          name: "collection"
+          type: "publicKey"
+          index: false
+        },
+        {
+          name: "nftMint"
+          type: "publicKey"
+          index: false
+        },
+        {
+          name: "nftBucket"
+          type: "publicKey"
+          index: false
+        }
+      ]
+    },
+    {
+      name: "TokensReturned"
+      fields: [
+        {
+          name: "admin"
+          type: "publicKey"
+          index: false
+        },
+        {
+          name: "market"
+          type: "publicKey"
+          index: false
+        },
+        {
+          name: "collection"
+          type: {
+            option: "publicKey"
+          }
+          index: false
+        },
+        {
+          name: "mint"
+          type: "publicKey"
+          index: false
+        },
+        {
+          name: "amount"
+          type: "u64"
+          index: false
+        },
+        {
+          name: "recipient"
+          type: "publicKey"
+          index: false
+        }
+      ]
+    }
+  ]
+  errors: [
+    {
+      code: 300
+      name: "Unauthorized"
+      msg: "Instruction invoked without a valid admin."
+    },
+    {
+      code: 301
+      name: "CannotRemoveSelf"
+      msg: "Signing account cannot remove itself."
+    },
+    {
+      code: 302
+      name: "InvalidPurchaseMint"
+      msg: "Cannot purchase from selected collection with specified Mint."
+    },
+    {
+      code: 303
+      name: "Overflow"
+      msg: "Overflow when applying an arithmetic operation."
+    },
+    {
+      code: 304
+      name: "AdminNotFound"
+      msg: "Admin address was not found in market whitelist."
+    },
+    {
+      code: 305
+      name: "AdminOutOfBounds"
+      msg: "Admin whitelist exceeded max length of 16."
+    },
+    {
+      code: 306
+      name: "CollectionNonEmpty"
+      msg: "Collections cannot be closed until all NFTs are removed."
+    },
+    {
+      code: 307
+      name: "InvalidFeeBasisPoints"
+      msg: "Fee basis points cannot exceed 10000."
+    },
+    {
+      code: 308
+      name: "InvalidProgramData"
+      msg: "Program data account does not match the deployed program."
+    },
+    {
+      code: 309
+      name: "Paused"
+      msg: "Market or collection is paused."
+    },
+    {
+      code: 310
+      name: "PriceModelInUse"
+      msg: "Price models cannot be closed while NFTs are listed with them."
+    },
+    {
+      code: 311
+      name: "MarketNonEmpty"
+      msg: "Markets cannot be closed until all collections and price models are closed."
+    },
+    {
+      code: 312
+      name: "InvalidTreasury"
+      msg: "Treasury account is not owned by the closing account."
+    },
+    {
+      code: 313
+      name: "TreasuryNonEmpty"
+      msg: "Treasury accounts must be emptied before closing."
+    },
+    {
+      code: 314
+      name: "TitleTooLong"
+      msg: "Title exceeded max length of 32."
+    },
+    {
+      code: 315
+      name: "DescriptionTooLong"
+      msg: "Description exceeded max length of 256."
+    },
+    {
+      code: 316
+      name: "UriTooLong"
+      msg: "URI exceeded max length of 200."
+    },
+    {
+      code: 317
+      name: "InvalidInventoryPage"
+      msg: "Inventory page does not belong to the collection."
+    },
+    {
+      code: 318
+      name: "InventoryPageFull"
+      msg: "Inventory page has no free slots."
+    },
+    {
+      code: 319
+      name: "InvalidInventorySlot"
+      msg: "Inventory slot does not hold the NFT bucket."
+    },
+    {
+      code: 320
+      name: "InventoryPageNonEmpty"
+      msg: "Inventory pages cannot be closed until all NFTs are removed."
+    },
+    {
+      code: 321
+      name: "ListItemTombstoned"
+      msg: "List item has already been purchased or withdrawn."
+    },
+    {
+      code: 322
+      name: "ListItemNotTombstoned"
+      msg: "Only purchased or withdrawn list items can be compacted."
+    },
+    {
+      code: 323
+      name: "InvalidListItem"
+      msg: "List item does not belong to the collection or cannot be linked there."
+    },
+    {
+      code: 324
+      name: "InvalidMove"
+      msg: "List item is already at the requested position."
+    },
+    {
+      code: 325
+      name: "SaleNotStarted"
+      msg: "Sale has not started yet."
+    },
+    {
+      code: 326
+      name: "SaleEnded"
+      msg: "Sale has ended."
+    },
+    {
+      code: 327
+      name: "InvalidSaleWindow"
+      msg: "Sale start must be before sale end."
+    },
+    {
+      code: 328
+      name: "ListingExpired"
+      msg: "Listing has expired."
+    },
+    {
+      code: 329
+      name: "ListingNotExpired"
+      msg: "Listing has not expired."
+    },
+    {
+      code: 330
+      name: "MysteryBoxDisabled"
+      msg: "Collection does not offer mystery box purchases with this price model."
+    },
+    {
+      code: 331
+      name: "RevealTooEarly"
+      msg: "Purchase commitment must be revealed in a later slot."
+    },
+    {
+      code: 332
+      name: "RevealWindowElapsed"
+      msg: "Purchase commitment can no longer be revealed."
+    },
+    {
+      code: 333
+      name: "InvalidRandomSelection"
+      msg: "List item does not match the random selection."
+    },
+    {
+      code: 334
+      name: "RefundUnavailable"
+      msg: "Purchase can only be refunded once its draw can't be delivered."
+    },
+    {
+      code: 335
+      name: "SalePricesOutOfBounds"
+      msg: "Price model exceeded max length of 8 sale prices."
+    },
+    {
+      code: 336
+      name: "InvalidAccountVersion"
+      msg: "Account is not at a version that can be migrated."
+    },
+    {
+      code: 337
+      name: "InvalidMigrationAccount"
+      msg: "Account does not belong to the account being migrated."
+    },
+    {
+      code: 338
+      name: "InvalidRepair"
+      msg: "List items cannot be linked in this order."
+    },
+    {
+      code: 339
+      name: "ListLinkIntact"
+      msg: "List items are already linked."
+    },
+    {
+      code: 340
+      name: "InvalidRescueAmount"
+      msg: "Account does not hold the amount to rescue."
+    },
+    {
+      code: 341
+      name: "InvalidFeeAccount"
+      msg: "Fee account is not the fee recipient's associated token account."
+    },
+    {
+      code: 342
+      name: "RevealWindowOpen"
+      msg: "Purchase commitment can still be revealed."
+    },
+    {
+      code: 343
+      name: "InventoryEmpty"
+      msg: "Nothing could be drawn, the purchase can be refunded."
+    },
+    {
+      code: 344
+      name: "TreasuryNotClosed"
+      msg: "Every tracked treasury account must be closed with its collection or market."
+    },
+    {
+      code: 345
+      name: "DrawVoided"
+      msg: "Draw can no longer be delivered, the purchase can be refunded."
+    },
+    {
+      code: 346
+      name: "LegacyBucketsPending"
+      msg: "Collection has legacy buckets left to count or migrate."
+    }
+  ]
+}
+
+export const IDL: VibeMarket = {
+  version: "0.0.0",
+  name: "vibe_market",
+  instructions: [
+    {
+      name: "initGlobalState",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "programData",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "globalState",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "updateProtocolFee",
+      accounts: [
+        {
+          name: "authority",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "globalState",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "feeRecipient",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "feeBasisPoints",
+          type: "u16",
+        },
+      ],
+    },
+    {
+      name: "setGlobalAuthority",
+      accounts: [
+        {
+          name: "authority",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "globalState",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "newAuthority",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "updateMarketCreation",
+      accounts: [
+        {
+          name: "authority",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "globalState",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "marketCreation",
+          type: {
+            defined: "MarketCreation",
+          },
+        },
+        {
+          name: "marketCreationFee",
+          type: "u64",
+        },
+      ],
+    },
+    {
+      name: "initMarket",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "globalState",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "feeRecipient",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "whitelist",
+          type: {
+            vec: "publicKey",
+          },
+        },
+        {
+          name: "title",
+          type: "string",
+        },
+      ],
+    },
+    {
+      name: "addAdmin",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "addAdmin",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "removeAdmin",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "removeAdmin",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "setMarketPaused",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "paused",
+          type: "bool",
+        },
+      ],
+    },
+    {
+      name: "updateMarketMetadata",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "title",
+          type: "string",
+        },
+        {
+          name: "metadata",
+          type: {
+            defined: "Metadata",
+          },
+        },
+      ],
+    },
+    {
+      name: "initCollection",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listHead",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listTail",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "title",
+          type: "string",
+        },
+      ],
+    },
+    {
+      name: "setCollectionPaused",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "paused",
+          type: "bool",
+        },
+      ],
+    },
+    {
+      name: "setCollectionSaleWindow",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "saleStart",
+          type: {
+            option: "i64",
+          },
+        },
+        {
+          name: "saleEnd",
+          type: {
+            option: "i64",
+          },
+        },
+      ],
+    },
+    {
+      name: "setNftSaleWindow",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "saleStart",
+          type: {
+            option: "i64",
+          },
+        },
+        {
+          name: "saleEnd",
+          type: {
+            option: "i64",
+          },
+        },
+      ],
+    },
+    {
+      name: "setNftExpiry",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "expiresAt",
+          type: {
+            option: "i64",
+          },
+        },
+      ],
+    },
+    {
+      name: "setMysteryPriceModel",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "enabled",
+          type: "bool",
+        },
+      ],
+    },
+    {
+      name: "updateCollectionMetadata",
+      accounts: [
+        {
+          name: "admin",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "title",
+          type: "string",
+        },
+        {
+          name: "metadata",
+          type: {
+            defined: "Metadata",
+          },
+        },
+      ],
+    },
+    {
+      name: "initInventoryPage",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "closeInventoryPage",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "initPriceModel",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "salePrices",
+          type: {
+            vec: {
+              defined: "SalePrice",
+            },
+          },
+        },
+      ],
+    },
+    {
+      name: "closePriceModel",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "addNft",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "prevListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "nextListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "adminNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "adminNftMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "newItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "insertNftAfter",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "prevListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "nextListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "adminNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "adminNftMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "newItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "moveItem",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "listItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "prevListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "nextListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "newPrevListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "newNextListItem",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "listPage",
+      accounts: [
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "cursor",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "limit",
+          type: "u8",
+        },
+      ],
+    },
+    {
+      name: "verifyCollection",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "cursor",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "repairLink",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "prevListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "nextListItem",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "withdrawNft",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "rentRefund",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "withdrawListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "adminNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "quotePurchase",
+      accounts: [
+        {
+          name: "globalState",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "listItem",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "purchaseNft",
+      accounts: [
+        {
+          name: "owner",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "rentRefund",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "globalState",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "purchaseListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "debitMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "debitAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "feeRecipient",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "feeCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "ownerNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "crankExpired",
+      accounts: [
+        {
+          name: "cranker",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "rentRefund",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "expiredListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "prevListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "nextListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "payerNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "commitRandomPurchase",
+      accounts: [
+        {
+          name: "owner",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "globalState",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "mysteryCommit",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "debitMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "debitAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "feeRecipient",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "feeCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "revealRandomPurchase",
+      accounts: [
+        {
+          name: "cranker",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "owner",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "rentRefund",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "mysteryCommit",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "purchaseListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "ownerNftAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "slotHashes",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "refundRandomPurchase",
+      accounts: [
+        {
+          name: "owner",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "mysteryCommit",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "debitAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "slotHashes",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "expireRandomPurchase",
+      accounts: [
+        {
+          name: "owner",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "mysteryCommit",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "slotHashes",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "compactListItem",
+      accounts: [
+        {
+          name: "rentRefund",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "prevListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "nextListItem",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "withdrawLiquidity",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "globalState",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "withdrawMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "programDebitAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "adminCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "amount",
+          type: "u64",
+        },
+      ],
+    },
+    {
+      name: "withdrawCollectionLiquidity",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "withdrawMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "programDebitAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "adminCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "amount",
+          type: "u64",
+        },
+      ],
+    },
+    {
+      name: "adoptNft",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "prevListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "nextListItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftAccount",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "nftMint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "newItem",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "returnCollectionTokens",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "mint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "nftBucket",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "programDebitAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "recipient",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "recipientCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "amount",
+          type: "u64",
+        },
+      ],
+    },
+    {
+      name: "returnMarketTokens",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "globalState",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "mint",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "programDebitAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "recipient",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "recipientCreditAccount",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "associatedTokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [
+        {
+          name: "amount",
+          type: "u64",
+        },
+      ],
+    },
+    {
+      name: "trackMarketTreasury",
+      accounts: [
+        {
+          name: "globalState",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "treasury",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "closeCollection",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "rentRefund",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listHead",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listTail",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "closeMarket",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "rentRefund",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "globalState",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "tokenProgram",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "migrateGlobalState",
+      accounts: [
+        {
+          name: "admin",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "programData",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "globalState",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "migrateMarket",
+      accounts: [
+        {
+          name: "admin",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "migrateCollection",
+      accounts: [
+        {
+          name: "admin",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listHead",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "listTail",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "countLegacyBuckets",
+      accounts: [
+        {
+          name: "admin",
+          isMut: false,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "migratePriceModel",
+      accounts: [
+        {
+          name: "admin",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+    {
+      name: "migrateNftBucket",
+      accounts: [
+        {
+          name: "admin",
+          isMut: true,
+          isSigner: true,
+        },
+        {
+          name: "market",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "collection",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "nftBucket",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "programNftAccount",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "linkedListItem",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "priceModel",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "inventoryPage",
+          isMut: true,
+          isSigner: false,
+        },
+        {
+          name: "systemProgram",
+          isMut: false,
+          isSigner: false,
+        },
+        {
+          name: "rent",
+          isMut: false,
+          isSigner: false,
+        },
+      ],
+      args: [],
+    },
+  ],
+  accounts: [
+    {
+      name: "globalState",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "version",
+            type: "u8",
+          },
+          {
+            name: "nonce",
+            type: "u8",
+          },
+          {
+            name: "numMarkets",
+            type: "u32",
+          },
+          {
+            name: "authority",
+            type: "publicKey",
+          },
+          {
+            name: "feeBasisPoints",
+            type: "u16",
+          },
+          {
+            name: "feeRecipient",
+            type: "publicKey",
+          },
+          {
+            name: "marketCreation",
+            type: {
+              defined: "MarketCreation",
+            },
+          },
+          {
+            name: "marketCreationFee",
+            type: "u64",
+          },
+          {
+            name: "reserved",
+            type: {
+              array: [
+                "u8",
+                64,
+              ],
+            },
+          },
+        ],
+      },
+    },
+    {
+      name: "market",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "version",
+            type: "u8",
+          },
+          {
+            name: "nonce",
+            type: "u8",
+          },
+          {
+            name: "index",
+            type: "u32",
+          },
+          {
+            name: "whitelist",
+            type: {
+              array: [
+                "publicKey",
+                16,
+              ],
+            },
+          },
+          {
+            name: "whitelistLen",
+            type: "u8",
+          },
+          {
+            name: "numCollections",
+            type: "u32",
+          },
+          {
+            name: "numPriceModels",
+            type: "u32",
+          },
+          {
+            name: "title",
+            type: {
+              array: [
+                "u8",
+                32,
+              ],
+            },
+          },
+          {
+            name: "titleLen",
+            type: "u8",
+          },
+          {
+            name: "paused",
+            type: "u8",
+          },
+          {
+            name: "numOpenCollections",
+            type: "u32",
+          },
+          {
+            name: "numOpenPriceModels",
+            type: "u32",
+          },
+          {
+            name: "metadata",
+            type: {
+              defined: "MarketMetadata",
+            },
+          },
+          {
+            name: "stats",
+            type: {
+              defined: "SalesStats",
+            },
+          },
+          {
+            name: "unpausedSlot",
+            type: "u64",
+          },
+          {
+            name: "numTreasuryAccounts",
+            type: "u32",
+          },
+          {
+            name: "reserved",
+            type: {
+              array: [
+                "u8",
+                116,
+              ],
+            },
+          },
+        ],
+      },
+    },
+    {
+      name: "collection",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "version",
+            type: "u8",
+          },
+          {
+            name: "nonce",
+            type: "u8",
+          },
+          {
+            name: "index",
+            type: "u32",
+          },
+          {
+            name: "listHead",
+            type: "publicKey",
+          },
+          {
+            name: "listTail",
+            type: "publicKey",
+          },
+          {
+            name: "title",
+            type: "string",
+          },
+          {
+            name: "paused",
+            type: "bool",
+          },
+          {
+            name: "metadata",
+            type: {
+              defined: "Metadata",
+            },
+          },
+          {
+            name: "numInventoryPages",
+            type: "u32",
+          },
+          {
+            name: "saleStart",
+            type: {
+              option: "i64",
+            },
+          },
+          {
+            name: "saleEnd",
+            type: {
+              option: "i64",
+            },
+          },
+          {
+            name: "mysteryPriceModel",
+            type: {
+              option: "publicKey",
+            },
+          },
+          {
+            name: "stats",
+            type: {
+              defined: "SalesStats",
+            },
+          },
+          {
+            name: "numTreasuryAccounts",
+            type: "u32",
+          },
+          {
+            name: "inventoryVersion",
+            type: "u64",
+          },
+          {
+            name: "pagesClosedVersion",
+            type: "u64",
+          },
+          {
+            name: "unpausedSlot",
+            type: "u64",
+          },
+          {
+            name: "legacyCursor",
+            type: "publicKey",
+          },
+          {
+            name: "numLegacyBuckets",
+            type: "u32",
+          },
+        ],
+      },
+    },
+    {
+      name: "nftBucket",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "version",
+            type: "u8",
+          },
+          {
+            name: "nonce",
+            type: "u8",
+          },
+          {
+            name: "nftMint",
+            type: "publicKey",
+          },
+          {
+            name: "priceModel",
+            type: "publicKey",
+          },
+          {
+            name: "prevListItem",
+            type: "publicKey",
+          },
+          {
+            name: "nextListItem",
+            type: "publicKey",
+          },
+          {
+            name: "payer",
+            type: "publicKey",
+          },
+          {
+            name: "inventoryPage",
+            type: "publicKey",
+          },
+          {
+            name: "inventorySlot",
+            type: "u32",
+          },
+          {
+            name: "tombstoned",
+            type: "u8",
+          },
+          {
+            name: "saleStart",
+            type: "i64",
+          },
+          {
+            name: "saleEnd",
+            type: "i64",
+          },
+          {
+            name: "expiresAt",
+            type: "i64",
+          },
+          {
+            name: "collection",
+            type: "publicKey",
+          },
+          {
+            name: "reserved",
+            type: {
+              array: [
+                "u8",
+                32,
+              ],
+            },
+          },
+        ],
+      },
+    },
+    {
+      name: "mysteryCommit",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "nonce",
+            type: "u8",
+          },
+          {
+            name: "owner",
+            type: "publicKey",
+          },
+          {
+            name: "collection",
+            type: "publicKey",
+          },
+          {
+            name: "commitSlot",
+            type: "u64",
+          },
+          {
+            name: "debitMint",
+            type: "publicKey",
+          },
+          {
+            name: "amount",
+            type: "u64",
+          },
+          {
+            name: "commitTimestamp",
+            type: "i64",
+          },
+          {
+            name: "inventoryVersion",
+            type: "u64",
+          },
+        ],
+      },
+    },
+    {
+      name: "inventoryPage",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "collection",
+            type: "publicKey",
+          },
+          {
+            name: "index",
+            type: "u32",
+          },
+          {
+            name: "nonce",
+            type: "u8",
+          },
+          {
+            name: "numOccupied",
+            type: "u32",
+          },
+          {
+            name: "freeHead",
+            type: "u32",
+          },
+          {
+            name: "slots",
+            type: {
+              array: [
+                {
+                  defined: "InventorySlot",
+                },
+                64,
+              ],
+            },
+          },
+        ],
+      },
+    },
+    {
+      name: "priceModel",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "version",
+            type: "u8",
+          },
+          {
+            name: "nonce",
+            type: "u8",
+          },
+          {
+            name: "index",
+            type: "u32",
+          },
+          {
+            name: "market",
+            type: "publicKey",
+          },
+          {
+            name: "salePrices",
+            type: {
+              array: [
+                {
+                  defined: "SalePrice",
+                },
+                8,
+              ],
+            },
+          },
+          {
+            name: "numSalePrices",
+            type: "u8",
+          },
+          {
+            name: "numListings",
+            type: "u32",
+          },
+          {
+            name: "reserved",
+            type: {
+              array: [
+                "u8",
+                64,
+              ],
+            },
+          },
+        ],
+      },
+    },
+  ],
+  types: [
+    {
+      name: "MarketMetadata",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "description",
+            type: {
+              array: [
+                "u8",
+                256,
+              ],
+            },
+          },
+          {
+            name: "descriptionLen",
+            type: "u16",
+          },
+          {
+            name: "imageUri",
+            type: {
+              array: [
+                "u8",
+                256,
+              ],
+            },
+          },
+          {
+            name: "imageUriLen",
+            type: "u16",
+          },
+          {
+            name: "externalUrl",
+            type: {
+              array: [
+                "u8",
+                256,
+              ],
+            },
+          },
+          {
+            name: "externalUrlLen",
+            type: "u16",
+          },
+          {
+            name: "sortPriority",
+            type: "u32",
+          },
+        ],
+      },
+    },
+    {
+      name: "InventorySlot",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "nftBucket",
+            type: "publicKey",
+          },
+          {
+            name: "nftMint",
+            type: "publicKey",
+          },
+          {
+            name: "nextFree",
+            type: "u32",
+          },
+          {
+            name: "drawWindow",
+            type: {
+              defined: "DrawWindow",
+            },
+          },
+          {
+            name: "priorDrawWindow",
+            type: {
+              defined: "DrawWindow",
+            },
+          },
+        ],
+      },
+    },
+    {
+      name: "DrawWindow",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "start",
+            type: "i64",
+          },
+          {
+            name: "end",
+            type: "i64",
+          },
+          {
+            name: "version",
+            type: "u64",
+          },
+        ],
+      },
+    },
+    {
+      name: "Metadata",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "description",
+            type: "string",
+          },
+          {
+            name: "imageUri",
+            type: "string",
+          },
+          {
+            name: "externalUrl",
+            type: "string",
+          },
+          {
+            name: "sortPriority",
+            type: "u32",
+          },
+        ],
+      },
+    },
+    {
+      name: "SalesStats",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "numListed",
+            type: "u32",
+          },
+          {
+            name: "numSold",
+            type: "u32",
+          },
+          {
+            name: "volume",
+            type: {
+              array: [
+                {
+                  defined: "MintVolume",
+                },
+                8,
+              ],
+            },
+          },
+          {
+            name: "numVolume",
+            type: "u8",
+          },
+          {
+            name: "lastSale",
+            type: {
+              defined: "LastSale",
+            },
+          },
+        ],
+      },
+    },
+    {
+      name: "MintVolume",
+      type: {
+        kind: "struct",
+        fields: [
+          {
+            name: "mint",
+            type: "publicKey",
+          },
+          {
+            name: "amount",
+            type: "u64",
+          },
+        ],
+      },
     },
-  ],
-  accounts: [
     {
-      name: "globalState",
+      name: "LastSale",
       type: {
         kind: "struct",
         fields: [
           {
-            name: "nonce",
-            type: "u8",
+            name: "nftMint",
+            type: "publicKey",
           },
           {
-            name: "numMarkets",
-            type: "u32",
+            name: "debitMint",
+            type: "publicKey",
+          },
+          {
+            name: "amount",
+            type: "u64",
+          },
+          {
+            name: "buyer",
+            type: "publicKey",
+          },
+          {
+            name: "timestamp",
+            type: "i64",
           },
         ],
       },
     },
     {
-      name: "market",
+      name: "SalePrice",
       type: {
         kind: "struct",
         fields: [
           {
-            name: "nonce",
-            type: "u8",
+            name: "mint",
+            type: "publicKey",
           },
           {
-            name: "index",
-            type: "u32",
+            name: "amount",
+            type: "u64",
           },
+        ],
+      },
+    },
+    {
+      name: "PriceQuote",
+      type: {
+        kind: "struct",
+        fields: [
           {
-            name: "whitelist",
-            type: {
-              vec: "publicKey",
-            },
+            name: "mint",
+            type: "publicKey",
           },
           {
-            name: "numCollections",
-            type: "u32",
+            name: "amount",
+            type: "u64",
           },
           {
-            name: "numPriceModels",
-            type: "u32",
+            name: "protocolFee",
+            type: "u64",
           },
           {
-            name: "title",
-            type: "string",
+            name: "proceeds",
+            type: "u64",
           },
         ],
       },
     },
     {
-      name: "collection",
+      name: "ListPageData",
       type: {
         kind: "struct",
         fields: [
           {
-            name: "nonce",
-            type: "u8",
+            name: "items",
+            type: {
+              vec: {
+                defined: "ListPageItem",
+              },
+            },
           },
           {
-            name: "index",
-            type: "u32",
+            name: "nextCursor",
+            type: {
+              option: "publicKey",
+            },
           },
+        ],
+      },
+    },
+    {
+      name: "ListPageItem",
+      type: {
+        kind: "struct",
+        fields: [
           {
-            name: "listHead",
+            name: "nftBucket",
             type: "publicKey",
           },
           {
-            name: "listTail",
+            name: "nftMint",
             type: "publicKey",
           },
-          {
-            name: "title",
-            type: "string",
-          },
         ],
       },
     },
     {
-      name: "nftBucket",
+      name: "ListVerification",
       type: {
         kind: "struct",
         fields: [
           {
-            name: "nonce",
-            type: "u8",
+            name: "count",
+            type: "u32",
           },
           {
-            name: "nftMint",
-            type: "publicKey",
+            name: "numTombstoned",
+            type: "u32",
           },
           {
-            name: "priceModel",
-            type: "publicKey",
+            name: "inconsistency",
+            type: {
+              option: {
+                defined: "ListInconsistency",
+              },
+            },
           },
           {
-            name: "prevListItem",
-            type: "publicKey",
+            name: "nextCursor",
+            type: {
+              option: "publicKey",
+            },
+          },
+        ],
+      },
+    },
+    {
+      name: "Draw",
+      type: {
+        kind: "enum",
+        variants: [
+          {
+            name: "Empty",
           },
           {
-            name: "nextListItem",
-            type: "publicKey",
+            name: "Unknown",
           },
           {
-            name: "payer",
-            type: "publicKey",
+            name: "Slot",
+            fields: [
+              {
+                name: "page_position",
+                type: {
+                  defined: "usize",
+                },
+              },
+              {
+                name: "slot_index",
+                type: "u32",
+              },
+              {
+                name: "nft_bucket",
+                type: {
+                  option: "publicKey",
+                },
+              },
+            ],
           },
         ],
       },
     },
     {
-      name: "priceModel",
+      name: "MarketCreation",
       type: {
-        kind: "struct",
-        fields: [
-          {
-            name: "nonce",
-            type: "u8",
-          },
+        kind: "enum",
+        variants: [
           {
-            name: "index",
-            type: "u32",
+            name: "Open",
           },
           {
-            name: "market",
-            type: "publicKey",
+            name: "Permissioned",
           },
           {
-            name: "salePrices",
-            type: {
-              vec: {
-                defined: "SalePrice",
-              },
-            },
+            name: "FeeGated",
           },
         ],
       },
     },
-  ],
-  types: [
     {
-      name: "SalePrice",
+      name: "ListInconsistency",
       type: {
-        kind: "struct",
-        fields: [
+        kind: "enum",
+        variants: [
           {
-            name: "mint",
-            type: "publicKey",
+            name: "InvalidItem",
+            fields: [
+              {
+                name: "list_item",
+                type: "publicKey",
+              },
+            ],
           },
           {
-            name: "amount",
-            type: "u64",
+            name: "BrokenPrevLink",
+            fields: [
+              {
+                name: "list_item",
+                type: "publicKey",
+              },
+              {
+                name: "expected",
+                type: "publicKey",
+              },
+              {
+                name: "found",
+                type: "publicKey",
+              },
+            ],
           },
         ],
       },
     },
   ],
+  events: [
+    {
+      name: "NftAdopted",
+      fields: [
+        {
+          name: "admin",
+          type: "publicKey",
+          index: false,
+        },
+        {
+          name: "market",
+          type: "publicKey",
+          index: false,
+        },
+        {
+          name: "collection",
+          type: "publicKey",
+          index: false,
+        },
+        {
+          name: "nftMint",
+          type: "publicKey",
+          index: false,
+        },
+        {
+          name: "nftBucket",
+          type: "publicKey",
+          index: false,
+        },
+      ],
+    },
+    {
+      name: "TokensReturned",
+      fields: [
+        {
+          name: "admin",
+          type: "publicKey",
+          index: false,
+        },
+        {
+          name: "market",
+          type: "publicKey",
+          index: false,
+        },
+        {
+          name: "collection",
+          type: {
+            option: "publicKey",
+          },
+          index: false,
+        },
+        {
+          name: "mint",
+          type: "publicKey",
+          index: false,
+        },
+        {
+          name: "amount",
+          type: "u64",
+          index: false,
+        },
+        {
+          name: "recipient",
+          type: "publicKey",
+          index: false,
+        },
+      ],
+    },
+  ],
   errors: [
     {
       code: 300,
@@ -1501,5 +6291,205 @@ export const IDL: VibeMarket = {
       name: "CollectionNonEmpty",
       msg: "Collections cannot be closed until all NFTs are removed.",
     },
+    {
+      code: 307,
+      name: "InvalidFeeBasisPoints",
+      msg: "Fee basis points cannot exceed 10000.",
+    },
+    {
+      code: 308,
+      name: "InvalidProgramData",
+      msg: "Program data account does not match the deployed program.",
+    },
+    {
+      code: 309,
+      name: "Paused",
+      msg: "Market or collection is paused.",
+    },
+    {
+      code: 310,
+      name: "PriceModelInUse",
+      msg: "Price models cannot be closed while NFTs are listed with them.",
+    },
+    {
+      code: 311,
+      name: "MarketNonEmpty",
+      msg: "Markets cannot be closed until all collections and price models are closed.",
+    },
+    {
+      code: 312,
+      name: "InvalidTreasury",
+      msg: "Treasury account is not owned by the closing account.",
+    },
+    {
+      code: 313,
+      name: "TreasuryNonEmpty",
+      msg: "Treasury accounts must be emptied before closing.",
+    },
+    {
+      code: 314,
+      name: "TitleTooLong",
+      msg: "Title exceeded max length of 32.",
+    },
+    {
+      code: 315,
+      name: "DescriptionTooLong",
+      msg: "Description exceeded max length of 256.",
+    },
+    {
+      code: 316,
+      name: "UriTooLong",
+      msg: "URI exceeded max length of 200.",
+    },
+    {
+      code: 317,
+      name: "InvalidInventoryPage",
+      msg: "Inventory page does not belong to the collection.",
+    },
+    {
+      code: 318,
+      name: "InventoryPageFull",
+      msg: "Inventory page has no free slots.",
+    },
+    {
+      code: 319,
+      name: "InvalidInventorySlot",
+      msg: "Inventory slot does not hold the NFT bucket.",
+    },
+    {
+      code: 320,
+      name: "InventoryPageNonEmpty",
+      msg: "Inventory pages cannot be closed until all NFTs are removed.",
+    },
+    {
+      code: 321,
+      name: "ListItemTombstoned",
+      msg: "List item has already been purchased or withdrawn.",
+    },
+    {
+      code: 322,
+      name: "ListItemNotTombstoned",
+      msg: "Only purchased or withdrawn list items can be compacted.",
+    },
+    {
+      code: 323,
+      name: "InvalidListItem",
+      msg: "List item does not belong to the collection or cannot be linked there.",
+    },
+    {
+      code: 324,
+      name: "InvalidMove",
+      msg: "List item is already at the requested position.",
+    },
+    {
+      code: 325,
+      name: "SaleNotStarted",
+      msg: "Sale has not started yet.",
+    },
+    {
+      code: 326,
+      name: "SaleEnded",
+      msg: "Sale has ended.",
+    },
+    {
+      code: 327,
+      name: "InvalidSaleWindow",
+      msg: "Sale start must be before sale end.",
+    },
+    {
+      code: 328,
+      name: "ListingExpired",
+      msg: "Listing has expired.",
+    },
+    {
+      code: 329,
+      name: "ListingNotExpired",
+      msg: "Listing has not expired.",
+    },
+    {
+      code: 330,
+      name: "MysteryBoxDisabled",
+      msg: "Collection does not offer mystery box purchases with this price model.",
+    },
+    {
+      code: 331,
+      name: "RevealTooEarly",
+      msg: "Purchase commitment must be revealed in a later slot.",
+    },
+    {
+      code: 332,
+      name: "RevealWindowElapsed",
+      msg: "Purchase commitment can no longer be revealed.",
+    },
+    {
+      code: 333,
+      name: "InvalidRandomSelection",
+      msg: "List item does not match the random selection.",
+    },
+    {
+      code: 334,
+      name: "RefundUnavailable",
+      msg: "Purchase can only be refunded once its draw can't be delivered.",
+    },
+    {
+      code: 335,
+      name: "SalePricesOutOfBounds",
+      msg: "Price model exceeded max length of 8 sale prices.",
+    },
+    {
+      code: 336,
+      name: "InvalidAccountVersion",
+      msg: "Account is not at a version that can be migrated.",
+    },
+    {
+      code: 337,
+      name: "InvalidMigrationAccount",
+      msg: "Account does not belong to the account being migrated.",
+    },
+    {
+      code: 338,
+      name: "InvalidRepair",
+      msg: "List items cannot be linked in this order.",
+    },
+    {
+      code: 339,
+      name: "ListLinkIntact",
+      msg: "List items are already linked.",
+    },
+    {
+      code: 340,
+      name: "InvalidRescueAmount",
+      msg: "Account does not hold the amount to rescue.",
+    },
+    {
+      code: 341,
+      name: "InvalidFeeAccount",
+      msg: "Fee account is not the fee recipient's associated token account.",
+    },
+    {
+      code: 342,
+      name: "RevealWindowOpen",
+      msg: "Purchase commitment can still be revealed.",
+    },
+    {
+      code: 343,
+      name: "InventoryEmpty",
+      msg: "Nothing could be drawn, the purchase can be refunded.",
+    },
+    {
+      code: 344,
+      name: "TreasuryNotClosed",
+      msg: "Every tracked treasury account must be closed with its collection or market.",
+    },
+    {
+      code: 345,
+      name: "DrawVoided",
+      msg: "Draw can no longer be delivered, the purchase can be refunded.",
+    },
+    {
+      code: 346,
+      name: "LegacyBucketsPending",
+      msg: "Collection has legacy buckets left to count or migrate.",
+    },
   ],
 }
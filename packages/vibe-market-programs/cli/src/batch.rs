@@ -0,0 +1,300 @@
+//! Batch listing from a `batchAddNftConfig.json` file.
+//!
+//! Every NFT held by the configured wallet is listed at the front of the
+//! collection, `BATCH_SIZE` per transaction. Confirmed listings are appended
+//! to a progress file after each transaction, and mints that are already
+//! listed are skipped, so an interrupted run can simply be restarted.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token::{self, TokenAccount};
+use anyhow::{anyhow, bail, Context as _, Result};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use vibe_market::Collection;
+use vibe_market_client::account::{fetch_inventory_pages, fetch_nft_bucket};
+use vibe_market_client::instruction::{self as ix, ListNft};
+use vibe_market_client::pda::{find_inventory_page_address, find_nft_bucket_address};
+
+use crate::Context;
+
+const BATCH_SIZE: usize = 3;
+// Slots per InventoryPage
+const INVENTORY_PAGE_SLOTS: usize = 64;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Config {
+    cluster: String,
+    keypair: String,
+    collection_address: String,
+    price_model_address: String,
+    preview: bool,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Progress {
+    collection_address: String,
+    listed: Vec<ListedNft>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ListedNft {
+    mint: String,
+    nft_bucket: String,
+    signature: String,
+}
+
+#[derive(Clone, Copy)]
+struct PendingNft {
+    nft_account: Pubkey,
+    nft_mint: Pubkey,
+}
+
+fn load_config(path: &Path) -> Result<(Config, Pubkey, Pubkey)> {
+    let config: Config = serde_json::from_str(&fs::read_to_string(path)?)
+        .with_context(|| format!("failed to read config {}", path.display()))?;
+
+    let mut invalid_keys = Vec::new();
+    if !["devnet", "mainnet-beta"].contains(&config.cluster.as_str()) {
+        invalid_keys.push("cluster");
+    }
+    let collection = config.collection_address.parse::<Pubkey>();
+    if collection.is_err() {
+        invalid_keys.push("collectionAddress");
+    }
+    let price_model = config.price_model_address.parse::<Pubkey>();
+    if price_model.is_err() {
+        invalid_keys.push("priceModelAddress");
+    }
+    match (collection, price_model) {
+        (Ok(collection), Ok(price_model)) if invalid_keys.is_empty() => {
+            Ok((config, collection, price_model))
+        }
+        _ => bail!("Invalid keys: {}", invalid_keys.join(", ")),
+    }
+}
+
+fn load_progress(path: &Path, collection: &Pubkey) -> Result<Progress> {
+    if !path.exists() {
+        return Ok(Progress {
+            collection_address: collection.to_string(),
+            ..Progress::default()
+        });
+    }
+    let progress: Progress = serde_json::from_str(&fs::read_to_string(path)?)
+        .with_context(|| format!("failed to read progress file {}", path.display()))?;
+    if progress.collection_address != collection.to_string() {
+        bail!(
+            "progress file {} belongs to collection {}",
+            path.display(),
+            progress.collection_address
+        );
+    }
+    Ok(progress)
+}
+
+fn save_progress(path: &Path, progress: &Progress) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(progress)?)?;
+    Ok(())
+}
+
+/// Token accounts of `owner` holding exactly one token.
+fn fetch_nft_accounts(ctx: &Context, owner: &Pubkey) -> Result<Vec<PendingNft>> {
+    let accounts = ctx.client.get_program_accounts_with_config(
+        &token::ID,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(165),
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: 32,
+                    bytes: MemcmpEncodedBytes::Base58(owner.to_string()),
+                    encoding: None,
+                }),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            with_context: None,
+        },
+    )?;
+
+    let mut nfts = Vec::new();
+    for (nft_account, account) in accounts {
+        let token_account = TokenAccount::try_deserialize(&mut &account.data[..])?;
+        if token_account.amount == 1 {
+            nfts.push(PendingNft {
+                nft_account,
+                nft_mint: token_account.mint,
+            });
+        }
+    }
+    Ok(nfts)
+}
+
+/// Drops NFTs recorded in the progress file or already listed on-chain.
+fn pending_nfts(
+    ctx: &Context,
+    collection: &Pubkey,
+    progress: &Progress,
+    nfts: Vec<PendingNft>,
+) -> Result<Vec<PendingNft>> {
+    let recorded: HashSet<&str> = progress
+        .listed
+        .iter()
+        .map(|nft| nft.mint.as_str())
+        .collect();
+    let nfts: Vec<PendingNft> = nfts
+        .into_iter()
+        .filter(|nft| !recorded.contains(nft.nft_mint.to_string().as_str()))
+        .collect();
+
+    let mut pending = Vec::with_capacity(nfts.len());
+    for chunk in nfts.chunks(100) {
+        let buckets: Vec<Pubkey> = chunk
+            .iter()
+            .map(|nft| find_nft_bucket_address(collection, &nft.nft_mint).0)
+            .collect();
+        for (nft, bucket) in chunk
+            .iter()
+            .zip(ctx.client.get_multiple_accounts(&buckets)?)
+        {
+            if bucket.is_none() {
+                pending.push(*nft);
+            }
+        }
+    }
+    Ok(pending)
+}
+
+/// Assigns each pending NFT an inventory page with a free slot, returning
+/// the page per NFT and the number of pages that must be created first.
+fn plan_inventory_pages(
+    ctx: &Context,
+    collection: &Pubkey,
+    collection_state: &Collection,
+    num_nfts: usize,
+) -> Result<(Vec<u32>, u32)> {
+    let mut free_slots: Vec<usize> =
+        fetch_inventory_pages(&ctx.client, collection, collection_state)?
+            .iter()
+            .map(|(_, page)| page.slots.len() - page.num_occupied as usize)
+            .collect();
+
+    let mut assignments = Vec::with_capacity(num_nfts);
+    let mut page_index = 0;
+    for _ in 0..num_nfts {
+        while page_index < free_slots.len() && free_slots[page_index] == 0 {
+            page_index += 1;
+        }
+        if page_index == free_slots.len() {
+            free_slots.push(INVENTORY_PAGE_SLOTS);
+        }
+        free_slots[page_index] -= 1;
+        assignments.push(page_index as u32);
+    }
+    let num_new_pages = free_slots.len() as u32 - collection_state.num_inventory_pages;
+    Ok((assignments, num_new_pages))
+}
+
+pub fn run(config_path: &Path, dry_run: bool, progress_path: Option<&Path>) -> Result<()> {
+    let (config, collection, price_model) = load_config(config_path)?;
+    let progress_path: PathBuf = match progress_path {
+        Some(path) => path.to_path_buf(),
+        None => config_path.with_extension("progress.json"),
+    };
+
+    let ctx = Context::new(&config.cluster, &config.keypair)?;
+    let signer = ctx.signer();
+    println!("Using wallet: {}", signer);
+
+    let mut collection_state = ctx.fetch_collection(&collection)?;
+    let market = ctx.collection_market(&collection, &collection_state)?;
+    println!("Market: {}", market);
+    println!("Collection: {} ({})", collection, collection_state.title);
+    println!("Price model: {}", price_model);
+
+    let mut progress = load_progress(&progress_path, &collection)?;
+    println!("Fetching token accounts...");
+    let nfts = fetch_nft_accounts(&ctx, &signer)?;
+    println!("Fetched {} nft token accounts", nfts.len());
+    let nfts = pending_nfts(&ctx, &collection, &progress, nfts)?;
+    println!(
+        "{} already listed, {} to list",
+        progress.listed.len(),
+        nfts.len()
+    );
+
+    let (pages, num_new_pages) =
+        plan_inventory_pages(&ctx, &collection, &collection_state, nfts.len())?;
+    println!(
+        "{} inventory pages to create, {} transactions to send",
+        num_new_pages,
+        num_new_pages as usize + nfts.len().div_ceil(BATCH_SIZE)
+    );
+
+    if config.preview || dry_run {
+        for nft in &nfts {
+            println!("  {} from {}", nft.nft_mint, nft.nft_account);
+        }
+        println!("Exiting preview...");
+        return Ok(());
+    }
+
+    for _ in 0..num_new_pages {
+        ctx.send(&[ix::init_inventory_page(
+            &signer,
+            &market,
+            &collection,
+            &collection_state,
+        )])?;
+        collection_state.num_inventory_pages += 1;
+    }
+
+    let list_head = collection_state.list_head;
+    let mut next_list_item = fetch_nft_bucket(&ctx.client, &list_head)?.next_list_item;
+    for (batch, batch_pages) in nfts.chunks(BATCH_SIZE).zip(pages.chunks(BATCH_SIZE)) {
+        let mut instructions = Vec::with_capacity(batch.len());
+        let mut buckets = Vec::with_capacity(batch.len());
+        for (nft, page_index) in batch.iter().zip(batch_pages) {
+            let nft_bucket = find_nft_bucket_address(&collection, &nft.nft_mint).0;
+            instructions.push(ix::add_nft(&ListNft {
+                admin: signer,
+                market,
+                collection,
+                prev_list_item: list_head,
+                next_list_item,
+                nft_mint: nft.nft_mint,
+                admin_nft_account: nft.nft_account,
+                inventory_page: find_inventory_page_address(&collection, *page_index).0,
+                price_model,
+            }));
+            buckets.push(nft_bucket);
+            next_list_item = nft_bucket;
+        }
+
+        let signature = ctx
+            .send(&instructions)
+            .map_err(|err| anyhow!("{}; rerun to resume from {}", err, progress_path.display()))?;
+        for (nft, nft_bucket) in batch.iter().zip(buckets) {
+            progress.listed.push(ListedNft {
+                mint: nft.nft_mint.to_string(),
+                nft_bucket: nft_bucket.to_string(),
+                signature: signature.to_string(),
+            });
+        }
+        save_progress(&progress_path, &progress)?;
+        println!("Uploaded {} total", progress.listed.len());
+    }
+    println!("Process complete");
+    Ok(())
+}
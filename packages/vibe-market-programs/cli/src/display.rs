@@ -0,0 +1,186 @@
+//! Human readable output for `show` subcommands.
+
+use anchor_lang::prelude::Pubkey;
+use vibe_market::{
+    Collection, GlobalState, Market, MarketCreation, NftBucket, PriceModel, SalesStats,
+};
+use vibe_market_client::account::{fixed_str, ListItem};
+
+fn timestamp(value: Option<i64>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "unset".to_string(),
+    }
+}
+
+// Zero-copy accounts store unset timestamps as 0
+fn zero_copy_timestamp(value: i64) -> String {
+    timestamp(if value == 0 { None } else { Some(value) })
+}
+
+pub fn print_global_state(address: &Pubkey, global_state: &GlobalState) {
+    let market_creation = match global_state.market_creation {
+        MarketCreation::Open => "open",
+        MarketCreation::Permissioned => "permissioned",
+        MarketCreation::FeeGated => "fee-gated",
+    };
+    println!("Global state {}", address);
+    println!("  version:             {}", global_state.version);
+    println!("  authority:           {}", global_state.authority);
+    println!("  markets:             {}", global_state.num_markets);
+    println!(
+        "  protocol fee:        {} bps",
+        global_state.fee_basis_points
+    );
+    println!("  fee recipient:       {}", global_state.fee_recipient);
+    println!("  market creation:     {}", market_creation);
+    println!(
+        "  market creation fee: {}",
+        global_state.market_creation_fee
+    );
+}
+
+pub fn print_market(address: &Pubkey, market: &Market) {
+    // Copy packed fields out before formatting them
+    let (version, index, paused) = (market.version, market.index, market.paused);
+    let (num_collections, num_open_collections) =
+        (market.num_collections, market.num_open_collections);
+    let (num_price_models, num_open_price_models) =
+        (market.num_price_models, market.num_open_price_models);
+    let metadata = market.metadata;
+    let sort_priority = metadata.sort_priority;
+
+    println!("Market {}", address);
+    println!("  version:      {}", version);
+    println!("  index:        {}", index);
+    println!(
+        "  title:        {}",
+        fixed_str(&market.title, market.title_len as usize)
+    );
+    println!("  paused:       {}", paused != 0);
+    println!(
+        "  collections:  {} open of {}",
+        num_open_collections, num_collections
+    );
+    println!(
+        "  price models: {} open of {}",
+        num_open_price_models, num_price_models
+    );
+    println!(
+        "  description:  {}",
+        fixed_str(&metadata.description, metadata.description_len as usize)
+    );
+    println!(
+        "  image uri:    {}",
+        fixed_str(&metadata.image_uri, metadata.image_uri_len as usize)
+    );
+    println!(
+        "  external url: {}",
+        fixed_str(&metadata.external_url, metadata.external_url_len as usize)
+    );
+    println!("  sort priority: {}", sort_priority);
+    println!("  admins:");
+    for admin in market.whitelist() {
+        println!("    {}", admin);
+    }
+    print_stats(&market.stats);
+}
+
+pub fn print_collection(address: &Pubkey, collection: &Collection) {
+    println!("Collection {}", address);
+    println!("  version:         {}", collection.version);
+    println!("  index:           {}", collection.index);
+    println!("  title:           {}", collection.title);
+    println!("  paused:          {}", collection.paused);
+    println!("  description:     {}", collection.metadata.description);
+    println!("  image uri:       {}", collection.metadata.image_uri);
+    println!("  external url:    {}", collection.metadata.external_url);
+    println!("  sort priority:   {}", collection.metadata.sort_priority);
+    println!("  sale start:      {}", timestamp(collection.sale_start));
+    println!("  sale end:        {}", timestamp(collection.sale_end));
+    match collection.mystery_price_model {
+        Some(price_model) => println!("  mystery box:     {}", price_model),
+        None => println!("  mystery box:     disabled"),
+    }
+    println!("  inventory pages: {}", collection.num_inventory_pages);
+    println!("  list head:       {}", collection.list_head);
+    println!("  list tail:       {}", collection.list_tail);
+    print_stats(&collection.stats);
+}
+
+pub fn print_price_model(address: &Pubkey, price_model: &PriceModel) {
+    let (version, index, num_listings) = (
+        price_model.version,
+        price_model.index,
+        price_model.num_listings,
+    );
+
+    println!("Price model {}", address);
+    println!("  version:  {}", version);
+    println!("  index:    {}", index);
+    println!("  market:   {}", price_model.market);
+    println!("  listings: {}", num_listings);
+    println!("  prices:");
+    for sale_price in &price_model.sale_prices[..price_model.num_sale_prices as usize] {
+        let amount = sale_price.amount;
+        println!("    {} {}", amount, sale_price.mint);
+    }
+}
+
+pub fn print_nft_bucket(address: &Pubkey, nft_bucket: &NftBucket) {
+    let (version, inventory_slot) = (nft_bucket.version, nft_bucket.inventory_slot);
+    let (sale_start, sale_end, expires_at) = (
+        nft_bucket.sale_start,
+        nft_bucket.sale_end,
+        nft_bucket.expires_at,
+    );
+
+    println!("Listing {}", address);
+    println!("  version:        {}", version);
+    println!("  mint:           {}", nft_bucket.nft_mint);
+    println!("  price model:    {}", nft_bucket.price_model);
+    println!("  payer:          {}", nft_bucket.payer);
+    println!("  tombstoned:     {}", nft_bucket.tombstoned != 0);
+    println!("  inventory page: {}", nft_bucket.inventory_page);
+    println!("  inventory slot: {}", inventory_slot);
+    println!("  sale start:     {}", zero_copy_timestamp(sale_start));
+    println!("  sale end:       {}", zero_copy_timestamp(sale_end));
+    println!("  expires at:     {}", zero_copy_timestamp(expires_at));
+    println!("  prev:           {}", nft_bucket.prev_list_item);
+    println!("  next:           {}", nft_bucket.next_list_item);
+}
+
+pub fn print_list(items: &[ListItem]) {
+    println!("Listings ({}):", items.len());
+    for (position, item) in items.iter().enumerate() {
+        let status = if item.is_tombstoned() {
+            "tombstoned"
+        } else {
+            "listed"
+        };
+        println!(
+            "  {:>4}  {}  mint {}  price model {}  {}",
+            position, item.address, item.bucket.nft_mint, item.bucket.price_model, status
+        );
+    }
+}
+
+fn print_stats(stats: &SalesStats) {
+    let (num_listed, num_sold) = (stats.num_listed, stats.num_sold);
+    let last_sale = stats.last_sale;
+    let (amount, timestamp) = (last_sale.amount, last_sale.timestamp);
+
+    println!("  listed:       {}", num_listed);
+    println!("  sold:         {}", num_sold);
+    println!("  volume:");
+    for volume in &stats.volume[..stats.num_volume as usize] {
+        let volume_amount = volume.amount;
+        println!("    {} {}", volume_amount, volume.mint);
+    }
+    if timestamp != 0 {
+        println!(
+            "  last sale:    {} for {} {} to {} at {}",
+            last_sale.nft_mint, amount, last_sale.debit_mint, last_sale.buyer, timestamp
+        );
+    }
+}
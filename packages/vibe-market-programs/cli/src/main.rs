@@ -0,0 +1,1255 @@
+mod batch;
+mod display;
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::AnchorDeserialize;
+use anchor_spl::associated_token::get_associated_token_address;
+use anyhow::{anyhow, bail, Context as _, Result};
+use clap::{Args, Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::sysvar;
+use solana_sdk::transaction::Transaction;
+use vibe_market::{Collection, LegacyNftBucket, MarketCreation, Metadata, SalePrice};
+use vibe_market_client::account::*;
+use vibe_market_client::pda::*;
+use vibe_market_client::{instruction as ix, mystery};
+
+// Legacy buckets counted per transaction, within the account limit
+const LEGACY_COUNT_BATCH_LEN: usize = 20;
+
+#[derive(Parser)]
+#[clap(name = "vibe-market", about = "Administer Vibe Market deployments")]
+struct Opts {
+    /// RPC URL or cluster moniker (localnet, devnet, mainnet-beta)
+    #[clap(long, short = 'u', default_value = "devnet")]
+    url: String,
+    /// Keypair that signs and pays for transactions
+    #[clap(long, short = 'k', default_value = "~/.config/solana/id.json")]
+    keypair: String,
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print markets, collections, price models and listings
+    #[clap(subcommand)]
+    Show(Show),
+    /// List every NFT held by the configured wallet, from a
+    /// batchAddNftConfig.json file
+    BatchAddNfts {
+        config: PathBuf,
+        /// Print the plan without sending transactions
+        #[clap(long)]
+        dry_run: bool,
+        /// Progress file, defaults to the config path with a .progress.json
+        /// extension
+        #[clap(long)]
+        progress: Option<PathBuf>,
+    },
+    InitGlobalState,
+    UpdateProtocolFee {
+        #[clap(long)]
+        fee_basis_points: u16,
+        #[clap(long)]
+        fee_recipient: Pubkey,
+    },
+    SetGlobalAuthority {
+        new_authority: Pubkey,
+    },
+    UpdateMarketCreation {
+        /// open, permissioned or fee-gated
+        #[clap(long, parse(try_from_str = parse_market_creation))]
+        mode: MarketCreation,
+        #[clap(long, default_value = "0")]
+        fee: u64,
+    },
+    InitMarket {
+        #[clap(long)]
+        title: String,
+        /// Market admin, repeatable. Defaults to the signer
+        #[clap(long = "admin")]
+        whitelist: Vec<Pubkey>,
+    },
+    AddAdmin {
+        #[clap(long)]
+        market: Pubkey,
+        admin: Pubkey,
+    },
+    RemoveAdmin {
+        #[clap(long)]
+        market: Pubkey,
+        admin: Pubkey,
+    },
+    SetMarketPaused {
+        #[clap(long)]
+        market: Pubkey,
+        #[clap(parse(try_from_str))]
+        paused: bool,
+    },
+    UpdateMarketMetadata {
+        #[clap(long)]
+        market: Pubkey,
+        #[clap(long)]
+        title: String,
+        #[clap(flatten)]
+        metadata: MetadataArgs,
+    },
+    InitCollection {
+        #[clap(long)]
+        market: Pubkey,
+        #[clap(long)]
+        title: String,
+    },
+    SetCollectionPaused {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(parse(try_from_str))]
+        paused: bool,
+    },
+    SetCollectionSaleWindow {
+        #[clap(long)]
+        collection: Pubkey,
+        /// Unix timestamp, omit to clear
+        #[clap(long)]
+        sale_start: Option<i64>,
+        /// Unix timestamp, omit to clear
+        #[clap(long)]
+        sale_end: Option<i64>,
+    },
+    SetNftSaleWindow {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        sale_start: Option<i64>,
+        #[clap(long)]
+        sale_end: Option<i64>,
+    },
+    SetNftExpiry {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        /// Unix timestamp, omit to clear
+        #[clap(long)]
+        expires_at: Option<i64>,
+    },
+    SetMysteryPriceModel {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        price_model: Pubkey,
+        #[clap(parse(try_from_str))]
+        enabled: bool,
+    },
+    UpdateCollectionMetadata {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        title: String,
+        #[clap(flatten)]
+        metadata: MetadataArgs,
+    },
+    InitInventoryPage {
+        #[clap(long)]
+        collection: Pubkey,
+    },
+    /// Close the collection's last inventory page
+    CloseInventoryPage {
+        #[clap(long)]
+        collection: Pubkey,
+    },
+    InitPriceModel {
+        #[clap(long)]
+        market: Pubkey,
+        /// MINT:AMOUNT, repeatable
+        #[clap(long = "price", required = true, parse(try_from_str = parse_sale_price))]
+        sale_prices: Vec<SalePrice>,
+    },
+    ClosePriceModel {
+        price_model: Pubkey,
+    },
+    /// List an NFT at the front of the collection
+    AddNft {
+        #[clap(flatten)]
+        listing: ListingArgs,
+    },
+    /// List an NFT directly after another listed NFT
+    InsertNftAfter {
+        #[clap(flatten)]
+        listing: ListingArgs,
+        /// Mint of the listing to insert after
+        #[clap(long)]
+        after: Pubkey,
+    },
+    MoveItem {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        /// Mint of the listing to move after, defaults to the front
+        #[clap(long)]
+        after: Option<Pubkey>,
+    },
+    /// Link two list items directly, e.g. after `show collection --listings`
+    /// reports a broken list
+    RepairLink {
+        #[clap(long)]
+        collection: Pubkey,
+        /// List item address, the head or a listing's bucket
+        #[clap(long)]
+        prev: Pubkey,
+        /// List item address, the tail or a listing's bucket
+        #[clap(long)]
+        next: Pubkey,
+        /// Tombstoned or invalid item dropped from between the two, repeatable,
+        /// in the order the next pointers from `prev` give
+        #[clap(long = "skip")]
+        skipped: Vec<Pubkey>,
+    },
+    WithdrawNft {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    CrankExpired {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    PurchaseNft {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(flatten)]
+        payment: PaymentArgs,
+    },
+    CommitRandomPurchase {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(flatten)]
+        payment: PaymentArgs,
+    },
+    RevealRandomPurchase {
+        #[clap(long)]
+        collection: Pubkey,
+        /// Buyer of the draw, defaults to the signer
+        #[clap(long)]
+        owner: Option<Pubkey>,
+    },
+    RefundRandomPurchase {
+        #[clap(long)]
+        collection: Pubkey,
+        /// Defaults to the signer's associated token account
+        #[clap(long)]
+        debit_account: Option<Pubkey>,
+    },
+    /// Forfeit a draw that was not revealed in time
+    ExpireRandomPurchase {
+        #[clap(long)]
+        collection: Pubkey,
+        /// Buyer of the draw, defaults to the signer
+        #[clap(long)]
+        owner: Option<Pubkey>,
+    },
+    CompactListItem {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    WithdrawLiquidity {
+        #[clap(long)]
+        market: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        amount: u64,
+    },
+    WithdrawCollectionLiquidity {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        amount: u64,
+    },
+    /// List an NFT sent straight to the collection's token account
+    AdoptNft {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        price_model: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        /// Mint of the listing to insert after, defaults to the front
+        #[clap(long)]
+        after: Option<Pubkey>,
+    },
+    /// Send tokens held by the collection that aren't listed to a wallet
+    ReturnCollectionTokens {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        recipient: Pubkey,
+        amount: u64,
+    },
+    /// Send tokens held by the market to a wallet
+    ReturnMarketTokens {
+        #[clap(long)]
+        market: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+        #[clap(long)]
+        recipient: Pubkey,
+        amount: u64,
+    },
+    /// Close an empty collection; the signer must have created it
+    CloseCollection {
+        #[clap(long)]
+        collection: Pubkey,
+        /// Mint of an empty treasury to close, repeatable. Every mint the
+        /// collection has been paid in is required
+        #[clap(long = "treasury-mint")]
+        treasury_mints: Vec<Pubkey>,
+    },
+    /// Track a market treasury so the market can't close while it is open
+    TrackMarketTreasury {
+        #[clap(long)]
+        market: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+    },
+    CloseMarket {
+        #[clap(long)]
+        market: Pubkey,
+        /// Mint of an empty treasury to close, repeatable. Every tracked
+        /// treasury is required
+        #[clap(long = "treasury-mint")]
+        treasury_mints: Vec<Pubkey>,
+    },
+    MigrateGlobalState,
+    MigrateMarket {
+        market: Pubkey,
+    },
+    MigrateCollection {
+        collection: Pubkey,
+    },
+    /// Count a migrated collection's legacy buckets, before migrating them
+    CountLegacyBuckets {
+        collection: Pubkey,
+    },
+    MigratePriceModel {
+        price_model: Pubkey,
+    },
+    MigrateNftBucket {
+        #[clap(long)]
+        collection: Pubkey,
+        /// Legacy bucket address
+        #[clap(long)]
+        nft_bucket: Pubkey,
+        #[clap(long)]
+        price_model: Pubkey,
+    },
+}
+
+#[derive(Subcommand)]
+enum Show {
+    Global,
+    Market {
+        address: Pubkey,
+    },
+    Collection {
+        address: Pubkey,
+        /// Also walk the collection's list
+        #[clap(long)]
+        listings: bool,
+    },
+    PriceModel {
+        address: Pubkey,
+    },
+    Listing {
+        #[clap(long)]
+        collection: Pubkey,
+        #[clap(long)]
+        mint: Pubkey,
+    },
+}
+
+#[derive(Args)]
+struct MetadataArgs {
+    #[clap(long, default_value = "")]
+    description: String,
+    #[clap(long, default_value = "")]
+    image_uri: String,
+    #[clap(long, default_value = "")]
+    external_url: String,
+    #[clap(long, default_value = "0")]
+    sort_priority: u32,
+}
+
+impl From<MetadataArgs> for Metadata {
+    fn from(args: MetadataArgs) -> Self {
+        Metadata {
+            description: args.description,
+            image_uri: args.image_uri,
+            external_url: args.external_url,
+            sort_priority: args.sort_priority,
+        }
+    }
+}
+
+#[derive(Args)]
+struct ListingArgs {
+    #[clap(long)]
+    collection: Pubkey,
+    #[clap(long)]
+    price_model: Pubkey,
+    #[clap(long)]
+    mint: Pubkey,
+    /// Token account holding the NFT, defaults to the signer's associated
+    /// token account
+    #[clap(long)]
+    nft_account: Option<Pubkey>,
+}
+
+#[derive(Args)]
+struct PaymentArgs {
+    #[clap(long)]
+    debit_mint: Pubkey,
+    /// Defaults to the signer's associated token account
+    #[clap(long)]
+    debit_account: Option<Pubkey>,
+}
+
+fn parse_market_creation(value: &str) -> Result<MarketCreation> {
+    match value {
+        "open" => Ok(MarketCreation::Open),
+        "permissioned" => Ok(MarketCreation::Permissioned),
+        "fee-gated" => Ok(MarketCreation::FeeGated),
+        _ => bail!("expected open, permissioned or fee-gated"),
+    }
+}
+
+fn parse_sale_price(value: &str) -> Result<SalePrice> {
+    let (mint, amount) = value
+        .split_once(':')
+        .ok_or_else(|| anyhow!("expected MINT:AMOUNT"))?;
+    Ok(SalePrice {
+        mint: mint.parse()?,
+        amount: amount.parse()?,
+    })
+}
+
+pub fn cluster_url(cluster: &str) -> &str {
+    match cluster {
+        "localnet" | "localhost" => "http://localhost:8899",
+        "devnet" => "https://api.devnet.solana.com",
+        "mainnet-beta" => "https://api.mainnet-beta.solana.com",
+        url => url,
+    }
+}
+
+pub struct Context {
+    pub client: RpcClient,
+    pub payer: Keypair,
+}
+
+impl Context {
+    pub fn new(cluster: &str, keypair: &str) -> Result<Self> {
+        let keypair_path = match keypair.strip_prefix("~/") {
+            Some(path) => PathBuf::from(std::env::var("HOME")?).join(path),
+            None => PathBuf::from(keypair),
+        };
+        let payer = read_keypair_file(&keypair_path)
+            .map_err(|err| anyhow!("failed to load keypair {}: {}", keypair_path.display(), err))?;
+        Ok(Context {
+            client: RpcClient::new_with_commitment(
+                cluster_url(cluster).to_string(),
+                CommitmentConfig::confirmed(),
+            ),
+            payer,
+        })
+    }
+
+    pub fn signer(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    pub fn send(&self, instructions: &[Instruction]) -> Result<Signature> {
+        let blockhash = self.client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+        let signature = self.client.send_and_confirm_transaction(&transaction)?;
+        println!("Signature: {}", signature);
+        Ok(signature)
+    }
+
+    pub fn fetch_collection(&self, collection: &Pubkey) -> Result<Collection> {
+        fetch_collection(&self.client, collection)
+            .with_context(|| format!("failed to fetch collection {}", collection))
+    }
+
+    /// Finds the market owning `collection`. Collections don't store their
+    /// market, so each market's collection address at the same index is
+    /// derived until one matches.
+    pub fn collection_market(
+        &self,
+        collection: &Pubkey,
+        collection_state: &Collection,
+    ) -> Result<Pubkey> {
+        let global_state = fetch_global_state(&self.client)?;
+        let global_state_key = find_global_state_address().0;
+        (0..global_state.num_markets)
+            .map(|index| find_market_address(&global_state_key, index).0)
+            .find(|market| find_collection_address(market, collection_state.index).0 == *collection)
+            .ok_or_else(|| anyhow!("no market owns collection {}", collection))
+    }
+
+    /// Listing of `mint` in `collection`. Listings migrated from keypair
+    /// accounts are only found through the inventory pages, every other
+    /// listing is the mint's PDA.
+    pub fn listing_address(&self, collection: &Pubkey, mint: &Pubkey) -> Result<Pubkey> {
+        let collection_state = self.fetch_collection(collection)?;
+        let listed = fetch_inventory_pages(&self.client, collection, &collection_state)?
+            .into_iter()
+            .flat_map(|(_, page)| page.slots)
+            .find(|slot| slot.nft_mint == *mint)
+            .map(|slot| slot.nft_bucket);
+        Ok(listed.unwrap_or_else(|| find_nft_bucket_address(collection, mint).0))
+    }
+
+    /// First inventory page of the collection with a free slot.
+    pub fn free_inventory_page(
+        &self,
+        collection: &Pubkey,
+        collection_state: &Collection,
+    ) -> Result<Pubkey> {
+        fetch_inventory_pages(&self.client, collection, collection_state)?
+            .into_iter()
+            .find(|(_, page)| (page.num_occupied as usize) < page.slots.len())
+            .map(|(key, _)| key)
+            .ok_or_else(|| {
+                anyhow!(
+                    "collection {} has no free inventory slot, run init-inventory-page",
+                    collection
+                )
+            })
+    }
+}
+
+fn main() -> Result<()> {
+    let opts = Opts::parse();
+    if let Command::BatchAddNfts {
+        config,
+        dry_run,
+        progress,
+    } = &opts.command
+    {
+        return batch::run(config, *dry_run, progress.as_deref());
+    }
+
+    let ctx = Context::new(&opts.url, &opts.keypair)?;
+    let signer = ctx.signer();
+
+    match opts.command {
+        Command::Show(show) => run_show(&ctx, show)?,
+        Command::BatchAddNfts { .. } => unreachable!(),
+        Command::InitGlobalState => {
+            ctx.send(&[ix::init_global_state(&signer)])?;
+        }
+        Command::UpdateProtocolFee {
+            fee_basis_points,
+            fee_recipient,
+        } => {
+            ctx.send(&[ix::update_protocol_fee(
+                &signer,
+                &fee_recipient,
+                fee_basis_points,
+            )])?;
+        }
+        Command::SetGlobalAuthority { new_authority } => {
+            ctx.send(&[ix::set_global_authority(&signer, &new_authority)])?;
+        }
+        Command::UpdateMarketCreation { mode, fee } => {
+            ctx.send(&[ix::update_market_creation(&signer, mode, fee)])?;
+        }
+        Command::InitMarket {
+            title,
+            mut whitelist,
+        } => {
+            if whitelist.is_empty() {
+                whitelist.push(signer);
+            }
+            let global_state = fetch_global_state(&ctx.client)?;
+            let market =
+                find_market_address(&find_global_state_address().0, global_state.num_markets).0;
+            ctx.send(&[ix::init_market(&signer, &global_state, whitelist, title)])?;
+            println!("Market: {}", market);
+        }
+        Command::AddAdmin { market, admin } => {
+            ctx.send(&[ix::add_admin(&signer, &market, &admin)])?;
+        }
+        Command::RemoveAdmin { market, admin } => {
+            ctx.send(&[ix::remove_admin(&signer, &market, &admin)])?;
+        }
+        Command::SetMarketPaused { market, paused } => {
+            ctx.send(&[ix::set_market_paused(&signer, &market, paused)])?;
+        }
+        Command::UpdateMarketMetadata {
+            market,
+            title,
+            metadata,
+        } => {
+            ctx.send(&[ix::update_market_metadata(
+                &signer,
+                &market,
+                title,
+                metadata.into(),
+            )])?;
+        }
+        Command::InitCollection { market, title } => {
+            let market_state = fetch_market(&ctx.client, &market)?;
+            let collection = find_collection_address(&market, market_state.num_collections).0;
+            // Open the first inventory page too, so NFTs can be listed right away
+            ctx.send(&[
+                ix::init_collection(&signer, &market, &market_state, title),
+                ix::init_inventory_page(&signer, &market, &collection, &Collection::default()),
+            ])?;
+            println!("Collection: {}", collection);
+        }
+        Command::SetCollectionPaused { collection, paused } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            ctx.send(&[ix::set_collection_paused(
+                &signer,
+                &market,
+                &collection,
+                paused,
+            )])?;
+        }
+        Command::SetCollectionSaleWindow {
+            collection,
+            sale_start,
+            sale_end,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            ctx.send(&[ix::set_collection_sale_window(
+                &signer,
+                &market,
+                &collection,
+                sale_start,
+                sale_end,
+            )])?;
+        }
+        Command::SetNftSaleWindow {
+            collection,
+            mint,
+            sale_start,
+            sale_end,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            let list_item = ctx.listing_address(&collection, &mint)?;
+            let list_item_state = fetch_nft_bucket(&ctx.client, &list_item)?;
+            ctx.send(&[ix::set_nft_sale_window(
+                &signer,
+                &market,
+                &collection,
+                &list_item,
+                &list_item_state,
+                sale_start,
+                sale_end,
+            )])?;
+        }
+        Command::SetNftExpiry {
+            collection,
+            mint,
+            expires_at,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            let list_item = ctx.listing_address(&collection, &mint)?;
+            let list_item_state = fetch_nft_bucket(&ctx.client, &list_item)?;
+            ctx.send(&[ix::set_nft_expiry(
+                &signer,
+                &market,
+                &collection,
+                &list_item,
+                &list_item_state,
+                expires_at,
+            )])?;
+        }
+        Command::SetMysteryPriceModel {
+            collection,
+            price_model,
+            enabled,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            ctx.send(&[ix::set_mystery_price_model(
+                &signer,
+                &market,
+                &collection,
+                &price_model,
+                enabled,
+            )])?;
+        }
+        Command::UpdateCollectionMetadata {
+            collection,
+            title,
+            metadata,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            ctx.send(&[ix::update_collection_metadata(
+                &signer,
+                &market,
+                &collection,
+                title,
+                metadata.into(),
+            )])?;
+        }
+        Command::InitInventoryPage { collection } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            ctx.send(&[ix::init_inventory_page(
+                &signer,
+                &market,
+                &collection,
+                &collection_state,
+            )])?;
+        }
+        Command::CloseInventoryPage { collection } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let page_index = collection_state
+                .num_inventory_pages
+                .checked_sub(1)
+                .ok_or_else(|| anyhow!("collection {} has no inventory pages", collection))?;
+            ctx.send(&[ix::close_inventory_page(
+                &signer,
+                &market,
+                &collection,
+                page_index,
+            )])?;
+        }
+        Command::InitPriceModel {
+            market,
+            sale_prices,
+        } => {
+            let market_state = fetch_market(&ctx.client, &market)?;
+            let price_model = find_price_model_address(&market, market_state.num_price_models).0;
+            ctx.send(&[ix::init_price_model(
+                &signer,
+                &market,
+                &market_state,
+                sale_prices,
+            )])?;
+            println!("Price model: {}", price_model);
+        }
+        Command::ClosePriceModel { price_model } => {
+            let market = fetch_price_model(&ctx.client, &price_model)?.market;
+            ctx.send(&[ix::close_price_model(&signer, &market, &price_model)])?;
+        }
+        Command::AddNft { listing } => {
+            let collection_state = ctx.fetch_collection(&listing.collection)?;
+            let head = fetch_nft_bucket(&ctx.client, &collection_state.list_head)?;
+            let args = listing_accounts(
+                &ctx,
+                &listing,
+                &collection_state,
+                collection_state.list_head,
+                head.next_list_item,
+            )?;
+            ctx.send(&[ix::add_nft(&args)])?;
+            println!(
+                "Listing: {}",
+                find_nft_bucket_address(&listing.collection, &listing.mint).0
+            );
+        }
+        Command::InsertNftAfter { listing, after } => {
+            let collection_state = ctx.fetch_collection(&listing.collection)?;
+            let prev_list_item = ctx.listing_address(&listing.collection, &after)?;
+            let prev = fetch_nft_bucket(&ctx.client, &prev_list_item)?;
+            let args = listing_accounts(
+                &ctx,
+                &listing,
+                &collection_state,
+                prev_list_item,
+                prev.next_list_item,
+            )?;
+            ctx.send(&[ix::insert_nft_after(&args)])?;
+            println!(
+                "Listing: {}",
+                find_nft_bucket_address(&listing.collection, &listing.mint).0
+            );
+        }
+        Command::MoveItem {
+            collection,
+            mint,
+            after,
+        } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let list_item = ctx.listing_address(&collection, &mint)?;
+            let list_item_state = fetch_nft_bucket(&ctx.client, &list_item)?;
+            let new_prev_list_item = match after {
+                Some(after) => ctx.listing_address(&collection, &after)?,
+                None => collection_state.list_head,
+            };
+            let new_next_list_item =
+                fetch_nft_bucket(&ctx.client, &new_prev_list_item)?.next_list_item;
+            ctx.send(&[ix::move_item(
+                &signer,
+                &market,
+                &collection,
+                &list_item,
+                &list_item_state,
+                &new_prev_list_item,
+                &new_next_list_item,
+            )])?;
+        }
+        Command::RepairLink {
+            collection,
+            prev,
+            next,
+            skipped,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            ctx.send(&[ix::repair_link(
+                &signer,
+                &market,
+                &collection,
+                &prev,
+                &next,
+                &skipped,
+            )])?;
+        }
+        Command::WithdrawNft { collection, mint } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            let list_item = ctx.listing_address(&collection, &mint)?;
+            let list_item_state = fetch_nft_bucket(&ctx.client, &list_item)?;
+            ctx.send(&[ix::withdraw_nft(
+                &signer,
+                &market,
+                &collection,
+                &list_item,
+                &list_item_state,
+            )])?;
+        }
+        Command::CrankExpired { collection, mint } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            let list_item = ctx.listing_address(&collection, &mint)?;
+            let list_item_state = fetch_nft_bucket(&ctx.client, &list_item)?;
+            ctx.send(&[ix::crank_expired(
+                &signer,
+                &market,
+                &collection,
+                &list_item,
+                &list_item_state,
+            )])?;
+        }
+        Command::PurchaseNft {
+            collection,
+            mint,
+            payment,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            let global_state = fetch_global_state(&ctx.client)?;
+            let list_item = ctx.listing_address(&collection, &mint)?;
+            let list_item_state = fetch_nft_bucket(&ctx.client, &list_item)?;
+            let debit_account = payment.debit_account(&signer);
+            ctx.send(&[ix::purchase_nft(
+                &signer,
+                &global_state,
+                &market,
+                &collection,
+                &list_item,
+                &list_item_state,
+                &payment.debit_mint,
+                &debit_account,
+            )])?;
+        }
+        Command::CommitRandomPurchase {
+            collection,
+            payment,
+        } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let price_model = collection_state
+                .mystery_price_model
+                .ok_or_else(|| anyhow!("collection {} has no mystery box price", collection))?;
+            let global_state = fetch_global_state(&ctx.client)?;
+            let debit_account = payment.debit_account(&signer);
+            ctx.send(&[ix::commit_random_purchase(
+                &signer,
+                &global_state,
+                &market,
+                &collection,
+                &price_model,
+                &payment.debit_mint,
+                &debit_account,
+            )])?;
+        }
+        Command::RevealRandomPurchase { collection, owner } => {
+            let owner = owner.unwrap_or(signer);
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let mystery_commit = find_mystery_commit_address(&collection, &owner).0;
+            let mystery_commit_state = fetch_mystery_commit(&ctx.client, &mystery_commit)?;
+            let commit_slot = mystery_commit_state.commit_slot;
+            let slot_hashes = ctx.client.get_account_data(&sysvar::slot_hashes::ID)?;
+            let slot_hash =
+                mystery::reveal_slot_hash(&slot_hashes, commit_slot).ok_or_else(|| {
+                    anyhow!("the draw for slot {} cannot be revealed now", commit_slot)
+                })?;
+            let (page_keys, pages): (Vec<Pubkey>, Vec<_>) =
+                fetch_inventory_pages(&ctx.client, &collection, &collection_state)?
+                    .into_iter()
+                    .unzip();
+            let list_item = mystery::select_random_bucket(
+                &slot_hash,
+                &mystery_commit,
+                &mystery_commit_state,
+                &pages,
+            )
+            .ok_or_else(|| {
+                anyhow!("the draw cannot be delivered, refund it with refund-random-purchase")
+            })?;
+            let list_item_state = fetch_nft_bucket(&ctx.client, &list_item)?;
+            ctx.send(&[ix::reveal_random_purchase(
+                &signer,
+                &owner,
+                &market,
+                &collection,
+                &list_item,
+                &list_item_state,
+                &page_keys,
+            )])?;
+            println!("Drew: {}", list_item_state.nft_mint);
+        }
+        Command::RefundRandomPurchase {
+            collection,
+            debit_account,
+        } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let mystery_commit = fetch_mystery_commit(
+                &ctx.client,
+                &find_mystery_commit_address(&collection, &signer).0,
+            )?;
+            let debit_account = debit_account.unwrap_or_else(|| {
+                get_associated_token_address(&signer, &mystery_commit.debit_mint)
+            });
+            let page_keys: Vec<Pubkey> = (0..collection_state.num_inventory_pages)
+                .map(|index| find_inventory_page_address(&collection, index).0)
+                .collect();
+            ctx.send(&[ix::refund_random_purchase(
+                &signer,
+                &market,
+                &collection,
+                &mystery_commit,
+                &debit_account,
+                &page_keys,
+            )])?;
+        }
+        Command::ExpireRandomPurchase { collection, owner } => {
+            let owner = owner.unwrap_or(signer);
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let page_keys: Vec<Pubkey> = (0..collection_state.num_inventory_pages)
+                .map(|index| find_inventory_page_address(&collection, index).0)
+                .collect();
+            ctx.send(&[ix::expire_random_purchase(
+                &owner,
+                &market,
+                &collection,
+                &page_keys,
+            )])?;
+        }
+        Command::CompactListItem { collection, mint } => {
+            let list_item = ctx.listing_address(&collection, &mint)?;
+            let list_item_state = fetch_nft_bucket(&ctx.client, &list_item)?;
+            ctx.send(&[ix::compact_list_item(&list_item, &list_item_state)])?;
+        }
+        Command::WithdrawLiquidity {
+            market,
+            mint,
+            amount,
+        } => {
+            ctx.send(&[ix::withdraw_liquidity(&signer, &market, &mint, amount)])?;
+        }
+        Command::WithdrawCollectionLiquidity {
+            collection,
+            mint,
+            amount,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            ctx.send(&[ix::withdraw_collection_liquidity(
+                &signer,
+                &market,
+                &collection,
+                &mint,
+                amount,
+            )])?;
+        }
+        Command::AdoptNft {
+            collection,
+            price_model,
+            mint,
+            after,
+        } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let prev_list_item = match after {
+                Some(after) => ctx.listing_address(&collection, &after)?,
+                None => collection_state.list_head,
+            };
+            let next_list_item = fetch_nft_bucket(&ctx.client, &prev_list_item)?.next_list_item;
+            let inventory_page = ctx.free_inventory_page(&collection, &collection_state)?;
+            let page_keys: Vec<Pubkey> = (0..collection_state.num_inventory_pages)
+                .map(|index| find_inventory_page_address(&collection, index).0)
+                .collect();
+            ctx.send(&[ix::adopt_nft(
+                &signer,
+                &market,
+                &collection,
+                &prev_list_item,
+                &next_list_item,
+                &mint,
+                &inventory_page,
+                &price_model,
+                &page_keys,
+            )])?;
+            println!("Listing: {}", find_nft_bucket_address(&collection, &mint).0);
+        }
+        Command::ReturnCollectionTokens {
+            collection,
+            mint,
+            recipient,
+            amount,
+        } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let page_keys: Vec<Pubkey> = (0..collection_state.num_inventory_pages)
+                .map(|index| find_inventory_page_address(&collection, index).0)
+                .collect();
+            ctx.send(&[ix::return_collection_tokens(
+                &signer,
+                &market,
+                &collection,
+                &mint,
+                &recipient,
+                amount,
+                &page_keys,
+            )])?;
+        }
+        Command::ReturnMarketTokens {
+            market,
+            mint,
+            recipient,
+            amount,
+        } => {
+            ctx.send(&[ix::return_market_tokens(
+                &signer,
+                &market,
+                &mint,
+                &recipient,
+                amount,
+            )])?;
+        }
+        Command::CloseCollection {
+            collection,
+            treasury_mints,
+        } => {
+            let market = ctx.collection_market(&collection, &ctx.fetch_collection(&collection)?)?;
+            let treasuries = associated_token_accounts(&collection, &treasury_mints);
+            ctx.send(&[ix::close_collection(
+                &signer,
+                &market,
+                &collection,
+                &signer,
+                &treasuries,
+            )])?;
+        }
+        Command::TrackMarketTreasury { market, mint } => {
+            ctx.send(&[ix::track_market_treasury(&market, &mint)])?;
+        }
+        Command::CloseMarket {
+            market,
+            treasury_mints,
+        } => {
+            let treasuries = associated_token_accounts(&market, &treasury_mints);
+            ctx.send(&[ix::close_market(&signer, &market, &signer, &treasuries)])?;
+        }
+        Command::MigrateGlobalState => {
+            ctx.send(&[ix::migrate_global_state(&signer)])?;
+        }
+        Command::MigrateMarket { market } => {
+            ctx.send(&[ix::migrate_market(&signer, &market)])?;
+        }
+        Command::MigrateCollection { collection } => {
+            // Legacy collections don't decode with the current layout, so
+            // the index is read from the legacy (nonce, index, ..) layout
+            let data = ctx.client.get_account_data(&collection)?;
+            let index = data
+                .get(9..13)
+                .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                .ok_or_else(|| anyhow!("account {} is not a collection", collection))?;
+            let collection_state = Collection {
+                index,
+                ..Collection::default()
+            };
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            ctx.send(&[ix::migrate_collection(&signer, &market, &collection)])?;
+        }
+        Command::CountLegacyBuckets { collection } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            // Legacy buckets don't decode with the current layout, so links
+            // are read from the legacy layout
+            let mut cursor = collection_state.legacy_cursor;
+            let mut num_legacy_buckets = collection_state.num_legacy_buckets;
+            while cursor != Pubkey::default() {
+                let mut legacy_buckets = Vec::with_capacity(LEGACY_COUNT_BATCH_LEN);
+                while cursor != Pubkey::default() && legacy_buckets.len() < LEGACY_COUNT_BATCH_LEN {
+                    let data = ctx.client.get_account_data(&cursor)?;
+                    let legacy =
+                        LegacyNftBucket::deserialize(&mut data.get(8..).unwrap_or_default())
+                            .with_context(|| {
+                                format!("account {} is not a legacy bucket", cursor)
+                            })?;
+                    legacy_buckets.push(cursor);
+                    cursor = if legacy.next_list_item == collection_state.list_tail {
+                        Pubkey::default()
+                    } else {
+                        legacy.next_list_item
+                    };
+                }
+                num_legacy_buckets += legacy_buckets.len() as u32;
+                ctx.send(&[ix::count_legacy_buckets(
+                    &signer,
+                    &market,
+                    &collection,
+                    &legacy_buckets,
+                )])?;
+            }
+            println!("Legacy buckets to migrate: {}", num_legacy_buckets);
+        }
+        Command::MigratePriceModel { price_model } => {
+            // Legacy price models are laid out (nonce, index, market, ..)
+            let data = ctx.client.get_account_data(&price_model)?;
+            let market = data
+                .get(13..45)
+                .map(Pubkey::new)
+                .ok_or_else(|| anyhow!("account {} is not a price model", price_model))?;
+            ctx.send(&[ix::migrate_price_model(&signer, &market, &price_model)])?;
+        }
+        Command::MigrateNftBucket {
+            collection,
+            nft_bucket,
+            price_model,
+        } => {
+            let collection_state = ctx.fetch_collection(&collection)?;
+            let market = ctx.collection_market(&collection, &collection_state)?;
+            let inventory_page = ctx.free_inventory_page(&collection, &collection_state)?;
+            let data = ctx.client.get_account_data(&nft_bucket)?;
+            let legacy = LegacyNftBucket::deserialize(&mut data.get(8..).unwrap_or_default())
+                .with_context(|| format!("account {} is not a legacy bucket", nft_bucket))?;
+            // Either neighbour works once migrated, legacy ones don't decode
+            let prev_migrated = fetch_nft_bucket(&ctx.client, &legacy.prev_list_item).is_ok();
+            let linked_list_item = if prev_migrated {
+                legacy.prev_list_item
+            } else {
+                legacy.next_list_item
+            };
+            ctx.send(&[ix::migrate_nft_bucket(
+                &signer,
+                &market,
+                &collection,
+                &nft_bucket,
+                &legacy.nft_mint,
+                &linked_list_item,
+                &price_model,
+                &inventory_page,
+            )])?;
+        }
+    }
+    Ok(())
+}
+
+impl PaymentArgs {
+    fn debit_account(&self, owner: &Pubkey) -> Pubkey {
+        self.debit_account
+            .unwrap_or_else(|| get_associated_token_address(owner, &self.debit_mint))
+    }
+}
+
+fn listing_accounts(
+    ctx: &Context,
+    listing: &ListingArgs,
+    collection_state: &Collection,
+    prev_list_item: Pubkey,
+    next_list_item: Pubkey,
+) -> Result<ix::ListNft> {
+    let signer = ctx.signer();
+    Ok(ix::ListNft {
+        admin: signer,
+        market: ctx.collection_market(&listing.collection, collection_state)?,
+        collection: listing.collection,
+        prev_list_item,
+        next_list_item,
+        nft_mint: listing.mint,
+        admin_nft_account: listing
+            .nft_account
+            .unwrap_or_else(|| get_associated_token_address(&signer, &listing.mint)),
+        inventory_page: ctx.free_inventory_page(&listing.collection, collection_state)?,
+        price_model: listing.price_model,
+    })
+}
+
+fn associated_token_accounts(owner: &Pubkey, mints: &[Pubkey]) -> Vec<Pubkey> {
+    mints
+        .iter()
+        .map(|mint| get_associated_token_address(owner, mint))
+        .collect()
+}
+
+fn run_show(ctx: &Context, show: Show) -> Result<()> {
+    match show {
+        Show::Global => {
+            display::print_global_state(
+                &find_global_state_address().0,
+                &fetch_global_state(&ctx.client)?,
+            );
+        }
+        Show::Market { address } => {
+            display::print_market(&address, &fetch_market(&ctx.client, &address)?);
+        }
+        Show::Collection { address, listings } => {
+            let collection = ctx.fetch_collection(&address)?;
+            display::print_collection(&address, &collection);
+            if listings {
+                display::print_list(&fetch_list(&ctx.client, &collection)?);
+            }
+        }
+        Show::PriceModel { address } => {
+            display::print_price_model(&address, &fetch_price_model(&ctx.client, &address)?);
+        }
+        Show::Listing { collection, mint } => {
+            let address = ctx.listing_address(&collection, &mint)?;
+            display::print_nft_bucket(&address, &fetch_nft_bucket(&ctx.client, &address)?);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,244 @@
+//! Account fetching and decoding.
+//!
+//! Borsh accounts (`GlobalState`, `Collection`, `MysteryCommit`) decode
+//! through `AccountDeserialize`; zero-copy accounts (`Market`, `NftBucket`,
+//! `PriceModel`, `InventoryPage`) are copied out of the account data after
+//! their discriminator is checked.
+
+use std::collections::HashSet;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, ZeroCopy};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use vibe_market::{
+    Collection, GlobalState, InventoryPage, Market, MysteryCommit, NftBucket, PriceModel,
+};
+
+use crate::pda::{find_global_state_address, find_inventory_page_address};
+use crate::ClientError;
+
+fn invalid<T>() -> ClientError {
+    ClientError::InvalidAccountData(std::any::type_name::<T>())
+}
+
+pub fn decode_account<T: AccountDeserialize>(data: &[u8]) -> Result<T, ClientError> {
+    T::try_deserialize(&mut &data[..]).map_err(|_| invalid::<T>())
+}
+
+pub fn decode_zero_copy<T: ZeroCopy>(data: &[u8]) -> Result<T, ClientError> {
+    let len = 8 + std::mem::size_of::<T>();
+    if data.len() < len || data[..8] != T::discriminator() {
+        return Err(invalid::<T>());
+    }
+    bytemuck::try_from_bytes::<T>(&data[8..len])
+        .copied()
+        .map_err(|_| invalid::<T>())
+}
+
+pub fn fetch_account<T: AccountDeserialize>(
+    client: &RpcClient,
+    address: &Pubkey,
+) -> Result<T, ClientError> {
+    decode_account(&client.get_account_data(address)?)
+}
+
+pub fn fetch_zero_copy<T: ZeroCopy>(client: &RpcClient, address: &Pubkey) -> Result<T, ClientError> {
+    decode_zero_copy(&client.get_account_data(address)?)
+}
+
+pub fn fetch_global_state(client: &RpcClient) -> Result<GlobalState, ClientError> {
+    fetch_account(client, &find_global_state_address().0)
+}
+
+pub fn fetch_market(client: &RpcClient, address: &Pubkey) -> Result<Market, ClientError> {
+    fetch_zero_copy(client, address)
+}
+
+pub fn fetch_collection(client: &RpcClient, address: &Pubkey) -> Result<Collection, ClientError> {
+    fetch_account(client, address)
+}
+
+pub fn fetch_price_model(client: &RpcClient, address: &Pubkey) -> Result<PriceModel, ClientError> {
+    fetch_zero_copy(client, address)
+}
+
+pub fn fetch_nft_bucket(client: &RpcClient, address: &Pubkey) -> Result<NftBucket, ClientError> {
+    fetch_zero_copy(client, address)
+}
+
+pub fn fetch_mystery_commit(
+    client: &RpcClient,
+    address: &Pubkey,
+) -> Result<MysteryCommit, ClientError> {
+    fetch_account(client, address)
+}
+
+/// Fetches every inventory page of a collection in index order, batching
+/// requests at the RPC's limit of 100 accounts per call.
+pub fn fetch_inventory_pages(
+    client: &RpcClient,
+    collection: &Pubkey,
+    collection_state: &Collection,
+) -> Result<Vec<(Pubkey, InventoryPage)>, ClientError> {
+    let keys: Vec<Pubkey> = (0..collection_state.num_inventory_pages)
+        .map(|index| find_inventory_page_address(collection, index).0)
+        .collect();
+    let mut pages = Vec::with_capacity(keys.len());
+    for chunk in keys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        for (key, account) in chunk.iter().zip(client.get_multiple_accounts(chunk)?) {
+            let account = account.ok_or_else(invalid::<InventoryPage>)?;
+            pages.push((*key, decode_zero_copy(&account.data)?));
+        }
+    }
+    Ok(pages)
+}
+
+/// A bucket linked into a collection's list, between the head and tail.
+#[derive(Clone, Copy)]
+pub struct ListItem {
+    pub address: Pubkey,
+    pub bucket: NftBucket,
+}
+
+impl ListItem {
+    pub fn is_tombstoned(&self) -> bool {
+        self.bucket.tombstoned != 0
+    }
+}
+
+/// Walks a collection's list from `list_head` to `list_tail`, loading each
+/// bucket with `load`. Every back pointer is checked against the walk, so a
+/// corrupt or cyclic list fails with `ClientError::BrokenList` at the first
+/// bucket that disagrees. Tombstoned buckets awaiting compaction are
+/// included.
+pub fn walk_list<F>(collection: &Collection, mut load: F) -> Result<Vec<ListItem>, ClientError>
+where
+    F: FnMut(&Pubkey) -> Result<NftBucket, ClientError>,
+{
+    let mut items = Vec::new();
+    let mut visited = HashSet::new();
+    let mut prev = collection.list_head;
+    let mut next = load(&collection.list_head)?.next_list_item;
+
+    while next != collection.list_tail {
+        if !visited.insert(next) {
+            return Err(ClientError::BrokenList(next));
+        }
+        let bucket = load(&next)?;
+        if bucket.prev_list_item != prev {
+            return Err(ClientError::BrokenList(next));
+        }
+        items.push(ListItem { address: next, bucket });
+        prev = next;
+        next = bucket.next_list_item;
+    }
+
+    if load(&collection.list_tail)?.prev_list_item != prev {
+        return Err(ClientError::BrokenList(collection.list_tail));
+    }
+    Ok(items)
+}
+
+/// Fetches a collection's list in display order, one bucket per request.
+pub fn fetch_list(client: &RpcClient, collection: &Collection) -> Result<Vec<ListItem>, ClientError> {
+    walk_list(collection, |address| fetch_nft_bucket(client, address))
+}
+
+/// Fetches the buckets of a collection that are currently for sale, in
+/// display order.
+pub fn fetch_listings(
+    client: &RpcClient,
+    collection: &Collection,
+) -> Result<Vec<ListItem>, ClientError> {
+    let mut items = fetch_list(client, collection)?;
+    items.retain(|item| !item.is_tombstoned());
+    Ok(items)
+}
+
+/// Decodes a fixed-size string field such as `Market::title`.
+pub fn fixed_str(bytes: &[u8], len: usize) -> String {
+    String::from_utf8_lossy(&bytes[..len.min(bytes.len())]).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+    use std::collections::HashMap;
+
+    fn bucket(prev_list_item: Pubkey, next_list_item: Pubkey) -> NftBucket {
+        let mut bucket: NftBucket = bytemuck::Zeroable::zeroed();
+        bucket.prev_list_item = prev_list_item;
+        bucket.next_list_item = next_list_item;
+        bucket
+    }
+
+    fn collection(list_head: Pubkey, list_tail: Pubkey) -> Collection {
+        Collection {
+            list_head,
+            list_tail,
+            ..Collection::default()
+        }
+    }
+
+    #[test]
+    fn zero_copy_accounts_roundtrip() {
+        let mut nft_bucket = bucket(Pubkey::new_unique(), Pubkey::new_unique());
+        nft_bucket.nft_mint = Pubkey::new_unique();
+
+        let mut data = NftBucket::discriminator().to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(&nft_bucket));
+        let decoded: NftBucket = decode_zero_copy(&data).unwrap();
+        assert_eq!(bytemuck::bytes_of(&decoded), bytemuck::bytes_of(&nft_bucket));
+
+        // The discriminator keeps one zero-copy account from decoding as another
+        assert!(decode_zero_copy::<PriceModel>(&data).is_err());
+        assert!(decode_zero_copy::<NftBucket>(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn walk_list_follows_links_in_order() {
+        let (head, a, b, tail) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut buckets = HashMap::new();
+        buckets.insert(head, bucket(Pubkey::default(), a));
+        buckets.insert(a, bucket(head, b));
+        buckets.insert(b, bucket(a, tail));
+        buckets.insert(tail, bucket(b, Pubkey::default()));
+
+        let items = walk_list(&collection(head, tail), |key| {
+            buckets.get(key).copied().ok_or_else(invalid::<NftBucket>)
+        })
+        .unwrap();
+        let order: Vec<Pubkey> = items.iter().map(|item| item.address).collect();
+        assert_eq!(order, vec![a, b]);
+    }
+
+    #[test]
+    fn walk_list_reports_first_broken_link() {
+        let (head, a, b, tail) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut buckets = HashMap::new();
+        buckets.insert(head, bucket(Pubkey::default(), a));
+        buckets.insert(a, bucket(head, b));
+        // b's back pointer skips a
+        buckets.insert(b, bucket(head, tail));
+        buckets.insert(tail, bucket(b, Pubkey::default()));
+
+        let err = walk_list(&collection(head, tail), |key| {
+            buckets.get(key).copied().ok_or_else(invalid::<NftBucket>)
+        })
+        .err()
+        .unwrap();
+        assert!(matches!(err, ClientError::BrokenList(key) if key == b));
+    }
+}
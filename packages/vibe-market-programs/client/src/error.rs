@@ -0,0 +1,19 @@
+use anchor_lang::prelude::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    // Boxed, the RPC error is several times larger than the other variants
+    #[error("rpc request failed: {0}")]
+    Rpc(Box<solana_client::client_error::ClientError>),
+    #[error("account data is not a valid {0}")]
+    InvalidAccountData(&'static str),
+    #[error("collection list is broken at {0}")]
+    BrokenList(Pubkey),
+}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(error: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(Box::new(error))
+    }
+}
@@ -0,0 +1,1065 @@
+//! Instruction builders for every program instruction.
+//!
+//! Builders derive PDAs and associated token accounts from their inputs, so
+//! callers only supply the keys the program cannot derive. Where an
+//! instruction needs values stored on an existing account (a bucket's payer,
+//! a collection's list head) the decoded account is passed in directly.
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::{system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token;
+use vibe_market::{
+    accounts, instruction, Collection, GlobalState, Market, MarketCreation, Metadata,
+    MysteryCommit, NftBucket, SalePrice,
+};
+
+use crate::pda::*;
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: vibe_market::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn with_remaining_accounts(mut ix: Instruction, keys: &[Pubkey], is_writable: bool) -> Instruction {
+    ix.accounts.extend(keys.iter().map(|key| {
+        if is_writable {
+            AccountMeta::new(*key, false)
+        } else {
+            AccountMeta::new_readonly(*key, false)
+        }
+    }));
+    ix
+}
+
+pub fn init_global_state(admin: &Pubkey) -> Instruction {
+    build(
+        accounts::InitGlobalState {
+            admin: *admin,
+            program_data: find_program_data_address().0,
+            global_state: find_global_state_address().0,
+            system_program: system_program::ID,
+        },
+        instruction::InitGlobalState {},
+    )
+}
+
+pub fn update_protocol_fee(
+    authority: &Pubkey,
+    fee_recipient: &Pubkey,
+    fee_basis_points: u16,
+) -> Instruction {
+    build(
+        accounts::UpdateProtocolFee {
+            authority: *authority,
+            global_state: find_global_state_address().0,
+            fee_recipient: *fee_recipient,
+        },
+        instruction::UpdateProtocolFee { fee_basis_points },
+    )
+}
+
+pub fn set_global_authority(authority: &Pubkey, new_authority: &Pubkey) -> Instruction {
+    build(
+        accounts::SetGlobalAuthority {
+            authority: *authority,
+            global_state: find_global_state_address().0,
+            new_authority: *new_authority,
+        },
+        instruction::SetGlobalAuthority {},
+    )
+}
+
+pub fn update_market_creation(
+    authority: &Pubkey,
+    market_creation: MarketCreation,
+    market_creation_fee: u64,
+) -> Instruction {
+    build(
+        accounts::UpdateMarketCreation {
+            authority: *authority,
+            global_state: find_global_state_address().0,
+        },
+        instruction::UpdateMarketCreation {
+            market_creation,
+            market_creation_fee,
+        },
+    )
+}
+
+/// Creates the next market, whose index is `global_state.num_markets`.
+pub fn init_market(
+    admin: &Pubkey,
+    global_state: &GlobalState,
+    whitelist: Vec<Pubkey>,
+    title: String,
+) -> Instruction {
+    let global_state_key = find_global_state_address().0;
+    build(
+        accounts::InitMarket {
+            admin: *admin,
+            global_state: global_state_key,
+            fee_recipient: global_state.fee_recipient,
+            market: find_market_address(&global_state_key, global_state.num_markets).0,
+            system_program: system_program::ID,
+        },
+        instruction::InitMarket { whitelist, title },
+    )
+}
+
+pub fn add_admin(admin: &Pubkey, market: &Pubkey, add_admin: &Pubkey) -> Instruction {
+    build(
+        accounts::AddAdmin {
+            admin: *admin,
+            market: *market,
+            add_admin: *add_admin,
+        },
+        instruction::AddAdmin {},
+    )
+}
+
+pub fn remove_admin(admin: &Pubkey, market: &Pubkey, remove_admin: &Pubkey) -> Instruction {
+    build(
+        accounts::RemoveAdmin {
+            admin: *admin,
+            market: *market,
+            remove_admin: *remove_admin,
+        },
+        instruction::RemoveAdmin {},
+    )
+}
+
+pub fn set_market_paused(admin: &Pubkey, market: &Pubkey, paused: bool) -> Instruction {
+    build(
+        accounts::SetMarketPaused {
+            admin: *admin,
+            market: *market,
+        },
+        instruction::SetMarketPaused { paused },
+    )
+}
+
+pub fn update_market_metadata(
+    admin: &Pubkey,
+    market: &Pubkey,
+    title: String,
+    metadata: Metadata,
+) -> Instruction {
+    build(
+        accounts::UpdateMarketMetadata {
+            admin: *admin,
+            market: *market,
+        },
+        instruction::UpdateMarketMetadata { title, metadata },
+    )
+}
+
+/// Creates the next collection of `market`, whose index is
+/// `market_state.num_collections`.
+pub fn init_collection(
+    admin: &Pubkey,
+    market: &Pubkey,
+    market_state: &Market,
+    title: String,
+) -> Instruction {
+    let collection = find_collection_address(market, market_state.num_collections).0;
+    build(
+        accounts::InitCollection {
+            admin: *admin,
+            market: *market,
+            collection,
+            list_head: find_list_head_address(&collection).0,
+            list_tail: find_list_tail_address(&collection).0,
+            system_program: system_program::ID,
+        },
+        instruction::InitCollection { title },
+    )
+}
+
+pub fn set_collection_paused(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    paused: bool,
+) -> Instruction {
+    build(
+        accounts::SetCollectionPaused {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+        },
+        instruction::SetCollectionPaused { paused },
+    )
+}
+
+pub fn set_collection_sale_window(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    sale_start: Option<i64>,
+    sale_end: Option<i64>,
+) -> Instruction {
+    build(
+        accounts::SetCollectionSaleWindow {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+        },
+        instruction::SetCollectionSaleWindow { sale_start, sale_end },
+    )
+}
+
+pub fn set_nft_sale_window(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    list_item: &Pubkey,
+    list_item_state: &NftBucket,
+    sale_start: Option<i64>,
+    sale_end: Option<i64>,
+) -> Instruction {
+    build(
+        accounts::SetNftSaleWindow {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            list_item: *list_item,
+            inventory_page: list_item_state.inventory_page,
+        },
+        instruction::SetNftSaleWindow { sale_start, sale_end },
+    )
+}
+
+pub fn set_nft_expiry(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    list_item: &Pubkey,
+    list_item_state: &NftBucket,
+    expires_at: Option<i64>,
+) -> Instruction {
+    build(
+        accounts::SetNftExpiry {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            list_item: *list_item,
+            inventory_page: list_item_state.inventory_page,
+        },
+        instruction::SetNftExpiry { expires_at },
+    )
+}
+
+pub fn set_mystery_price_model(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    price_model: &Pubkey,
+    enabled: bool,
+) -> Instruction {
+    build(
+        accounts::SetMysteryPriceModel {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            price_model: *price_model,
+        },
+        instruction::SetMysteryPriceModel { enabled },
+    )
+}
+
+pub fn update_collection_metadata(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    title: String,
+    metadata: Metadata,
+) -> Instruction {
+    build(
+        accounts::UpdateCollectionMetadata {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::UpdateCollectionMetadata { title, metadata },
+    )
+}
+
+/// Creates the next inventory page of `collection`, whose index is
+/// `collection_state.num_inventory_pages`.
+pub fn init_inventory_page(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    collection_state: &Collection,
+) -> Instruction {
+    build(
+        accounts::InitInventoryPage {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            inventory_page: find_inventory_page_address(
+                collection,
+                collection_state.num_inventory_pages,
+            )
+            .0,
+            system_program: system_program::ID,
+        },
+        instruction::InitInventoryPage {},
+    )
+}
+
+/// Closes page `page_index`, which must be the collection's last page.
+pub fn close_inventory_page(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    page_index: u32,
+) -> Instruction {
+    build(
+        accounts::CloseInventoryPage {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            inventory_page: find_inventory_page_address(collection, page_index).0,
+        },
+        instruction::CloseInventoryPage {},
+    )
+}
+
+/// Creates the next price model of `market`, whose index is
+/// `market_state.num_price_models`.
+pub fn init_price_model(
+    admin: &Pubkey,
+    market: &Pubkey,
+    market_state: &Market,
+    sale_prices: Vec<SalePrice>,
+) -> Instruction {
+    build(
+        accounts::InitPriceModel {
+            admin: *admin,
+            market: *market,
+            price_model: find_price_model_address(market, market_state.num_price_models).0,
+            system_program: system_program::ID,
+        },
+        instruction::InitPriceModel { sale_prices },
+    )
+}
+
+pub fn close_price_model(admin: &Pubkey, market: &Pubkey, price_model: &Pubkey) -> Instruction {
+    build(
+        accounts::ClosePriceModel {
+            admin: *admin,
+            market: *market,
+            price_model: *price_model,
+        },
+        instruction::ClosePriceModel {},
+    )
+}
+
+/// Accounts shared by `add_nft` and `insert_nft_after`.
+#[derive(Clone, Debug)]
+pub struct ListNft {
+    pub admin: Pubkey,
+    pub market: Pubkey,
+    pub collection: Pubkey,
+    pub prev_list_item: Pubkey,
+    pub next_list_item: Pubkey,
+    pub nft_mint: Pubkey,
+    /// Token account the NFT is transferred from.
+    pub admin_nft_account: Pubkey,
+    pub inventory_page: Pubkey,
+    pub price_model: Pubkey,
+}
+
+impl ListNft {
+    fn to_accounts(&self) -> accounts::AddNft {
+        accounts::AddNft {
+            admin: self.admin,
+            market: self.market,
+            collection: self.collection,
+            prev_list_item: self.prev_list_item,
+            next_list_item: self.next_list_item,
+            admin_nft_account: self.admin_nft_account,
+            admin_nft_mint: self.nft_mint,
+            new_item: find_nft_bucket_address(&self.collection, &self.nft_mint).0,
+            inventory_page: self.inventory_page,
+            price_model: self.price_model,
+            program_nft_account: get_associated_token_address(&self.collection, &self.nft_mint),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        }
+    }
+}
+
+/// Lists an NFT directly after the list head. `prev_list_item` must be the
+/// collection's `list_head` and `next_list_item` the head's successor.
+pub fn add_nft(args: &ListNft) -> Instruction {
+    build(args.to_accounts(), instruction::AddNft {})
+}
+
+/// Lists an NFT directly after `args.prev_list_item`.
+pub fn insert_nft_after(args: &ListNft) -> Instruction {
+    build(args.to_accounts(), instruction::InsertNftAfter {})
+}
+
+/// Moves `list_item` between `new_prev_list_item` and `new_next_list_item`.
+pub fn move_item(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    list_item: &Pubkey,
+    list_item_state: &NftBucket,
+    new_prev_list_item: &Pubkey,
+    new_next_list_item: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::MoveItem {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            list_item: *list_item,
+            prev_list_item: list_item_state.prev_list_item,
+            next_list_item: list_item_state.next_list_item,
+            new_prev_list_item: *new_prev_list_item,
+            new_next_list_item: *new_next_list_item,
+        },
+        instruction::MoveItem {},
+    )
+}
+
+/// Pages through a collection's list from `cursor`. `list_items` must follow
+/// `cursor` in list order; the page is returned as `ListPageData`.
+pub fn list_page(
+    collection: &Pubkey,
+    cursor: &Pubkey,
+    list_items: &[Pubkey],
+    limit: u8,
+) -> Instruction {
+    let ix = build(
+        accounts::ListPage {
+            collection: *collection,
+            cursor: *cursor,
+        },
+        instruction::ListPage { limit },
+    );
+    with_remaining_accounts(ix, list_items, false)
+}
+
+/// Checks the links of `list_items`, which must follow `cursor` in list
+/// order; the result is returned as `ListVerification`.
+pub fn verify_collection(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    cursor: &Pubkey,
+    list_items: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::VerifyCollection {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            cursor: *cursor,
+        },
+        instruction::VerifyCollection {},
+    );
+    with_remaining_accounts(ix, list_items, false)
+}
+
+/// Links `prev_list_item` directly to `next_list_item`. `skipped_list_items`
+/// are the items dropped from between them, following next pointers from
+/// `prev_list_item`, up to the first one that is not a bucket of the
+/// collection.
+pub fn repair_link(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    prev_list_item: &Pubkey,
+    next_list_item: &Pubkey,
+    skipped_list_items: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::RepairLink {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            prev_list_item: *prev_list_item,
+            next_list_item: *next_list_item,
+        },
+        instruction::RepairLink {},
+    );
+    with_remaining_accounts(ix, skipped_list_items, false)
+}
+
+pub fn withdraw_nft(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    list_item: &Pubkey,
+    list_item_state: &NftBucket,
+) -> Instruction {
+    let nft_mint = list_item_state.nft_mint;
+    build(
+        accounts::WithdrawNft {
+            admin: *admin,
+            rent_refund: list_item_state.payer,
+            price_model: list_item_state.price_model,
+            market: *market,
+            collection: *collection,
+            withdraw_list_item: *list_item,
+            inventory_page: list_item_state.inventory_page,
+            program_nft_account: get_associated_token_address(collection, &nft_mint),
+            program_nft_mint: nft_mint,
+            admin_nft_account: get_associated_token_address(admin, &nft_mint),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::WithdrawNft {},
+    )
+}
+
+pub fn crank_expired(
+    cranker: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    list_item: &Pubkey,
+    list_item_state: &NftBucket,
+) -> Instruction {
+    let nft_mint = list_item_state.nft_mint;
+    build(
+        accounts::CrankExpired {
+            cranker: *cranker,
+            rent_refund: list_item_state.payer,
+            price_model: list_item_state.price_model,
+            market: *market,
+            collection: *collection,
+            expired_list_item: *list_item,
+            prev_list_item: list_item_state.prev_list_item,
+            next_list_item: list_item_state.next_list_item,
+            inventory_page: list_item_state.inventory_page,
+            program_nft_account: get_associated_token_address(collection, &nft_mint),
+            program_nft_mint: nft_mint,
+            payer_nft_account: get_associated_token_address(&list_item_state.payer, &nft_mint),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CrankExpired {},
+    )
+}
+
+/// Quotes `list_item` in every accepted mint, returned as `Vec<PriceQuote>`.
+pub fn quote_purchase(
+    market: &Pubkey,
+    collection: &Pubkey,
+    list_item: &Pubkey,
+    list_item_state: &NftBucket,
+) -> Instruction {
+    build(
+        accounts::QuotePurchase {
+            global_state: find_global_state_address().0,
+            price_model: list_item_state.price_model,
+            market: *market,
+            collection: *collection,
+            list_item: *list_item,
+        },
+        instruction::QuotePurchase {},
+    )
+}
+
+/// Buys `list_item`, paying in `debit_mint` from `debit_account`.
+#[allow(clippy::too_many_arguments)]
+pub fn purchase_nft(
+    owner: &Pubkey,
+    global_state: &GlobalState,
+    market: &Pubkey,
+    collection: &Pubkey,
+    list_item: &Pubkey,
+    list_item_state: &NftBucket,
+    debit_mint: &Pubkey,
+    debit_account: &Pubkey,
+) -> Instruction {
+    let nft_mint = list_item_state.nft_mint;
+    build(
+        accounts::PurchaseNft {
+            owner: *owner,
+            rent_refund: list_item_state.payer,
+            global_state: find_global_state_address().0,
+            price_model: list_item_state.price_model,
+            market: *market,
+            collection: *collection,
+            purchase_list_item: *list_item,
+            inventory_page: list_item_state.inventory_page,
+            debit_mint: *debit_mint,
+            debit_account: *debit_account,
+            program_credit_account: get_associated_token_address(collection, debit_mint),
+            fee_recipient: global_state.fee_recipient,
+            fee_credit_account: get_associated_token_address(
+                &global_state.fee_recipient,
+                debit_mint,
+            ),
+            program_nft_account: get_associated_token_address(collection, &nft_mint),
+            program_nft_mint: nft_mint,
+            owner_nft_account: get_associated_token_address(owner, &nft_mint),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::PurchaseNft {},
+    )
+}
+
+/// Pays for a mystery box draw, revealed from the hash of the following slot.
+#[allow(clippy::too_many_arguments)]
+pub fn commit_random_purchase(
+    owner: &Pubkey,
+    global_state: &GlobalState,
+    market: &Pubkey,
+    collection: &Pubkey,
+    price_model: &Pubkey,
+    debit_mint: &Pubkey,
+    debit_account: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::CommitRandomPurchase {
+            owner: *owner,
+            global_state: find_global_state_address().0,
+            market: *market,
+            collection: *collection,
+            price_model: *price_model,
+            mystery_commit: find_mystery_commit_address(collection, owner).0,
+            debit_mint: *debit_mint,
+            debit_account: *debit_account,
+            program_credit_account: get_associated_token_address(collection, debit_mint),
+            fee_recipient: global_state.fee_recipient,
+            fee_credit_account: get_associated_token_address(
+                &global_state.fee_recipient,
+                debit_mint,
+            ),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::CommitRandomPurchase {},
+    )
+}
+
+/// Reveals `owner`'s mystery box draw, any `cranker` can submit it. The drawn
+/// bucket must be resolved off-chain from the same slot hash and inventory
+/// pages before building this instruction; `inventory_pages` lists every
+/// page of the collection in index order.
+pub fn reveal_random_purchase(
+    cranker: &Pubkey,
+    owner: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    list_item: &Pubkey,
+    list_item_state: &NftBucket,
+    inventory_pages: &[Pubkey],
+) -> Instruction {
+    let nft_mint = list_item_state.nft_mint;
+    let ix = build(
+        accounts::RevealRandomPurchase {
+            cranker: *cranker,
+            owner: *owner,
+            rent_refund: list_item_state.payer,
+            price_model: list_item_state.price_model,
+            market: *market,
+            collection: *collection,
+            mystery_commit: find_mystery_commit_address(collection, owner).0,
+            purchase_list_item: *list_item,
+            program_nft_account: get_associated_token_address(collection, &nft_mint),
+            program_nft_mint: nft_mint,
+            owner_nft_account: get_associated_token_address(owner, &nft_mint),
+            slot_hashes: sysvar::slot_hashes::ID,
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::RevealRandomPurchase {},
+    );
+    with_remaining_accounts(ix, inventory_pages, true)
+}
+
+/// Refunds a mystery box draw that can't be delivered, either because it was
+/// voided after the commit or because nothing could be drawn.
+pub fn refund_random_purchase(
+    owner: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    mystery_commit_state: &MysteryCommit,
+    debit_account: &Pubkey,
+    inventory_pages: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::RefundRandomPurchase {
+            owner: *owner,
+            market: *market,
+            collection: *collection,
+            mystery_commit: find_mystery_commit_address(collection, owner).0,
+            debit_account: *debit_account,
+            program_credit_account: get_associated_token_address(
+                collection,
+                &mystery_commit_state.debit_mint,
+            ),
+            slot_hashes: sysvar::slot_hashes::ID,
+            token_program: token::ID,
+        },
+        instruction::RefundRandomPurchase {},
+    );
+    with_remaining_accounts(ix, inventory_pages, false)
+}
+
+/// Closes `owner`'s mystery box draw once its reveal window has elapsed,
+/// forfeiting the payment. Any account can submit it.
+pub fn expire_random_purchase(
+    owner: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    inventory_pages: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::ExpireRandomPurchase {
+            owner: *owner,
+            market: *market,
+            collection: *collection,
+            mystery_commit: find_mystery_commit_address(collection, owner).0,
+            slot_hashes: sysvar::slot_hashes::ID,
+        },
+        instruction::ExpireRandomPurchase {},
+    );
+    with_remaining_accounts(ix, inventory_pages, false)
+}
+
+pub fn compact_list_item(list_item: &Pubkey, list_item_state: &NftBucket) -> Instruction {
+    build(
+        accounts::CompactListItem {
+            rent_refund: list_item_state.payer,
+            list_item: *list_item,
+            prev_list_item: list_item_state.prev_list_item,
+            next_list_item: list_item_state.next_list_item,
+        },
+        instruction::CompactListItem {},
+    )
+}
+
+pub fn withdraw_liquidity(
+    admin: &Pubkey,
+    market: &Pubkey,
+    withdraw_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::WithdrawLiquidity {
+            admin: *admin,
+            global_state: find_global_state_address().0,
+            market: *market,
+            withdraw_mint: *withdraw_mint,
+            program_debit_account: get_associated_token_address(market, withdraw_mint),
+            admin_credit_account: get_associated_token_address(admin, withdraw_mint),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::WithdrawLiquidity { amount },
+    )
+}
+
+pub fn withdraw_collection_liquidity(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    withdraw_mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::WithdrawCollectionLiquidity {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            withdraw_mint: *withdraw_mint,
+            program_debit_account: get_associated_token_address(collection, withdraw_mint),
+            admin_credit_account: get_associated_token_address(admin, withdraw_mint),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::WithdrawCollectionLiquidity { amount },
+    )
+}
+
+/// Lists an NFT already held by the collection's associated token account
+/// directly after `prev_list_item`, whose successor is `next_list_item`.
+/// `inventory_pages` is every page of the collection, in index order.
+#[allow(clippy::too_many_arguments)]
+pub fn adopt_nft(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    prev_list_item: &Pubkey,
+    next_list_item: &Pubkey,
+    nft_mint: &Pubkey,
+    inventory_page: &Pubkey,
+    price_model: &Pubkey,
+    inventory_pages: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::AdoptNft {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            prev_list_item: *prev_list_item,
+            next_list_item: *next_list_item,
+            program_nft_account: get_associated_token_address(collection, nft_mint),
+            nft_mint: *nft_mint,
+            new_item: find_nft_bucket_address(collection, nft_mint).0,
+            inventory_page: *inventory_page,
+            price_model: *price_model,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::AdoptNft {},
+    );
+    with_remaining_accounts(ix, inventory_pages, false)
+}
+
+/// Sends `amount` of `mint` held by the collection to `recipient`'s
+/// associated token account. `inventory_pages` is every page of the
+/// collection, in index order.
+pub fn return_collection_tokens(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    inventory_pages: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::ReturnCollectionTokens {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            mint: *mint,
+            nft_bucket: find_nft_bucket_address(collection, mint).0,
+            program_debit_account: get_associated_token_address(collection, mint),
+            recipient: *recipient,
+            recipient_credit_account: get_associated_token_address(recipient, mint),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::ReturnCollectionTokens { amount },
+    );
+    with_remaining_accounts(ix, inventory_pages, false)
+}
+
+/// Sends `amount` of `mint` held by the market to `recipient`'s associated
+/// token account.
+pub fn return_market_tokens(
+    admin: &Pubkey,
+    market: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::ReturnMarketTokens {
+            admin: *admin,
+            global_state: find_global_state_address().0,
+            market: *market,
+            mint: *mint,
+            program_debit_account: get_associated_token_address(market, mint),
+            recipient: *recipient,
+            recipient_credit_account: get_associated_token_address(recipient, mint),
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::ReturnMarketTokens { amount },
+    )
+}
+
+/// Tracks the market's ATA for `mint` so `close_market` requires it. Anyone
+/// can send this.
+pub fn track_market_treasury(market: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        accounts::TrackMarketTreasury {
+            global_state: find_global_state_address().0,
+            market: *market,
+            treasury: get_associated_token_address(market, mint),
+            token_program: token::ID,
+        },
+        instruction::TrackMarketTreasury {},
+    )
+}
+
+/// Closes an empty collection. `rent_refund` must be the payer recorded on
+/// the list head and sign; `treasury_accounts` are the collection's empty
+/// treasury ATAs to close alongside it, which must include every treasury
+/// the collection has been paid into.
+pub fn close_collection(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    rent_refund: &Pubkey,
+    treasury_accounts: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::CloseCollection {
+            admin: *admin,
+            market: *market,
+            rent_refund: *rent_refund,
+            collection: *collection,
+            list_head: find_list_head_address(collection).0,
+            list_tail: find_list_tail_address(collection).0,
+            token_program: token::ID,
+        },
+        instruction::CloseCollection {},
+    );
+    with_remaining_accounts(ix, treasury_accounts, true)
+}
+
+/// Closes a market with no open collections or price models, along with its
+/// empty `treasury_accounts`, which must include every tracked treasury.
+pub fn close_market(
+    admin: &Pubkey,
+    market: &Pubkey,
+    rent_refund: &Pubkey,
+    treasury_accounts: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::CloseMarket {
+            admin: *admin,
+            rent_refund: *rent_refund,
+            global_state: find_global_state_address().0,
+            market: *market,
+            token_program: token::ID,
+        },
+        instruction::CloseMarket {},
+    );
+    with_remaining_accounts(ix, treasury_accounts, true)
+}
+
+pub fn migrate_global_state(admin: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateGlobalState {
+            admin: *admin,
+            program_data: find_program_data_address().0,
+            global_state: find_global_state_address().0,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MigrateGlobalState {},
+    )
+}
+
+pub fn migrate_market(admin: &Pubkey, market: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateMarket {
+            admin: *admin,
+            market: *market,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MigrateMarket {},
+    )
+}
+
+pub fn migrate_collection(admin: &Pubkey, market: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateCollection {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            list_head: find_list_head_address(collection).0,
+            list_tail: find_list_tail_address(collection).0,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MigrateCollection {},
+    )
+}
+
+/// Counts the legacy buckets from the collection's `legacy_cursor`,
+/// `legacy_buckets` following it in list order.
+pub fn count_legacy_buckets(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    legacy_buckets: &[Pubkey],
+) -> Instruction {
+    let ix = build(
+        accounts::CountLegacyBuckets {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+        },
+        instruction::CountLegacyBuckets {},
+    );
+    with_remaining_accounts(ix, legacy_buckets, false)
+}
+
+pub fn migrate_price_model(admin: &Pubkey, market: &Pubkey, price_model: &Pubkey) -> Instruction {
+    build(
+        accounts::MigratePriceModel {
+            admin: *admin,
+            market: *market,
+            price_model: *price_model,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MigratePriceModel {},
+    )
+}
+
+/// Migrates a legacy bucket, indexing it into `inventory_page`. The
+/// collection must hold `nft_mint`, and `linked_list_item` is a migrated
+/// neighbour of the bucket that links back to it.
+#[allow(clippy::too_many_arguments)]
+pub fn migrate_nft_bucket(
+    admin: &Pubkey,
+    market: &Pubkey,
+    collection: &Pubkey,
+    nft_bucket: &Pubkey,
+    nft_mint: &Pubkey,
+    linked_list_item: &Pubkey,
+    price_model: &Pubkey,
+    inventory_page: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::MigrateNftBucket {
+            admin: *admin,
+            market: *market,
+            collection: *collection,
+            nft_bucket: *nft_bucket,
+            program_nft_account: get_associated_token_address(collection, nft_mint),
+            linked_list_item: *linked_list_item,
+            price_model: *price_model,
+            inventory_page: *inventory_page,
+            system_program: system_program::ID,
+            rent: sysvar::rent::ID,
+        },
+        instruction::MigrateNftBucket {},
+    )
+}
@@ -0,0 +1,16 @@
+//! Rust client for the Vibe Market program.
+//!
+//! * [`pda`] derives program addresses from the same seeds as the program
+//! * [`instruction`] builds instructions, deriving every account it can
+//! * [`account`] fetches and decodes accounts, including collection lists
+//! * [`mystery`] resolves mystery box draws off-chain
+
+pub mod account;
+pub mod instruction;
+pub mod mystery;
+pub mod pda;
+
+mod error;
+
+pub use error::ClientError;
+pub use vibe_market::ID;
@@ -0,0 +1,106 @@
+//! Off-chain mirror of the mystery box draw, so a cranker can resolve which
+//! bucket a reveal delivers before building `reveal_random_purchase`.
+
+use std::convert::TryInto;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hashv;
+use vibe_market::{InventoryPage, MysteryCommit};
+
+// (slot, hash) entries of the SlotHashes sysvar
+const SLOT_HASH_ENTRY_LEN: usize = 40;
+
+/// Slot hash a reveal of a commit made at `commit_slot` draws from, read from
+/// SlotHashes sysvar data. Returns `None` before the next slot has been
+/// hashed and after it has been evicted from the sysvar.
+pub fn reveal_slot_hash(slot_hashes: &[u8], commit_slot: u64) -> Option<[u8; 32]> {
+    let len = u64::from_le_bytes(slot_hashes.get(..8)?.try_into().ok()?) as usize;
+
+    let mut reveal_hash = None;
+    let mut oldest_slot = u64::MAX;
+    for i in 0..len {
+        let offset = 8 + i * SLOT_HASH_ENTRY_LEN;
+        let entry = slot_hashes.get(offset..offset + SLOT_HASH_ENTRY_LEN)?;
+        oldest_slot = u64::from_le_bytes(entry[..8].try_into().ok()?);
+        if oldest_slot <= commit_slot {
+            break;
+        }
+        reveal_hash = Some(entry[8..].try_into().ok()?);
+    }
+
+    match reveal_hash {
+        Some(_) if oldest_slot > commit_slot + 1 => None,
+        reveal_hash => reveal_hash,
+    }
+}
+
+/// Bucket drawn for `mystery_commit` from the collection's inventory pages,
+/// which must be every page in index order. The draw is made over the buckets
+/// on sale at the commit; `None` means there is nothing to deliver and the
+/// purchase can be refunded instead.
+pub fn select_random_bucket(
+    slot_hash: &[u8; 32],
+    mystery_commit: &Pubkey,
+    mystery_commit_state: &MysteryCommit,
+    inventory_pages: &[InventoryPage],
+) -> Option<Pubkey> {
+    let random_bytes = hashv(&[&slot_hash[..], mystery_commit.as_ref()]).to_bytes();
+    let random = u64::from_le_bytes(random_bytes[..8].try_into().ok()?);
+
+    let version = mystery_commit_state.inventory_version;
+    let timestamp = mystery_commit_state.commit_timestamp;
+    let mut num_drawable = Vec::with_capacity(inventory_pages.len());
+    for page in inventory_pages {
+        let mut page_drawable = 0u64;
+        for slot in page.slots.iter() {
+            // A slot that changed too often since the commit voids the draw
+            if slot.could_be_drawn(version, timestamp)? {
+                page_drawable += 1;
+            }
+        }
+        num_drawable.push(page_drawable);
+    }
+    let total: u64 = num_drawable.iter().sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut target = random % total;
+    for (page, page_drawable) in inventory_pages.iter().zip(num_drawable) {
+        if target >= page_drawable {
+            target -= page_drawable;
+            continue;
+        }
+        return page
+            .slots
+            .iter()
+            .filter(|slot| slot.could_be_drawn(version, timestamp) == Some(true))
+            .map(|slot| slot.nft_bucket)
+            .nth(target as usize)
+            .filter(|nft_bucket| *nft_bucket != Pubkey::default());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot_hashes(slots: &[u64]) -> Vec<u8> {
+        let mut data = (slots.len() as u64).to_le_bytes().to_vec();
+        for slot in slots {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[*slot as u8; 32]);
+        }
+        data
+    }
+
+    #[test]
+    fn reveal_uses_first_slot_after_commit() {
+        let data = slot_hashes(&[12, 11, 10]);
+        assert_eq!(reveal_slot_hash(&data, 10), Some([11; 32]));
+        assert_eq!(reveal_slot_hash(&data, 12), None);
+        // Slot 10 has been evicted, so a commit at slot 9 can only be expired
+        assert_eq!(reveal_slot_hash(&data, 8), None);
+    }
+}
@@ -0,0 +1,62 @@
+//! Program derived addresses, matching the seeds used on-chain.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+
+pub fn find_program_data_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[vibe_market::ID.as_ref()], &bpf_loader_upgradeable::ID)
+}
+
+pub fn find_global_state_address() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"global".as_ref()], &vibe_market::ID)
+}
+
+pub fn find_market_address(global_state: &Pubkey, index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[global_state.as_ref(), &index.to_le_bytes()],
+        &vibe_market::ID,
+    )
+}
+
+pub fn find_collection_address(market: &Pubkey, index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[market.as_ref(), &index.to_le_bytes(), b"collection".as_ref()],
+        &vibe_market::ID,
+    )
+}
+
+pub fn find_list_head_address(collection: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[collection.as_ref(), b"head".as_ref()], &vibe_market::ID)
+}
+
+pub fn find_list_tail_address(collection: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[collection.as_ref(), b"tail".as_ref()], &vibe_market::ID)
+}
+
+pub fn find_inventory_page_address(collection: &Pubkey, index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[collection.as_ref(), &index.to_le_bytes(), b"inventory_page".as_ref()],
+        &vibe_market::ID,
+    )
+}
+
+pub fn find_nft_bucket_address(collection: &Pubkey, nft_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[collection.as_ref(), nft_mint.as_ref(), b"nft_bucket".as_ref()],
+        &vibe_market::ID,
+    )
+}
+
+pub fn find_price_model_address(market: &Pubkey, index: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[market.as_ref(), &index.to_le_bytes(), b"price_model".as_ref()],
+        &vibe_market::ID,
+    )
+}
+
+pub fn find_mystery_commit_address(collection: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[collection.as_ref(), owner.as_ref(), b"mystery_commit".as_ref()],
+        &vibe_market::ID,
+    )
+}
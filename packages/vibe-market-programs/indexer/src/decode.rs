@@ -0,0 +1,673 @@
+//! Instruction decoding.
+//!
+//! Anchor prefixes instruction data with the first 8 bytes of
+//! `sha256("global:<name>")`; the remaining bytes are the borsh encoded
+//! `vibe_market::instruction` struct. Accounts are positional, in the field
+//! order of the instruction's `Accounts` struct. Instructions that don't
+//! change indexed state decode to `None`.
+
+use std::collections::HashMap;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::AnchorDeserialize;
+use anyhow::{anyhow, Context as _, Result};
+use vibe_market::instruction;
+
+const INSTRUCTION_NAMES: &[&str] = &[
+    "init_market",
+    "add_admin",
+    "remove_admin",
+    "set_market_paused",
+    "update_market_metadata",
+    "close_market",
+    "init_collection",
+    "set_collection_paused",
+    "set_collection_sale_window",
+    "set_mystery_price_model",
+    "update_collection_metadata",
+    "close_collection",
+    "init_price_model",
+    "close_price_model",
+    "add_nft",
+    "insert_nft_after",
+    "move_item",
+    "repair_link",
+    "set_nft_sale_window",
+    "set_nft_expiry",
+    "withdraw_nft",
+    "purchase_nft",
+    "crank_expired",
+    "commit_random_purchase",
+    "reveal_random_purchase",
+    "refund_random_purchase",
+    "expire_random_purchase",
+    "compact_list_item",
+    "withdraw_liquidity",
+    "withdraw_collection_liquidity",
+    "adopt_nft",
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum VibeInstruction {
+    InitMarket {
+        market: Pubkey,
+        whitelist: Vec<Pubkey>,
+        title: String,
+    },
+    AddAdmin {
+        market: Pubkey,
+        admin: Pubkey,
+    },
+    RemoveAdmin {
+        market: Pubkey,
+        admin: Pubkey,
+    },
+    SetMarketPaused {
+        market: Pubkey,
+        paused: bool,
+    },
+    UpdateMarketMetadata {
+        market: Pubkey,
+        title: String,
+    },
+    CloseMarket {
+        market: Pubkey,
+    },
+    InitCollection {
+        market: Pubkey,
+        collection: Pubkey,
+        list_head: Pubkey,
+        list_tail: Pubkey,
+        title: String,
+    },
+    SetCollectionPaused {
+        collection: Pubkey,
+        paused: bool,
+    },
+    SetCollectionSaleWindow {
+        collection: Pubkey,
+        sale_start: Option<i64>,
+        sale_end: Option<i64>,
+    },
+    SetMysteryPriceModel {
+        collection: Pubkey,
+        price_model: Pubkey,
+        enabled: bool,
+    },
+    UpdateCollectionMetadata {
+        collection: Pubkey,
+        title: String,
+    },
+    CloseCollection {
+        collection: Pubkey,
+    },
+    InitPriceModel {
+        market: Pubkey,
+        price_model: Pubkey,
+        /// Mint and amount of each sale price
+        sale_prices: Vec<(Pubkey, u64)>,
+    },
+    ClosePriceModel {
+        price_model: Pubkey,
+    },
+    /// Both `add_nft` and `insert_nft_after`
+    ListNft {
+        payer: Pubkey,
+        market: Pubkey,
+        collection: Pubkey,
+        prev_list_item: Pubkey,
+        next_list_item: Pubkey,
+        nft_mint: Pubkey,
+        nft_bucket: Pubkey,
+        price_model: Pubkey,
+    },
+    MoveItem {
+        list_item: Pubkey,
+        prev_list_item: Pubkey,
+        next_list_item: Pubkey,
+        new_prev_list_item: Pubkey,
+        new_next_list_item: Pubkey,
+    },
+    RepairLink {
+        prev_list_item: Pubkey,
+        next_list_item: Pubkey,
+    },
+    SetNftSaleWindow {
+        list_item: Pubkey,
+        sale_start: Option<i64>,
+        sale_end: Option<i64>,
+    },
+    SetNftExpiry {
+        list_item: Pubkey,
+        expires_at: Option<i64>,
+    },
+    WithdrawNft {
+        admin: Pubkey,
+        market: Pubkey,
+        collection: Pubkey,
+        nft_bucket: Pubkey,
+        nft_mint: Pubkey,
+    },
+    PurchaseNft {
+        owner: Pubkey,
+        market: Pubkey,
+        collection: Pubkey,
+        nft_bucket: Pubkey,
+        nft_mint: Pubkey,
+        price_model: Pubkey,
+        debit_mint: Pubkey,
+        debit_account: Pubkey,
+    },
+    CrankExpired {
+        payer: Pubkey,
+        market: Pubkey,
+        collection: Pubkey,
+        nft_bucket: Pubkey,
+        nft_mint: Pubkey,
+        prev_list_item: Pubkey,
+        next_list_item: Pubkey,
+    },
+    CommitRandomPurchase {
+        owner: Pubkey,
+        market: Pubkey,
+        collection: Pubkey,
+        price_model: Pubkey,
+        mystery_commit: Pubkey,
+        debit_mint: Pubkey,
+        debit_account: Pubkey,
+    },
+    RevealRandomPurchase {
+        owner: Pubkey,
+        market: Pubkey,
+        collection: Pubkey,
+        mystery_commit: Pubkey,
+        nft_bucket: Pubkey,
+        nft_mint: Pubkey,
+    },
+    RefundRandomPurchase {
+        owner: Pubkey,
+        market: Pubkey,
+        collection: Pubkey,
+        mystery_commit: Pubkey,
+    },
+    ExpireRandomPurchase {
+        owner: Pubkey,
+        market: Pubkey,
+        collection: Pubkey,
+        mystery_commit: Pubkey,
+    },
+    CompactListItem {
+        list_item: Pubkey,
+        prev_list_item: Pubkey,
+        next_list_item: Pubkey,
+    },
+    /// Both `withdraw_liquidity` and `withdraw_collection_liquidity`
+    WithdrawLiquidity {
+        admin: Pubkey,
+        market: Pubkey,
+        collection: Option<Pubkey>,
+        mint: Pubkey,
+        amount: u64,
+    },
+}
+
+pub struct Decoder {
+    sighashes: HashMap<[u8; 8], &'static str>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        let sighashes = INSTRUCTION_NAMES
+            .iter()
+            .map(|name| {
+                let mut sighash = [0u8; 8];
+                sighash
+                    .copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+                (sighash, *name)
+            })
+            .collect();
+        Decoder { sighashes }
+    }
+
+    /// Decodes one program instruction given its account keys in order.
+    pub fn decode(&self, data: &[u8], accounts: &[Pubkey]) -> Result<Option<VibeInstruction>> {
+        if data.len() < 8 {
+            return Ok(None);
+        }
+        let mut sighash = [0u8; 8];
+        sighash.copy_from_slice(&data[..8]);
+        let name = match self.sighashes.get(&sighash) {
+            Some(name) => *name,
+            None => return Ok(None),
+        };
+        decode_instruction(name, &data[8..], accounts)
+            .with_context(|| format!("failed to decode {}", name))
+            .map(Some)
+    }
+}
+
+fn account(accounts: &[Pubkey], index: usize) -> Result<Pubkey> {
+    accounts
+        .get(index)
+        .copied()
+        .ok_or_else(|| anyhow!("missing account {}", index))
+}
+
+fn decode_instruction(name: &str, args: &[u8], accounts: &[Pubkey]) -> Result<VibeInstruction> {
+    let key = |index| account(accounts, index);
+    let decoded = match name {
+        "init_market" => {
+            let args = instruction::InitMarket::try_from_slice(args)?;
+            VibeInstruction::InitMarket {
+                market: key(3)?,
+                whitelist: args.whitelist,
+                title: args.title,
+            }
+        }
+        "add_admin" => VibeInstruction::AddAdmin {
+            market: key(1)?,
+            admin: key(2)?,
+        },
+        "remove_admin" => VibeInstruction::RemoveAdmin {
+            market: key(1)?,
+            admin: key(2)?,
+        },
+        "set_market_paused" => VibeInstruction::SetMarketPaused {
+            market: key(1)?,
+            paused: instruction::SetMarketPaused::try_from_slice(args)?.paused,
+        },
+        "update_market_metadata" => VibeInstruction::UpdateMarketMetadata {
+            market: key(1)?,
+            title: instruction::UpdateMarketMetadata::try_from_slice(args)?.title,
+        },
+        "close_market" => VibeInstruction::CloseMarket { market: key(3)? },
+        "init_collection" => VibeInstruction::InitCollection {
+            market: key(1)?,
+            collection: key(2)?,
+            list_head: key(3)?,
+            list_tail: key(4)?,
+            title: instruction::InitCollection::try_from_slice(args)?.title,
+        },
+        "set_collection_paused" => VibeInstruction::SetCollectionPaused {
+            collection: key(2)?,
+            paused: instruction::SetCollectionPaused::try_from_slice(args)?.paused,
+        },
+        "set_collection_sale_window" => {
+            let args = instruction::SetCollectionSaleWindow::try_from_slice(args)?;
+            VibeInstruction::SetCollectionSaleWindow {
+                collection: key(2)?,
+                sale_start: args.sale_start,
+                sale_end: args.sale_end,
+            }
+        }
+        "set_mystery_price_model" => VibeInstruction::SetMysteryPriceModel {
+            collection: key(2)?,
+            price_model: key(3)?,
+            enabled: instruction::SetMysteryPriceModel::try_from_slice(args)?.enabled,
+        },
+        "update_collection_metadata" => VibeInstruction::UpdateCollectionMetadata {
+            collection: key(2)?,
+            title: instruction::UpdateCollectionMetadata::try_from_slice(args)?.title,
+        },
+        "close_collection" => VibeInstruction::CloseCollection {
+            collection: key(3)?,
+        },
+        "init_price_model" => VibeInstruction::InitPriceModel {
+            market: key(1)?,
+            price_model: key(2)?,
+            sale_prices: instruction::InitPriceModel::try_from_slice(args)?
+                .sale_prices
+                .iter()
+                .map(|sale_price| (sale_price.mint, sale_price.amount))
+                .collect(),
+        },
+        "close_price_model" => VibeInstruction::ClosePriceModel {
+            price_model: key(2)?,
+        },
+        // adopt_nft keeps the account positions of add_nft
+        "add_nft" | "insert_nft_after" | "adopt_nft" => VibeInstruction::ListNft {
+            payer: key(0)?,
+            market: key(1)?,
+            collection: key(2)?,
+            prev_list_item: key(3)?,
+            next_list_item: key(4)?,
+            nft_mint: key(6)?,
+            nft_bucket: key(7)?,
+            price_model: key(9)?,
+        },
+        "move_item" => VibeInstruction::MoveItem {
+            list_item: key(3)?,
+            prev_list_item: key(4)?,
+            next_list_item: key(5)?,
+            new_prev_list_item: key(6)?,
+            new_next_list_item: key(7)?,
+        },
+        "repair_link" => VibeInstruction::RepairLink {
+            prev_list_item: key(3)?,
+            next_list_item: key(4)?,
+        },
+        "set_nft_sale_window" => {
+            let args = instruction::SetNftSaleWindow::try_from_slice(args)?;
+            VibeInstruction::SetNftSaleWindow {
+                list_item: key(3)?,
+                sale_start: args.sale_start,
+                sale_end: args.sale_end,
+            }
+        }
+        "set_nft_expiry" => VibeInstruction::SetNftExpiry {
+            list_item: key(3)?,
+            expires_at: instruction::SetNftExpiry::try_from_slice(args)?.expires_at,
+        },
+        "withdraw_nft" => VibeInstruction::WithdrawNft {
+            admin: key(0)?,
+            market: key(3)?,
+            collection: key(4)?,
+            nft_bucket: key(5)?,
+            nft_mint: key(8)?,
+        },
+        "purchase_nft" => VibeInstruction::PurchaseNft {
+            owner: key(0)?,
+            market: key(4)?,
+            collection: key(5)?,
+            nft_bucket: key(6)?,
+            nft_mint: key(14)?,
+            price_model: key(3)?,
+            debit_mint: key(8)?,
+            debit_account: key(9)?,
+        },
+        "crank_expired" => VibeInstruction::CrankExpired {
+            payer: key(1)?,
+            market: key(3)?,
+            collection: key(4)?,
+            nft_bucket: key(5)?,
+            nft_mint: key(10)?,
+            prev_list_item: key(6)?,
+            next_list_item: key(7)?,
+        },
+        "commit_random_purchase" => VibeInstruction::CommitRandomPurchase {
+            owner: key(0)?,
+            market: key(2)?,
+            collection: key(3)?,
+            price_model: key(4)?,
+            mystery_commit: key(5)?,
+            debit_mint: key(6)?,
+            debit_account: key(7)?,
+        },
+        "reveal_random_purchase" => VibeInstruction::RevealRandomPurchase {
+            owner: key(1)?,
+            market: key(4)?,
+            collection: key(5)?,
+            mystery_commit: key(6)?,
+            nft_bucket: key(7)?,
+            nft_mint: key(9)?,
+        },
+        "refund_random_purchase" => VibeInstruction::RefundRandomPurchase {
+            owner: key(0)?,
+            market: key(1)?,
+            collection: key(2)?,
+            mystery_commit: key(3)?,
+        },
+        "expire_random_purchase" => VibeInstruction::ExpireRandomPurchase {
+            owner: key(0)?,
+            market: key(1)?,
+            collection: key(2)?,
+            mystery_commit: key(3)?,
+        },
+        "compact_list_item" => VibeInstruction::CompactListItem {
+            list_item: key(1)?,
+            prev_list_item: key(2)?,
+            next_list_item: key(3)?,
+        },
+        "withdraw_liquidity" => VibeInstruction::WithdrawLiquidity {
+            admin: key(0)?,
+            market: key(2)?,
+            collection: None,
+            mint: key(3)?,
+            amount: instruction::WithdrawLiquidity::try_from_slice(args)?.amount,
+        },
+        "withdraw_collection_liquidity" => VibeInstruction::WithdrawLiquidity {
+            admin: key(0)?,
+            market: key(1)?,
+            collection: Some(key(2)?),
+            mint: key(3)?,
+            amount: instruction::WithdrawCollectionLiquidity::try_from_slice(args)?.amount,
+        },
+        _ => unreachable!("{} is not in INSTRUCTION_NAMES", name),
+    };
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::instruction::Instruction;
+    use vibe_market::{GlobalState, NftBucket};
+    use vibe_market_client::instruction::{self as ix, ListNft};
+    use vibe_market_client::pda::{find_mystery_commit_address, find_nft_bucket_address};
+
+    fn decode(instruction: Instruction) -> VibeInstruction {
+        let accounts: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect();
+        Decoder::new()
+            .decode(&instruction.data, &accounts)
+            .unwrap()
+            .unwrap()
+    }
+
+    fn nft_bucket(nft_mint: Pubkey, price_model: Pubkey, payer: Pubkey) -> NftBucket {
+        let mut bucket: NftBucket = bytemuck::Zeroable::zeroed();
+        bucket.nft_mint = nft_mint;
+        bucket.price_model = price_model;
+        bucket.payer = payer;
+        bucket.prev_list_item = Pubkey::new_unique();
+        bucket.next_list_item = Pubkey::new_unique();
+        bucket
+    }
+
+    #[test]
+    fn decodes_listing_lifecycle() {
+        let (admin, market, collection) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (nft_mint, price_model) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let args = ListNft {
+            admin,
+            market,
+            collection,
+            prev_list_item: Pubkey::new_unique(),
+            next_list_item: Pubkey::new_unique(),
+            nft_mint,
+            admin_nft_account: Pubkey::new_unique(),
+            inventory_page: Pubkey::new_unique(),
+            price_model,
+        };
+        let nft_bucket_key = find_nft_bucket_address(&collection, &nft_mint).0;
+        assert_eq!(
+            decode(ix::insert_nft_after(&args)),
+            VibeInstruction::ListNft {
+                payer: admin,
+                market,
+                collection,
+                prev_list_item: args.prev_list_item,
+                next_list_item: args.next_list_item,
+                nft_mint,
+                nft_bucket: nft_bucket_key,
+                price_model,
+            }
+        );
+        assert_eq!(
+            decode(ix::adopt_nft(
+                &admin,
+                &market,
+                &collection,
+                &args.prev_list_item,
+                &args.next_list_item,
+                &nft_mint,
+                &args.inventory_page,
+                &price_model,
+                &[args.inventory_page],
+            )),
+            decode(ix::insert_nft_after(&args))
+        );
+
+        let bucket = nft_bucket(nft_mint, price_model, admin);
+        let (owner, debit_mint, debit_account) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        assert_eq!(
+            decode(ix::purchase_nft(
+                &owner,
+                &GlobalState::default(),
+                &market,
+                &collection,
+                &nft_bucket_key,
+                &bucket,
+                &debit_mint,
+                &debit_account,
+            )),
+            VibeInstruction::PurchaseNft {
+                owner,
+                market,
+                collection,
+                nft_bucket: nft_bucket_key,
+                nft_mint,
+                price_model,
+                debit_mint,
+                debit_account,
+            }
+        );
+        assert_eq!(
+            decode(ix::withdraw_nft(
+                &admin,
+                &market,
+                &collection,
+                &nft_bucket_key,
+                &bucket
+            )),
+            VibeInstruction::WithdrawNft {
+                admin,
+                market,
+                collection,
+                nft_bucket: nft_bucket_key,
+                nft_mint,
+            }
+        );
+        assert_eq!(
+            decode(ix::crank_expired(
+                &owner,
+                &market,
+                &collection,
+                &nft_bucket_key,
+                &bucket
+            )),
+            VibeInstruction::CrankExpired {
+                payer: admin,
+                market,
+                collection,
+                nft_bucket: nft_bucket_key,
+                nft_mint,
+                prev_list_item: bucket.prev_list_item,
+                next_list_item: bucket.next_list_item,
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_mystery_box_and_liquidity() {
+        let (owner, market, collection, price_model) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (debit_mint, debit_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            decode(ix::commit_random_purchase(
+                &owner,
+                &GlobalState::default(),
+                &market,
+                &collection,
+                &price_model,
+                &debit_mint,
+                &debit_account,
+            )),
+            VibeInstruction::CommitRandomPurchase {
+                owner,
+                market,
+                collection,
+                price_model,
+                mystery_commit: find_mystery_commit_address(&collection, &owner).0,
+                debit_mint,
+                debit_account,
+            }
+        );
+        let (cranker, nft_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let list_item = find_nft_bucket_address(&collection, &nft_mint).0;
+        assert_eq!(
+            decode(ix::reveal_random_purchase(
+                &cranker,
+                &owner,
+                &market,
+                &collection,
+                &list_item,
+                &nft_bucket(nft_mint, price_model, cranker),
+                &[Pubkey::new_unique()],
+            )),
+            VibeInstruction::RevealRandomPurchase {
+                owner,
+                market,
+                collection,
+                mystery_commit: find_mystery_commit_address(&collection, &owner).0,
+                nft_bucket: list_item,
+                nft_mint,
+            }
+        );
+        assert_eq!(
+            decode(ix::expire_random_purchase(&owner, &market, &collection, &[])),
+            VibeInstruction::ExpireRandomPurchase {
+                owner,
+                market,
+                collection,
+                mystery_commit: find_mystery_commit_address(&collection, &owner).0,
+            }
+        );
+        assert_eq!(
+            decode(ix::withdraw_collection_liquidity(
+                &owner,
+                &market,
+                &collection,
+                &debit_mint,
+                42
+            )),
+            VibeInstruction::WithdrawLiquidity {
+                admin: owner,
+                market,
+                collection: Some(collection),
+                mint: debit_mint,
+                amount: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn ignores_unindexed_instructions() {
+        let instruction = ix::init_global_state(&Pubkey::new_unique());
+        let accounts: Vec<Pubkey> = instruction
+            .accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert!(Decoder::new()
+            .decode(&instruction.data, &accounts)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,386 @@
+//! Transaction and account sources.
+//!
+//! Transactions are read from a local ledger directory or from a JSON dump of
+//! `getTransaction` responses (`"json"` encoding), and reduced to the program
+//! instructions they executed, CPIs included, in execution order. Failed
+//! transactions are dropped. Account snapshots are read from a dump of
+//! `getProgramAccounts` with `"base64"` encoding.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, bail, Context as _, Result};
+use serde::Deserialize;
+use solana_ledger::blockstore::Blockstore;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_transaction_status::TransactionTokenBalance;
+
+pub struct ProgramInstruction {
+    /// Position among the transaction's program instructions
+    pub index: u32,
+    pub data: Vec<u8>,
+    pub accounts: Vec<Pubkey>,
+}
+
+pub struct ReplayTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub instructions: Vec<ProgramInstruction>,
+    pre_token_balances: HashMap<Pubkey, u64>,
+    post_token_balances: HashMap<Pubkey, u64>,
+}
+
+impl ReplayTransaction {
+    #[cfg(test)]
+    pub fn new(signature: String, slot: u64, instructions: Vec<ProgramInstruction>) -> Self {
+        ReplayTransaction {
+            signature,
+            slot,
+            block_time: None,
+            instructions,
+            pre_token_balances: HashMap::new(),
+            post_token_balances: HashMap::new(),
+        }
+    }
+
+    /// Tokens that left `token_account` over the whole transaction.
+    pub fn token_debit(&self, token_account: &Pubkey) -> Option<u64> {
+        let pre = self.pre_token_balances.get(token_account)?;
+        let post = self.post_token_balances.get(token_account).unwrap_or(&0);
+        pre.checked_sub(*post)
+    }
+}
+
+// Instructions as (program id index, account indices, data)
+type RawInstruction = (u8, Vec<u8>, Vec<u8>);
+
+struct RawTransaction {
+    signature: String,
+    slot: u64,
+    block_time: Option<i64>,
+    account_keys: Vec<Pubkey>,
+    instructions: Vec<RawInstruction>,
+    // Inner instructions by the index of the top level instruction
+    inner_instructions: HashMap<u8, Vec<RawInstruction>>,
+    pre_token_balances: Vec<(u8, String)>,
+    post_token_balances: Vec<(u8, String)>,
+}
+
+impl RawTransaction {
+    fn into_replay(self) -> Result<ReplayTransaction> {
+        let key = |index: u8| {
+            self.account_keys
+                .get(index as usize)
+                .copied()
+                .ok_or_else(|| anyhow!("{}: account index {} out of range", self.signature, index))
+        };
+
+        let mut instructions = Vec::new();
+        for (position, instruction) in self.instructions.iter().enumerate() {
+            let inner = self
+                .inner_instructions
+                .get(&(position as u8))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for (program_id_index, accounts, data) in std::iter::once(instruction).chain(inner) {
+                if key(*program_id_index)? != vibe_market::ID {
+                    continue;
+                }
+                instructions.push(ProgramInstruction {
+                    index: instructions.len() as u32,
+                    data: data.clone(),
+                    accounts: accounts
+                        .iter()
+                        .map(|index| key(*index))
+                        .collect::<Result<_>>()?,
+                });
+            }
+        }
+
+        let balances = |balances: &[(u8, String)]| -> Result<HashMap<Pubkey, u64>> {
+            balances
+                .iter()
+                .map(|(index, amount)| Ok((key(*index)?, amount.parse()?)))
+                .collect()
+        };
+        Ok(ReplayTransaction {
+            pre_token_balances: balances(&self.pre_token_balances)?,
+            post_token_balances: balances(&self.post_token_balances)?,
+            signature: self.signature,
+            slot: self.slot,
+            block_time: self.block_time,
+            instructions,
+        })
+    }
+}
+
+fn compiled(instruction: &CompiledInstruction) -> RawInstruction {
+    (
+        instruction.program_id_index,
+        instruction.accounts.clone(),
+        instruction.data.clone(),
+    )
+}
+
+fn token_balances(balances: &Option<Vec<TransactionTokenBalance>>) -> Vec<(u8, String)> {
+    balances
+        .iter()
+        .flatten()
+        .map(|balance| {
+            (
+                balance.account_index,
+                balance.ui_token_amount.amount.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Reads rooted slots from `start_slot` through `end_slot`. The ledger is
+/// opened for writing, so the validator using it must be stopped.
+pub fn read_ledger(
+    path: &Path,
+    start_slot: u64,
+    end_slot: Option<u64>,
+) -> Result<Vec<ReplayTransaction>> {
+    let blockstore = Blockstore::open(path)
+        .with_context(|| format!("failed to open ledger {}", path.display()))?;
+    let mut transactions = Vec::new();
+    for slot in blockstore.rooted_slot_iterator(start_slot)? {
+        if matches!(end_slot, Some(end_slot) if slot > end_slot) {
+            break;
+        }
+        // Rooted slots without a complete block were skipped by the cluster
+        let block = match blockstore.get_rooted_block(slot, false) {
+            Ok(block) => block,
+            Err(_) => continue,
+        };
+        for transaction in block.transactions {
+            let meta = transaction.meta;
+            if meta.status.is_err() {
+                continue;
+            }
+            let message = &transaction.transaction.message;
+            if !message.account_keys.contains(&vibe_market::ID) {
+                continue;
+            }
+            let raw = RawTransaction {
+                signature: transaction.transaction.signatures[0].to_string(),
+                slot,
+                block_time: block.block_time,
+                account_keys: message.account_keys.clone(),
+                instructions: message.instructions.iter().map(compiled).collect(),
+                inner_instructions: meta
+                    .inner_instructions
+                    .iter()
+                    .flatten()
+                    .map(|inner| {
+                        (
+                            inner.index,
+                            inner.instructions.iter().map(compiled).collect(),
+                        )
+                    })
+                    .collect(),
+                pre_token_balances: token_balances(&meta.pre_token_balances),
+                post_token_balances: token_balances(&meta.post_token_balances),
+            };
+            transactions.push(raw.into_replay()?);
+        }
+    }
+    Ok(transactions)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpTransaction {
+    slot: u64,
+    block_time: Option<i64>,
+    transaction: DumpTransactionBody,
+    meta: Option<DumpMeta>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpTransactionBody {
+    signatures: Vec<String>,
+    message: DumpMessage,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpMessage {
+    account_keys: Vec<String>,
+    instructions: Vec<DumpInstruction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpInstruction {
+    program_id_index: u8,
+    accounts: Vec<u8>,
+    data: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpMeta {
+    err: Option<serde_json::Value>,
+    #[serde(default)]
+    inner_instructions: Vec<DumpInnerInstructions>,
+    #[serde(default)]
+    pre_token_balances: Vec<DumpTokenBalance>,
+    #[serde(default)]
+    post_token_balances: Vec<DumpTokenBalance>,
+}
+
+#[derive(Deserialize)]
+struct DumpInnerInstructions {
+    index: u8,
+    instructions: Vec<DumpInstruction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DumpTokenBalance {
+    account_index: u8,
+    ui_token_amount: DumpTokenAmount,
+}
+
+#[derive(Deserialize)]
+struct DumpTokenAmount {
+    amount: String,
+}
+
+fn dump_instruction(instruction: &DumpInstruction) -> Result<RawInstruction> {
+    Ok((
+        instruction.program_id_index,
+        instruction.accounts.clone(),
+        bs58::decode(&instruction.data)
+            .into_vec()
+            .context("instruction data is not base58")?,
+    ))
+}
+
+fn dump_balances(balances: &[DumpTokenBalance]) -> Vec<(u8, String)> {
+    balances
+        .iter()
+        .map(|balance| {
+            (
+                balance.account_index,
+                balance.ui_token_amount.amount.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Reads a JSON array of `getTransaction` results, or one result per line,
+/// and orders them by slot. Transactions within a slot keep their order in
+/// the dump.
+pub fn read_dump(path: &Path) -> Result<Vec<ReplayTransaction>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read dump {}", path.display()))?;
+    let dump: Vec<DumpTransaction> = if contents.trim_start().starts_with('[') {
+        serde_json::from_str(&contents)?
+    } else {
+        contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut transactions = Vec::new();
+    for transaction in dump {
+        let slot = transaction.slot;
+        let meta = match transaction.meta {
+            Some(meta) if meta.err.is_none() => meta,
+            _ => continue,
+        };
+        let message = transaction.transaction.message;
+        let raw = RawTransaction {
+            signature: transaction
+                .transaction
+                .signatures
+                .first()
+                .cloned()
+                .ok_or_else(|| {
+                    anyhow!("transaction in slot {} has no signature", slot)
+                })?,
+            slot,
+            block_time: transaction.block_time,
+            account_keys: message
+                .account_keys
+                .iter()
+                .map(|key| key.parse())
+                .collect::<Result<_, _>>()?,
+            instructions: message
+                .instructions
+                .iter()
+                .map(dump_instruction)
+                .collect::<Result<_>>()?,
+            inner_instructions: meta
+                .inner_instructions
+                .iter()
+                .map(|inner| {
+                    let instructions = inner
+                        .instructions
+                        .iter()
+                        .map(dump_instruction)
+                        .collect::<Result<_>>()?;
+                    Ok((inner.index, instructions))
+                })
+                .collect::<Result<_>>()?,
+            pre_token_balances: dump_balances(&meta.pre_token_balances),
+            post_token_balances: dump_balances(&meta.post_token_balances),
+        };
+        transactions.push(raw.into_replay()?);
+    }
+    transactions.sort_by_key(|transaction| transaction.slot);
+    Ok(transactions)
+}
+
+#[derive(Deserialize)]
+struct SnapshotAccount {
+    pubkey: String,
+    account: SnapshotAccountData,
+}
+
+#[derive(Deserialize)]
+struct SnapshotAccountData {
+    // [data, encoding]
+    data: (String, String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Snapshot {
+    Response { result: Vec<SnapshotAccount> },
+    Accounts(Vec<SnapshotAccount>),
+}
+
+/// Reads a `getProgramAccounts` response, or just its `result` array, as
+/// (address, data) pairs.
+pub fn read_snapshot(path: &Path) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read snapshot {}", path.display()))?;
+    let accounts = match serde_json::from_str(&contents)? {
+        Snapshot::Response { result } => result,
+        Snapshot::Accounts(accounts) => accounts,
+    };
+    accounts
+        .into_iter()
+        .map(|account| {
+            let (data, encoding) = account.account.data;
+            if encoding != "base64" {
+                bail!(
+                    "{}: expected base64 account data, got {}",
+                    account.pubkey,
+                    encoding
+                );
+            }
+            Ok((account.pubkey.parse()?, base64::decode(data)?))
+        })
+        .collect()
+}
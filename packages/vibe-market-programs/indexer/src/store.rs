@@ -0,0 +1,1190 @@
+//! SQLite state built from replayed instructions.
+//!
+//! Every indexed transaction is recorded in `transactions` and applied in a
+//! single SQLite transaction, so replaying an overlapping range is a no-op.
+//! Listing order is kept in `list_links`, one row per bucket including each
+//! collection's list head and tail, and `listing_positions` walks it.
+//! Buckets are PDAs of (collection, mint), so relisting a mint after its
+//! bucket was compacted replaces the old `listings` row; `sales` and
+//! `withdrawals` keep the history.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{Context as _, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use vibe_market::{Collection, InventoryPage, Market, MysteryCommit, NftBucket, PriceModel};
+use vibe_market_client::account::{decode_account, decode_zero_copy, fixed_str};
+use vibe_market_client::pda::find_collection_address;
+
+use crate::decode::{Decoder, VibeInstruction};
+use crate::source::ReplayTransaction;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    block_time INTEGER
+);
+CREATE TABLE IF NOT EXISTS markets (
+    address TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    paused INTEGER NOT NULL DEFAULT 0,
+    created_slot INTEGER,
+    closed_slot INTEGER
+);
+CREATE TABLE IF NOT EXISTS market_admins (
+    market TEXT NOT NULL,
+    admin TEXT NOT NULL,
+    PRIMARY KEY (market, admin)
+);
+CREATE TABLE IF NOT EXISTS collections (
+    address TEXT PRIMARY KEY,
+    market TEXT NOT NULL,
+    title TEXT NOT NULL,
+    paused INTEGER NOT NULL DEFAULT 0,
+    sale_start INTEGER,
+    sale_end INTEGER,
+    mystery_price_model TEXT,
+    list_head TEXT NOT NULL,
+    list_tail TEXT NOT NULL,
+    created_slot INTEGER,
+    closed_slot INTEGER
+);
+CREATE TABLE IF NOT EXISTS price_models (
+    address TEXT PRIMARY KEY,
+    market TEXT NOT NULL,
+    created_slot INTEGER,
+    closed_slot INTEGER
+);
+CREATE TABLE IF NOT EXISTS sale_prices (
+    price_model TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    PRIMARY KEY (price_model, mint)
+);
+-- status is listed, sold, withdrawn or expired; tombstoned when a snapshot
+-- shows a delisted bucket the replay never saw
+CREATE TABLE IF NOT EXISTS listings (
+    address TEXT PRIMARY KEY,
+    market TEXT,
+    collection TEXT NOT NULL,
+    nft_mint TEXT NOT NULL,
+    price_model TEXT NOT NULL,
+    payer TEXT NOT NULL,
+    status TEXT NOT NULL,
+    sale_start INTEGER,
+    sale_end INTEGER,
+    expires_at INTEGER,
+    listed_slot INTEGER,
+    delisted_slot INTEGER
+);
+CREATE INDEX IF NOT EXISTS listings_by_collection ON listings (collection, status);
+CREATE TABLE IF NOT EXISTS list_links (
+    address TEXT PRIMARY KEY,
+    collection TEXT NOT NULL,
+    prev_list_item TEXT,
+    next_list_item TEXT
+);
+CREATE VIEW IF NOT EXISTS listing_positions AS
+WITH RECURSIVE walk (collection, list_tail, address, position) AS (
+    SELECT collections.address, collections.list_tail, list_links.next_list_item, 0
+    FROM collections JOIN list_links ON list_links.address = collections.list_head
+    UNION ALL
+    SELECT walk.collection, walk.list_tail, list_links.next_list_item, walk.position + 1
+    FROM walk JOIN list_links ON list_links.address = walk.address
+    WHERE walk.address != walk.list_tail
+)
+SELECT collection, address AS listing, position FROM walk WHERE address != list_tail;
+-- debit_mint and amount are NULL for mystery box sales committed before the
+-- indexed range
+CREATE TABLE IF NOT EXISTS sales (
+    signature TEXT NOT NULL,
+    instruction_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    market TEXT NOT NULL,
+    collection TEXT NOT NULL,
+    listing TEXT NOT NULL,
+    nft_mint TEXT NOT NULL,
+    buyer TEXT NOT NULL,
+    debit_mint TEXT,
+    amount INTEGER,
+    mystery INTEGER NOT NULL,
+    PRIMARY KEY (signature, instruction_index)
+);
+-- kind is nft, expired, liquidity or refund; listing is set for nft and
+-- expired, collection for everything but market liquidity
+CREATE TABLE IF NOT EXISTS withdrawals (
+    signature TEXT NOT NULL,
+    instruction_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    kind TEXT NOT NULL,
+    market TEXT NOT NULL,
+    collection TEXT,
+    listing TEXT,
+    mint TEXT,
+    amount INTEGER,
+    recipient TEXT NOT NULL,
+    PRIMARY KEY (signature, instruction_index)
+);
+-- Mystery box draws paid for but not yet revealed, refunded or expired
+CREATE TABLE IF NOT EXISTS mystery_commits (
+    address TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    collection TEXT NOT NULL,
+    debit_mint TEXT NOT NULL,
+    amount INTEGER,
+    slot INTEGER
+);
+";
+
+fn key(key: &Pubkey) -> String {
+    key.to_string()
+}
+
+// Zero-copy accounts store unset timestamps as 0
+fn timestamp(value: i64) -> Option<i64> {
+    if value == 0 {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+#[derive(Default)]
+pub struct Summary {
+    pub transactions: usize,
+    pub skipped: usize,
+    pub instructions: usize,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open database {}", path.display()))?;
+        Self::init(conn)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store { conn })
+    }
+
+    #[cfg(test)]
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Applies each transaction that isn't indexed yet, in order.
+    pub fn index(
+        &mut self,
+        decoder: &Decoder,
+        transactions: &[ReplayTransaction],
+    ) -> Result<Summary> {
+        let mut summary = Summary::default();
+        for transaction in transactions {
+            match self.index_transaction(decoder, transaction)? {
+                Some(instructions) => {
+                    summary.transactions += 1;
+                    summary.instructions += instructions;
+                }
+                None => summary.skipped += 1,
+            }
+        }
+        Ok(summary)
+    }
+
+    fn index_transaction(
+        &mut self,
+        decoder: &Decoder,
+        transaction: &ReplayTransaction,
+    ) -> Result<Option<usize>> {
+        if transaction.instructions.is_empty() {
+            return Ok(None);
+        }
+        let tx = self.conn.transaction()?;
+        let inserted = tx.execute(
+            "INSERT OR IGNORE INTO transactions (signature, slot, block_time) VALUES (?1, ?2, ?3)",
+            params![
+                transaction.signature,
+                transaction.slot,
+                transaction.block_time
+            ],
+        )?;
+        if inserted == 0 {
+            return Ok(None);
+        }
+
+        let mut applied = 0;
+        for instruction in &transaction.instructions {
+            let decoded = decoder
+                .decode(&instruction.data, &instruction.accounts)
+                .with_context(|| format!("transaction {}", transaction.signature))?;
+            if let Some(decoded) = decoded {
+                let origin = Origin {
+                    transaction,
+                    index: instruction.index,
+                };
+                apply(&tx, &origin, &decoded).with_context(|| {
+                    format!(
+                        "failed to apply instruction {} of {}",
+                        instruction.index, transaction.signature
+                    )
+                })?;
+                applied += 1;
+            }
+        }
+        tx.commit()?;
+        Ok(Some(applied))
+    }
+
+    /// Upserts the current state of every decodable program account. Rows
+    /// for accounts missing from the snapshot are left as they are.
+    pub fn apply_snapshot(&mut self, accounts: &[(Pubkey, Vec<u8>)]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut applied = 0;
+
+        // Collections and buckets don't store their parent, so resolve it
+        // from markets and inventory pages first
+        let mut collection_markets = HashMap::new();
+        let mut page_collections = HashMap::new();
+        for (address, data) in accounts {
+            if let Ok(market) = decode_zero_copy::<Market>(data) {
+                for index in 0..market.num_collections {
+                    collection_markets.insert(find_collection_address(address, index).0, *address);
+                }
+                upsert_market(&tx, address, &market)?;
+                applied += 1;
+            } else if let Ok(page) = decode_zero_copy::<InventoryPage>(data) {
+                page_collections.insert(*address, page.collection);
+            }
+        }
+
+        let mut list_ends = HashMap::new();
+        for (address, data) in accounts {
+            if let Ok(price_model) = decode_zero_copy::<PriceModel>(data) {
+                upsert_price_model(&tx, address, &price_model)?;
+            } else if let Ok(collection) = decode_account::<Collection>(data) {
+                let market = collection_markets.get(address);
+                upsert_collection(&tx, address, market, &collection)?;
+                list_ends.insert(collection.list_head, *address);
+                list_ends.insert(collection.list_tail, *address);
+            } else if let Ok(commit) = decode_account::<MysteryCommit>(data) {
+                tx.execute(
+                    "INSERT OR REPLACE INTO mystery_commits
+                        (address, owner, collection, debit_mint, amount, slot)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        key(address),
+                        key(&commit.owner),
+                        key(&commit.collection),
+                        key(&commit.debit_mint),
+                        commit.amount,
+                        commit.commit_slot
+                    ],
+                )?;
+            } else {
+                continue;
+            }
+            applied += 1;
+        }
+
+        for (address, data) in accounts {
+            let bucket = match decode_zero_copy::<NftBucket>(data) {
+                Ok(bucket) => bucket,
+                Err(_) => continue,
+            };
+            let collection = match list_ends.get(address) {
+                Some(collection) => Some(*collection),
+                None => page_collections.get(&bucket.inventory_page).copied(),
+            };
+            let collection = match collection {
+                Some(collection) => collection,
+                None => continue,
+            };
+            link(
+                &tx,
+                address,
+                &collection,
+                Some(&bucket.prev_list_item),
+                Some(&bucket.next_list_item),
+            )?;
+            if !list_ends.contains_key(address) {
+                upsert_nft_bucket(
+                    &tx,
+                    address,
+                    &collection,
+                    collection_markets.get(&collection),
+                    &bucket,
+                )?;
+            }
+            applied += 1;
+        }
+
+        tx.commit()?;
+        Ok(applied)
+    }
+
+    /// Row counts of the main tables, for progress output.
+    pub fn counts(&self) -> Result<Vec<(&'static str, i64)>> {
+        [
+            "markets",
+            "collections",
+            "price_models",
+            "listings",
+            "sales",
+            "withdrawals",
+        ]
+        .iter()
+        .map(|table| {
+            let count =
+                self.conn
+                    .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                        row.get(0)
+                    })?;
+            Ok((*table, count))
+        })
+        .collect()
+    }
+}
+
+struct Origin<'a> {
+    transaction: &'a ReplayTransaction,
+    index: u32,
+}
+
+impl Origin<'_> {
+    fn slot(&self) -> u64 {
+        self.transaction.slot
+    }
+}
+
+fn link(
+    conn: &Connection,
+    address: &Pubkey,
+    collection: &Pubkey,
+    prev_list_item: Option<&Pubkey>,
+    next_list_item: Option<&Pubkey>,
+) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO list_links (address, collection, prev_list_item, next_list_item)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            key(address),
+            key(collection),
+            prev_list_item.map(key),
+            next_list_item.map(key)
+        ],
+    )?;
+    Ok(())
+}
+
+fn set_next(conn: &Connection, address: &Pubkey, next_list_item: &Pubkey) -> Result<()> {
+    conn.execute(
+        "UPDATE list_links SET next_list_item = ?2 WHERE address = ?1",
+        params![key(address), key(next_list_item)],
+    )?;
+    Ok(())
+}
+
+fn set_prev(conn: &Connection, address: &Pubkey, prev_list_item: &Pubkey) -> Result<()> {
+    conn.execute(
+        "UPDATE list_links SET prev_list_item = ?2 WHERE address = ?1",
+        params![key(address), key(prev_list_item)],
+    )?;
+    Ok(())
+}
+
+fn set_status(conn: &Connection, origin: &Origin, listing: &Pubkey, status: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE listings SET status = ?2, delisted_slot = ?3 WHERE address = ?1",
+        params![key(listing), status, origin.slot()],
+    )?;
+    Ok(())
+}
+
+fn sale_price(conn: &Connection, price_model: &Pubkey, mint: &Pubkey) -> Result<Option<u64>> {
+    Ok(conn
+        .query_row(
+            "SELECT amount FROM sale_prices WHERE price_model = ?1 AND mint = ?2",
+            params![key(price_model), key(mint)],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Removes a pending mystery box draw, returning its debit mint and amount
+/// if the commit was indexed.
+fn take_mystery_commit(
+    conn: &Connection,
+    mystery_commit: &Pubkey,
+) -> Result<(Option<String>, Option<u64>)> {
+    let commit: Option<(String, Option<u64>)> = conn
+        .query_row(
+            "SELECT debit_mint, amount FROM mystery_commits WHERE address = ?1",
+            params![key(mystery_commit)],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    conn.execute(
+        "DELETE FROM mystery_commits WHERE address = ?1",
+        params![key(mystery_commit)],
+    )?;
+    Ok(match commit {
+        Some((debit_mint, amount)) => (Some(debit_mint), amount),
+        None => (None, None),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert_sale(
+    conn: &Connection,
+    origin: &Origin,
+    market: &Pubkey,
+    collection: &Pubkey,
+    listing: &Pubkey,
+    nft_mint: &Pubkey,
+    buyer: &Pubkey,
+    debit_mint: Option<String>,
+    amount: Option<u64>,
+    mystery: bool,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sales (signature, instruction_index, slot, block_time, market, collection,
+            listing, nft_mint, buyer, debit_mint, amount, mystery)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            origin.transaction.signature,
+            origin.index,
+            origin.slot(),
+            origin.transaction.block_time,
+            key(market),
+            key(collection),
+            key(listing),
+            key(nft_mint),
+            key(buyer),
+            debit_mint,
+            amount,
+            mystery
+        ],
+    )?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert_withdrawal(
+    conn: &Connection,
+    origin: &Origin,
+    kind: &str,
+    market: &Pubkey,
+    collection: Option<&Pubkey>,
+    listing: Option<&Pubkey>,
+    mint: Option<String>,
+    amount: Option<u64>,
+    recipient: &Pubkey,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO withdrawals (signature, instruction_index, slot, block_time, kind, market,
+            collection, listing, mint, amount, recipient)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            origin.transaction.signature,
+            origin.index,
+            origin.slot(),
+            origin.transaction.block_time,
+            kind,
+            key(market),
+            collection.map(key),
+            listing.map(key),
+            mint,
+            amount,
+            key(recipient)
+        ],
+    )?;
+    Ok(())
+}
+
+fn apply(conn: &Connection, origin: &Origin, instruction: &VibeInstruction) -> Result<()> {
+    let slot = origin.slot();
+    match instruction {
+        VibeInstruction::InitMarket {
+            market,
+            whitelist,
+            title,
+        } => {
+            conn.execute(
+                "INSERT OR REPLACE INTO markets (address, title, paused, created_slot)
+                 VALUES (?1, ?2, 0, ?3)",
+                params![key(market), title, slot],
+            )?;
+            for admin in whitelist {
+                conn.execute(
+                    "INSERT OR IGNORE INTO market_admins (market, admin) VALUES (?1, ?2)",
+                    params![key(market), key(admin)],
+                )?;
+            }
+        }
+        VibeInstruction::AddAdmin { market, admin } => {
+            conn.execute(
+                "INSERT OR IGNORE INTO market_admins (market, admin) VALUES (?1, ?2)",
+                params![key(market), key(admin)],
+            )?;
+        }
+        VibeInstruction::RemoveAdmin { market, admin } => {
+            conn.execute(
+                "DELETE FROM market_admins WHERE market = ?1 AND admin = ?2",
+                params![key(market), key(admin)],
+            )?;
+        }
+        VibeInstruction::SetMarketPaused { market, paused } => {
+            conn.execute(
+                "UPDATE markets SET paused = ?2 WHERE address = ?1",
+                params![key(market), paused],
+            )?;
+        }
+        VibeInstruction::UpdateMarketMetadata { market, title } => {
+            conn.execute(
+                "UPDATE markets SET title = ?2 WHERE address = ?1",
+                params![key(market), title],
+            )?;
+        }
+        VibeInstruction::CloseMarket { market } => {
+            conn.execute(
+                "UPDATE markets SET closed_slot = ?2 WHERE address = ?1",
+                params![key(market), slot],
+            )?;
+        }
+        VibeInstruction::InitCollection {
+            market,
+            collection,
+            list_head,
+            list_tail,
+            title,
+        } => {
+            conn.execute(
+                "INSERT OR REPLACE INTO collections
+                    (address, market, title, paused, list_head, list_tail, created_slot)
+                 VALUES (?1, ?2, ?3, 0, ?4, ?5, ?6)",
+                params![
+                    key(collection),
+                    key(market),
+                    title,
+                    key(list_head),
+                    key(list_tail),
+                    slot
+                ],
+            )?;
+            link(conn, list_head, collection, None, Some(list_tail))?;
+            link(conn, list_tail, collection, Some(list_head), None)?;
+        }
+        VibeInstruction::SetCollectionPaused { collection, paused } => {
+            conn.execute(
+                "UPDATE collections SET paused = ?2 WHERE address = ?1",
+                params![key(collection), paused],
+            )?;
+        }
+        VibeInstruction::SetCollectionSaleWindow {
+            collection,
+            sale_start,
+            sale_end,
+        } => {
+            conn.execute(
+                "UPDATE collections SET sale_start = ?2, sale_end = ?3 WHERE address = ?1",
+                params![key(collection), sale_start, sale_end],
+            )?;
+        }
+        VibeInstruction::SetMysteryPriceModel {
+            collection,
+            price_model,
+            enabled,
+        } => {
+            conn.execute(
+                "UPDATE collections SET mystery_price_model = ?2 WHERE address = ?1",
+                params![
+                    key(collection),
+                    Some(price_model).filter(|_| *enabled).map(key)
+                ],
+            )?;
+        }
+        VibeInstruction::UpdateCollectionMetadata { collection, title } => {
+            conn.execute(
+                "UPDATE collections SET title = ?2 WHERE address = ?1",
+                params![key(collection), title],
+            )?;
+        }
+        VibeInstruction::CloseCollection { collection } => {
+            conn.execute(
+                "UPDATE collections SET closed_slot = ?2 WHERE address = ?1",
+                params![key(collection), slot],
+            )?;
+            conn.execute(
+                "DELETE FROM list_links WHERE collection = ?1",
+                params![key(collection)],
+            )?;
+        }
+        VibeInstruction::InitPriceModel {
+            market,
+            price_model,
+            sale_prices,
+        } => {
+            conn.execute(
+                "INSERT OR REPLACE INTO price_models (address, market, created_slot)
+                 VALUES (?1, ?2, ?3)",
+                params![key(price_model), key(market), slot],
+            )?;
+            for (mint, amount) in sale_prices {
+                conn.execute(
+                    "INSERT OR REPLACE INTO sale_prices (price_model, mint, amount)
+                     VALUES (?1, ?2, ?3)",
+                    params![key(price_model), key(mint), amount],
+                )?;
+            }
+        }
+        VibeInstruction::ClosePriceModel { price_model } => {
+            conn.execute(
+                "UPDATE price_models SET closed_slot = ?2 WHERE address = ?1",
+                params![key(price_model), slot],
+            )?;
+        }
+        VibeInstruction::ListNft {
+            payer,
+            market,
+            collection,
+            prev_list_item,
+            next_list_item,
+            nft_mint,
+            nft_bucket,
+            price_model,
+        } => {
+            conn.execute(
+                "INSERT OR REPLACE INTO listings
+                    (address, market, collection, nft_mint, price_model, payer, status, listed_slot)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'listed', ?7)",
+                params![
+                    key(nft_bucket),
+                    key(market),
+                    key(collection),
+                    key(nft_mint),
+                    key(price_model),
+                    key(payer),
+                    slot
+                ],
+            )?;
+            link(
+                conn,
+                nft_bucket,
+                collection,
+                Some(prev_list_item),
+                Some(next_list_item),
+            )?;
+            set_next(conn, prev_list_item, nft_bucket)?;
+            set_prev(conn, next_list_item, nft_bucket)?;
+        }
+        VibeInstruction::MoveItem {
+            list_item,
+            prev_list_item,
+            next_list_item,
+            new_prev_list_item,
+            new_next_list_item,
+        } => {
+            set_next(conn, prev_list_item, next_list_item)?;
+            set_prev(conn, next_list_item, prev_list_item)?;
+            conn.execute(
+                "UPDATE list_links SET prev_list_item = ?2, next_list_item = ?3 WHERE address = ?1",
+                params![
+                    key(list_item),
+                    key(new_prev_list_item),
+                    key(new_next_list_item)
+                ],
+            )?;
+            set_next(conn, new_prev_list_item, list_item)?;
+            set_prev(conn, new_next_list_item, list_item)?;
+        }
+        VibeInstruction::RepairLink {
+            prev_list_item,
+            next_list_item,
+        } => {
+            set_next(conn, prev_list_item, next_list_item)?;
+            set_prev(conn, next_list_item, prev_list_item)?;
+        }
+        VibeInstruction::SetNftSaleWindow {
+            list_item,
+            sale_start,
+            sale_end,
+        } => {
+            conn.execute(
+                "UPDATE listings SET sale_start = ?2, sale_end = ?3 WHERE address = ?1",
+                params![key(list_item), sale_start, sale_end],
+            )?;
+        }
+        VibeInstruction::SetNftExpiry {
+            list_item,
+            expires_at,
+        } => {
+            conn.execute(
+                "UPDATE listings SET expires_at = ?2 WHERE address = ?1",
+                params![key(list_item), expires_at],
+            )?;
+        }
+        VibeInstruction::WithdrawNft {
+            admin,
+            market,
+            collection,
+            nft_bucket,
+            nft_mint,
+        } => {
+            set_status(conn, origin, nft_bucket, "withdrawn")?;
+            insert_withdrawal(
+                conn,
+                origin,
+                "nft",
+                market,
+                Some(collection),
+                Some(nft_bucket),
+                Some(key(nft_mint)),
+                Some(1),
+                admin,
+            )?;
+        }
+        VibeInstruction::PurchaseNft {
+            owner,
+            market,
+            collection,
+            nft_bucket,
+            nft_mint,
+            price_model,
+            debit_mint,
+            debit_account,
+        } => {
+            // Price models are immutable, so the listed price is what was
+            // charged; fall back to the balance change if the price model
+            // predates the indexed range
+            let amount = match sale_price(conn, price_model, debit_mint)? {
+                Some(amount) => Some(amount),
+                None => origin.transaction.token_debit(debit_account),
+            };
+            set_status(conn, origin, nft_bucket, "sold")?;
+            insert_sale(
+                conn,
+                origin,
+                market,
+                collection,
+                nft_bucket,
+                nft_mint,
+                owner,
+                Some(key(debit_mint)),
+                amount,
+                false,
+            )?;
+        }
+        VibeInstruction::CrankExpired {
+            payer,
+            market,
+            collection,
+            nft_bucket,
+            nft_mint,
+            prev_list_item,
+            next_list_item,
+        } => {
+            set_status(conn, origin, nft_bucket, "expired")?;
+            set_next(conn, prev_list_item, next_list_item)?;
+            set_prev(conn, next_list_item, prev_list_item)?;
+            conn.execute(
+                "DELETE FROM list_links WHERE address = ?1",
+                params![key(nft_bucket)],
+            )?;
+            insert_withdrawal(
+                conn,
+                origin,
+                "expired",
+                market,
+                Some(collection),
+                Some(nft_bucket),
+                Some(key(nft_mint)),
+                Some(1),
+                payer,
+            )?;
+        }
+        VibeInstruction::CommitRandomPurchase {
+            owner,
+            market: _,
+            collection,
+            price_model,
+            mystery_commit,
+            debit_mint,
+            debit_account,
+        } => {
+            let amount = match sale_price(conn, price_model, debit_mint)? {
+                Some(amount) => Some(amount),
+                None => origin.transaction.token_debit(debit_account),
+            };
+            conn.execute(
+                "INSERT OR REPLACE INTO mystery_commits
+                    (address, owner, collection, debit_mint, amount, slot)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    key(mystery_commit),
+                    key(owner),
+                    key(collection),
+                    key(debit_mint),
+                    amount,
+                    slot
+                ],
+            )?;
+        }
+        VibeInstruction::RevealRandomPurchase {
+            owner,
+            market,
+            collection,
+            mystery_commit,
+            nft_bucket,
+            nft_mint,
+        } => {
+            let (debit_mint, amount) = take_mystery_commit(conn, mystery_commit)?;
+            set_status(conn, origin, nft_bucket, "sold")?;
+            insert_sale(
+                conn, origin, market, collection, nft_bucket, nft_mint, owner, debit_mint, amount,
+                true,
+            )?;
+        }
+        VibeInstruction::RefundRandomPurchase {
+            owner,
+            market,
+            collection,
+            mystery_commit,
+        } => {
+            let (debit_mint, amount) = take_mystery_commit(conn, mystery_commit)?;
+            insert_withdrawal(
+                conn,
+                origin,
+                "refund",
+                market,
+                Some(collection),
+                None,
+                debit_mint,
+                amount,
+                owner,
+            )?;
+        }
+        VibeInstruction::ExpireRandomPurchase {
+            owner: _,
+            market: _,
+            collection: _,
+            mystery_commit,
+        } => {
+            // The payment stays in the collection treasury
+            take_mystery_commit(conn, mystery_commit)?;
+        }
+        VibeInstruction::CompactListItem {
+            list_item,
+            prev_list_item,
+            next_list_item,
+        } => {
+            set_next(conn, prev_list_item, next_list_item)?;
+            set_prev(conn, next_list_item, prev_list_item)?;
+            conn.execute(
+                "DELETE FROM list_links WHERE address = ?1",
+                params![key(list_item)],
+            )?;
+        }
+        VibeInstruction::WithdrawLiquidity {
+            admin,
+            market,
+            collection,
+            mint,
+            amount,
+        } => {
+            insert_withdrawal(
+                conn,
+                origin,
+                "liquidity",
+                market,
+                collection.as_ref(),
+                None,
+                Some(key(mint)),
+                Some(*amount),
+                admin,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn upsert_market(conn: &Connection, address: &Pubkey, market: &Market) -> Result<()> {
+    conn.execute(
+        "INSERT INTO markets (address, title, paused) VALUES (?1, ?2, ?3)
+         ON CONFLICT (address) DO UPDATE SET title = excluded.title, paused = excluded.paused",
+        params![
+            key(address),
+            fixed_str(&market.title, market.title_len as usize),
+            market.paused != 0
+        ],
+    )?;
+    let whitelist: HashSet<String> = market.whitelist().iter().map(key).collect();
+    conn.execute(
+        "DELETE FROM market_admins WHERE market = ?1",
+        params![key(address)],
+    )?;
+    for admin in whitelist {
+        conn.execute(
+            "INSERT INTO market_admins (market, admin) VALUES (?1, ?2)",
+            params![key(address), admin],
+        )?;
+    }
+    Ok(())
+}
+
+fn upsert_collection(
+    conn: &Connection,
+    address: &Pubkey,
+    market: Option<&Pubkey>,
+    collection: &Collection,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO collections (address, market, title, paused, sale_start, sale_end,
+            mystery_price_model, list_head, list_tail)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT (address) DO UPDATE SET title = excluded.title, paused = excluded.paused,
+            sale_start = excluded.sale_start, sale_end = excluded.sale_end,
+            mystery_price_model = excluded.mystery_price_model",
+        params![
+            key(address),
+            market.map(key).unwrap_or_default(),
+            collection.title,
+            collection.paused,
+            collection.sale_start,
+            collection.sale_end,
+            collection.mystery_price_model.as_ref().map(key),
+            key(&collection.list_head),
+            key(&collection.list_tail)
+        ],
+    )?;
+    Ok(())
+}
+
+fn upsert_price_model(conn: &Connection, address: &Pubkey, price_model: &PriceModel) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO price_models (address, market) VALUES (?1, ?2)",
+        params![key(address), key(&price_model.market)],
+    )?;
+    for sale_price in &price_model.sale_prices[..price_model.num_sale_prices as usize] {
+        let amount = sale_price.amount;
+        conn.execute(
+            "INSERT OR REPLACE INTO sale_prices (price_model, mint, amount) VALUES (?1, ?2, ?3)",
+            params![key(address), key(&sale_price.mint), amount],
+        )?;
+    }
+    Ok(())
+}
+
+fn upsert_nft_bucket(
+    conn: &Connection,
+    address: &Pubkey,
+    collection: &Pubkey,
+    market: Option<&Pubkey>,
+    bucket: &NftBucket,
+) -> Result<()> {
+    let (sale_start, sale_end, expires_at) =
+        (bucket.sale_start, bucket.sale_end, bucket.expires_at);
+    let status = if bucket.tombstoned != 0 {
+        "tombstoned"
+    } else {
+        "listed"
+    };
+    // A tombstoned bucket keeps the more specific status from the replay
+    conn.execute(
+        "INSERT INTO listings (address, market, collection, nft_mint, price_model, payer, status,
+            sale_start, sale_end, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT (address) DO UPDATE SET price_model = excluded.price_model,
+            sale_start = excluded.sale_start, sale_end = excluded.sale_end,
+            expires_at = excluded.expires_at,
+            status = CASE
+                WHEN excluded.status = 'listed' OR listings.status = 'listed' THEN excluded.status
+                ELSE listings.status
+            END",
+        params![
+            key(address),
+            market.map(key),
+            key(collection),
+            key(&bucket.nft_mint),
+            key(&bucket.price_model),
+            key(&bucket.payer),
+            status,
+            timestamp(sale_start),
+            timestamp(sale_end),
+            timestamp(expires_at)
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::ProgramInstruction;
+    use anchor_lang::solana_program::instruction::Instruction;
+    use vibe_market::{GlobalState, Market, SalePrice};
+    use vibe_market_client::instruction::{self as ix, ListNft};
+    use vibe_market_client::pda::*;
+
+    fn replay(signature: &str, slot: u64, instructions: Vec<Instruction>) -> ReplayTransaction {
+        ReplayTransaction::new(
+            signature.to_string(),
+            slot,
+            instructions
+                .into_iter()
+                .enumerate()
+                .map(|(index, instruction)| ProgramInstruction {
+                    index: index as u32,
+                    data: instruction.data,
+                    accounts: instruction
+                        .accounts
+                        .iter()
+                        .map(|meta| meta.pubkey)
+                        .collect(),
+                })
+                .collect(),
+        )
+    }
+
+    fn positions(store: &Store, collection: &Pubkey) -> Vec<String> {
+        let mut statement = store
+            .conn()
+            .prepare(
+                "SELECT listing FROM listing_positions WHERE collection = ?1 ORDER BY position",
+            )
+            .unwrap();
+        let rows = statement
+            .query_map(params![key(collection)], |row| row.get(0))
+            .unwrap();
+        rows.collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn replays_listing_and_sale() {
+        let admin = Pubkey::new_unique();
+        let market = find_market_address(&find_global_state_address().0, 0).0;
+        let market_state: Market = bytemuck::Zeroable::zeroed();
+        let collection = find_collection_address(&market, 0).0;
+        let (list_head, list_tail) = (
+            find_list_head_address(&collection).0,
+            find_list_tail_address(&collection).0,
+        );
+        let price_model = find_price_model_address(&market, 0).0;
+        let payment_mint = Pubkey::new_unique();
+        let (first_mint, second_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let first = find_nft_bucket_address(&collection, &first_mint).0;
+        let second = find_nft_bucket_address(&collection, &second_mint).0;
+        let listing = |prev_list_item, next_list_item, nft_mint| ListNft {
+            admin,
+            market,
+            collection,
+            prev_list_item,
+            next_list_item,
+            nft_mint,
+            admin_nft_account: Pubkey::new_unique(),
+            inventory_page: find_inventory_page_address(&collection, 0).0,
+            price_model,
+        };
+
+        let mut store = Store::open_in_memory().unwrap();
+        let decoder = Decoder::new();
+        let transactions = vec![
+            replay(
+                "setup",
+                1,
+                vec![
+                    ix::init_market(
+                        &admin,
+                        &GlobalState::default(),
+                        vec![admin],
+                        "Market".to_string(),
+                    ),
+                    ix::init_collection(&admin, &market, &market_state, "Collection".to_string()),
+                    ix::init_price_model(
+                        &admin,
+                        &market,
+                        &market_state,
+                        vec![SalePrice {
+                            mint: payment_mint,
+                            amount: 5,
+                        }],
+                    ),
+                ],
+            ),
+            replay(
+                "list",
+                2,
+                vec![
+                    ix::add_nft(&listing(list_head, list_tail, first_mint)),
+                    ix::add_nft(&listing(list_head, first, second_mint)),
+                ],
+            ),
+        ];
+        store.index(&decoder, &transactions).unwrap();
+        assert_eq!(
+            positions(&store, &collection),
+            vec![key(&second), key(&first)]
+        );
+
+        let mut bucket: NftBucket = bytemuck::Zeroable::zeroed();
+        bucket.nft_mint = first_mint;
+        bucket.price_model = price_model;
+        bucket.payer = admin;
+        bucket.prev_list_item = second;
+        bucket.next_list_item = list_tail;
+        let buyer = Pubkey::new_unique();
+        let purchase = vec![replay(
+            "purchase",
+            3,
+            vec![
+                ix::purchase_nft(
+                    &buyer,
+                    &GlobalState::default(),
+                    &market,
+                    &collection,
+                    &first,
+                    &bucket,
+                    &payment_mint,
+                    &Pubkey::new_unique(),
+                ),
+                ix::compact_list_item(&first, &bucket),
+            ],
+        )];
+        let summary = store.index(&decoder, &purchase).unwrap();
+        assert_eq!((summary.transactions, summary.instructions), (1, 2));
+        // Replaying the same transaction is a no-op
+        assert_eq!(store.index(&decoder, &purchase).unwrap().skipped, 1);
+
+        assert_eq!(positions(&store, &collection), vec![key(&second)]);
+        let (status, delisted_slot): (String, u64) = store
+            .conn()
+            .query_row(
+                "SELECT status, delisted_slot FROM listings WHERE address = ?1",
+                params![key(&first)],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((status.as_str(), delisted_slot), ("sold", 3));
+        let (sale_buyer, amount): (String, u64) = store
+            .conn()
+            .query_row("SELECT buyer, amount FROM sales", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!((sale_buyer, amount), (key(&buyer), 5));
+    }
+}
@@ -4,12 +4,13 @@ import { vibeMarketProgramId } from "../utils/constants"
 import * as seedAddresses from "../utils/seedAddresses"
 
 const initGlobalState = async (vibeMarketProgram, walletPublicKey) => {
-  const [globalStateAddress, globalStateAddressNonce] =
-    await seedAddresses.getGlobalStateAddress()
+  const [programDataAddress] = await seedAddresses.getProgramDataAddress()
+  const [globalStateAddress] = await seedAddresses.getGlobalStateAddress()
 
-  await vibeMarketProgram.rpc.initGlobalState(globalStateAddressNonce, {
+  await vibeMarketProgram.rpc.initGlobalState({
     accounts: {
       admin: walletPublicKey,
+      programData: programDataAddress,
       globalState: globalStateAddress,
       systemProgram: anchor.web3.SystemProgram.programId,
     },
@@ -18,25 +19,26 @@ const initGlobalState = async (vibeMarketProgram, walletPublicKey) => {
 }
 
 const initMarket = async (vibeMarketProgram, walletPublicKey, marketIndex) => {
-  const [globalStateAddress, globalStateAddressNonce] =
-    await seedAddresses.getGlobalStateAddress()
-
-  const [marketAddress, marketAddressNonce] =
-    await seedAddresses.getMarketAddress(globalStateAddress, marketIndex)
-
-  await vibeMarketProgram.rpc.initMarket(
-    marketAddressNonce,
-    [walletPublicKey],
-    "Vibe Market",
-    {
-      accounts: {
-        admin: walletPublicKey,
-        globalState: globalStateAddress,
-        market: marketAddress,
-        systemProgram: anchor.web3.SystemProgram.programId,
-      },
-    }
+  const [globalStateAddress] = await seedAddresses.getGlobalStateAddress()
+
+  const [marketAddress] = await seedAddresses.getMarketAddress(
+    globalStateAddress,
+    marketIndex
   )
+
+  const globalState = await vibeMarketProgram.account.globalState.fetch(
+    globalStateAddress
+  )
+
+  await vibeMarketProgram.rpc.initMarket([walletPublicKey], "Vibe Market", {
+    accounts: {
+      admin: walletPublicKey,
+      globalState: globalStateAddress,
+      feeRecipient: globalState.feeRecipient,
+      market: marketAddress,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    },
+  })
   return marketAddress
 }
 
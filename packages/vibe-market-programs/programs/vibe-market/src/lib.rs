@@ -1,44 +1,154 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 use anchor_lang::solana_program::{
-    system_program,
+    bpf_loader_upgradeable, hash::hashv, program::{invoke, set_return_data},
+    program_option::COption, system_instruction, system_program, sysvar,
 };
 use anchor_spl::token::{
-    self, transfer, close_account, TokenAccount, Transfer, CloseAccount, Token, Mint
+    self, transfer, close_account, set_authority, TokenAccount, Transfer, CloseAccount,
+    SetAuthority, Token, Mint,
 };
 use anchor_spl::associated_token::{
     self, AssociatedToken,
 };
+use spl_token::instruction::AuthorityType;
 
 declare_id!("vXXYKPPwmERsfQPKEwhmnQBABVDoLUKRzz5LtLk9ndS");
 
 const ADMIN_WHITELIST_MAX_LEN: usize = 16;
+const FEE_BASIS_POINTS_MAX: u16 = 10_000;
+const TITLE_MAX_LEN: usize = 32;
+const DESCRIPTION_MAX_LEN: usize = 256;
+const URI_MAX_LEN: usize = 200;
+const INVENTORY_PAGE_SLOTS: usize = 64;
+const INVENTORY_SLOT_NONE: u32 = u32::MAX;
+const SLOT_HASH_ENTRY_LEN: usize = 40;
+const MINT_VOLUME_MAX_LEN: usize = 8;
+const SALE_PRICES_MAX_LEN: usize = 8;
+const ACCOUNT_VERSION: u8 = 1;
+// Keeps a ListPageData within the 1KiB return data limit
+const LIST_PAGE_MAX_LEN: usize = 15;
 
 #[program]
 pub mod vibe_market {
     use super::*;
-    pub fn init_global_state(ctx: Context<InitGlobalState>, nonce: u8) -> ProgramResult {
+    #[access_control(
+        GlobalState::is_upgrade_authority(&ctx.accounts.program_data, ctx.accounts.admin.key)
+    )]
+    pub fn init_global_state(ctx: Context<InitGlobalState>) -> ProgramResult {
+        // Bumps are derived on-chain; accounts created before this change keep
+        // their stored client-supplied nonce, which remains valid for signing.
+        let (_, nonce) = Pubkey::find_program_address(
+            &[b"global".as_ref()],
+            ctx.program_id,
+        );
+
         let global_state = &mut ctx.accounts.global_state;
+        global_state.version = ACCOUNT_VERSION;
         global_state.nonce = nonce;
+        global_state.authority = ctx.accounts.admin.key();
+        global_state.fee_recipient = ctx.accounts.admin.key();
+        // `init` zeroes the account, which would decode as the first variant
+        global_state.market_creation = MarketCreation::default();
+        Ok(())
+    }
+
+    #[access_control(
+        GlobalState::is_valid_authority(&ctx.accounts.global_state, ctx.accounts.authority.key)
+    )]
+    pub fn update_protocol_fee(
+        ctx: Context<UpdateProtocolFee>,
+        fee_basis_points: u16,
+    ) -> ProgramResult {
+        if fee_basis_points > FEE_BASIS_POINTS_MAX {
+            return Err(ErrorCode::InvalidFeeBasisPoints.into());
+        }
+
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.fee_recipient = ctx.accounts.fee_recipient.key();
+        global_state.fee_basis_points = fee_basis_points;
+        Ok(())
+    }
+
+    #[access_control(
+        GlobalState::is_valid_authority(&ctx.accounts.global_state, ctx.accounts.authority.key)
+    )]
+    pub fn set_global_authority(ctx: Context<SetGlobalAuthority>) -> ProgramResult {
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.authority = ctx.accounts.new_authority.key();
+        Ok(())
+    }
+
+    #[access_control(
+        GlobalState::is_valid_authority(&ctx.accounts.global_state, ctx.accounts.authority.key)
+    )]
+    pub fn update_market_creation(
+        ctx: Context<UpdateMarketCreation>,
+        market_creation: MarketCreation,
+        market_creation_fee: u64,
+    ) -> ProgramResult {
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.market_creation = market_creation;
+        global_state.market_creation_fee = market_creation_fee;
         Ok(())
     }
 
-    pub fn init_market(ctx: Context<InitMarket>, nonce: u8, whitelist: Vec<Pubkey>, title: String) -> ProgramResult {
+    pub fn init_market(ctx: Context<InitMarket>, whitelist: Vec<Pubkey>, title: String) -> ProgramResult {
+        let admin_key = ctx.accounts.admin.key();
+        let global_state = &ctx.accounts.global_state;
+
+        // Check market creation policy
+        if admin_key != global_state.authority {
+            match global_state.market_creation {
+                MarketCreation::Open => {},
+                MarketCreation::Permissioned => return Err(ErrorCode::Unauthorized.into()),
+                MarketCreation::FeeGated => {
+                    invoke(
+                        &system_instruction::transfer(
+                            &admin_key,
+                            &global_state.fee_recipient,
+                            global_state.market_creation_fee,
+                        ),
+                        &[
+                            ctx.accounts.admin.to_account_info(),
+                            ctx.accounts.fee_recipient.to_account_info(),
+                            ctx.accounts.system_program.to_account_info(),
+                        ],
+                    )?;
+                },
+            }
+        }
+
+        if !whitelist.contains(&admin_key) {
+            return Err(ErrorCode::AdminNotFound.into());
+        }
+        if whitelist.len() > ADMIN_WHITELIST_MAX_LEN {
+            return Err(ErrorCode::AdminOutOfBounds.into());
+        }
+        validate_title(&title)?;
+
+        let global_state_key = ctx.accounts.global_state.to_account_info().key();
         let global_state = &mut ctx.accounts.global_state;
-        let market = &mut ctx.accounts.market;
+        let market = &mut ctx.accounts.market.load_init()?;
         market.index = global_state.num_markets;
+        let (_, nonce) = Pubkey::find_program_address(
+            &[
+                global_state_key.as_ref(),
+                &market.index.to_le_bytes(),
+            ],
+            ctx.program_id,
+        );
 
         global_state.num_markets = global_state
             .num_markets
             .checked_add(1)
-            .ok_or_else(|| ErrorCode::Overflow)?;
+            .ok_or(ErrorCode::Overflow)?;
         
-        market.whitelist = whitelist;
+        market.whitelist[..whitelist.len()].copy_from_slice(&whitelist);
+        market.whitelist_len = whitelist.len() as u8;
+        market.version = ACCOUNT_VERSION;
         market.nonce = nonce;
-        market.title = title;
-
-        if market.whitelist.len() > ADMIN_WHITELIST_MAX_LEN {
-            return Err(ErrorCode::AdminOutOfBounds.into());
-        }
+        market.set_title(&title);
 
         Ok(())
     }
@@ -47,13 +157,15 @@ pub mod vibe_market {
         Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
     )]
     pub fn add_admin(ctx: Context<AddAdmin>) -> ProgramResult {
-        let market = &mut ctx.accounts.market;
-        market.whitelist.push(ctx.accounts.add_admin.key());
+        let market = &mut ctx.accounts.market.load_mut()?;
+        let whitelist_len = market.whitelist_len as usize;
 
-        if market.whitelist.len() > ADMIN_WHITELIST_MAX_LEN {
+        if whitelist_len >= ADMIN_WHITELIST_MAX_LEN {
             return Err(ErrorCode::AdminOutOfBounds.into());
         }
 
+        market.whitelist[whitelist_len] = ctx.accounts.add_admin.key();
+        market.whitelist_len += 1;
         Ok(())
     }
 
@@ -61,19 +173,60 @@ pub mod vibe_market {
         Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
     )]
     pub fn remove_admin(ctx: Context<RemoveAdmin>) -> ProgramResult {
-        let market = &mut ctx.accounts.market;
+        let market = &mut ctx.accounts.market.load_mut()?;
         let remove_admin_key = &ctx.accounts.remove_admin.key();
 
         if ctx.accounts.admin.key() == *remove_admin_key {
             return Err(ErrorCode::CannotRemoveSelf.into());
         };
-        if !market.whitelist.contains(remove_admin_key) {
+        if !market.whitelist().contains(remove_admin_key) {
             return Err(ErrorCode::AdminNotFound.into());
         };
 
-        market.whitelist = market.whitelist.clone().into_iter()
-            .filter(|p| p != remove_admin_key).collect();
+        // Compact the remaining admins to the front of the fixed whitelist
+        let whitelist_len = market.whitelist_len as usize;
+        let mut retained = 0;
+        for index in 0..whitelist_len {
+            let admin = market.whitelist[index];
+            if admin != *remove_admin_key {
+                market.whitelist[retained] = admin;
+                retained += 1;
+            }
+        }
+        for index in retained..whitelist_len {
+            market.whitelist[index] = Pubkey::default();
+        }
+        market.whitelist_len = retained as u8;
+
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn set_market_paused(ctx: Context<SetMarketPaused>, paused: bool) -> ProgramResult {
+        let market = &mut ctx.accounts.market.load_mut()?;
+        if market.paused != 0 && !paused {
+            market.unpaused_slot = Clock::get()?.slot;
+        }
+        market.paused = paused as u8;
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn update_market_metadata(
+        ctx: Context<UpdateMarketMetadata>,
+        title: String,
+        metadata: Metadata,
+    ) -> ProgramResult {
+        validate_title(&title)?;
+        metadata.validate()?;
 
+        let market = &mut ctx.accounts.market.load_mut()?;
+        market.set_title(&title);
+        market.metadata.set(&metadata);
         Ok(())
     }
 
@@ -82,31 +235,56 @@ pub mod vibe_market {
     )]
     pub fn init_collection(
         ctx: Context<InitCollection>,
-        collection_nonce: u8,
-        list_head_nonce: u8,
-        list_tail_nonce: u8,
         title: String,
     ) -> ProgramResult {
-        let market = &mut ctx.accounts.market;
+        validate_title(&title)?;
+
+        let market_key = ctx.accounts.market.to_account_info().key();
+        let market = &mut ctx.accounts.market.load_mut()?;
         let collection = &mut ctx.accounts.collection;
         collection.index = market.num_collections;
 
         market.num_collections = market
             .num_collections
             .checked_add(1)
-            .ok_or_else(|| ErrorCode::Overflow)?;
+            .ok_or(ErrorCode::Overflow)?;
+        market.num_open_collections = market
+            .num_open_collections
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let collection_key = collection.to_account_info().key();
+        let (_, collection_nonce) = Pubkey::find_program_address(
+            &[
+                market_key.as_ref(),
+                &collection.index.to_le_bytes(),
+                b"collection".as_ref(),
+            ],
+            ctx.program_id,
+        );
+        let (_, list_head_nonce) = Pubkey::find_program_address(
+            &[collection_key.as_ref(), b"head".as_ref()],
+            ctx.program_id,
+        );
+        let (_, list_tail_nonce) = Pubkey::find_program_address(
+            &[collection_key.as_ref(), b"tail".as_ref()],
+            ctx.program_id,
+        );
 
+        collection.version = ACCOUNT_VERSION;
         collection.nonce = collection_nonce;
         collection.title = title;
         collection.list_head = ctx.accounts.list_head.to_account_info().key();
         collection.list_tail = ctx.accounts.list_tail.to_account_info().key();
 
-        let list_head = &mut ctx.accounts.list_head;
+        let list_head = &mut ctx.accounts.list_head.load_init()?;
+        list_head.version = ACCOUNT_VERSION;
         list_head.nonce = list_head_nonce;
         list_head.payer = ctx.accounts.admin.key();
         list_head.next_list_item = ctx.accounts.list_tail.to_account_info().key();
 
-        let list_tail = &mut ctx.accounts.list_tail;
+        let list_tail = &mut ctx.accounts.list_tail.load_init()?;
+        list_tail.version = ACCOUNT_VERSION;
         list_tail.nonce = list_tail_nonce;
         list_tail.payer = ctx.accounts.admin.key();
         list_tail.prev_list_item = ctx.accounts.list_head.to_account_info().key();
@@ -114,63 +292,483 @@ pub mod vibe_market {
         Ok(())
     }
 
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn set_collection_paused(ctx: Context<SetCollectionPaused>, paused: bool) -> ProgramResult {
+        let collection = &mut ctx.accounts.collection;
+        if collection.paused && !paused {
+            collection.unpaused_slot = Clock::get()?.slot;
+        }
+        collection.paused = paused;
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn set_collection_sale_window(
+        ctx: Context<SetCollectionSaleWindow>,
+        sale_start: Option<i64>,
+        sale_end: Option<i64>,
+    ) -> ProgramResult {
+        validate_sale_window(sale_start, sale_end)?;
+
+        let collection = &mut ctx.accounts.collection;
+        collection.sale_start = sale_start;
+        collection.sale_end = sale_end;
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+        NftBucket::is_listed(&ctx.accounts.list_item)
+    )]
+    pub fn set_nft_sale_window(
+        ctx: Context<SetNftSaleWindow>,
+        sale_start: Option<i64>,
+        sale_end: Option<i64>,
+    ) -> ProgramResult {
+        validate_list_item(&ctx.accounts.collection, &ctx.accounts.list_item)?;
+        validate_sale_window(sale_start, sale_end)?;
+
+        let list_item_key = ctx.accounts.list_item.to_account_info().key();
+        let list_item = &mut ctx.accounts.list_item.load_mut()?;
+        list_item.sale_start = sale_start.unwrap_or(0);
+        list_item.sale_end = sale_end.unwrap_or(0);
+        let version = ctx.accounts.collection.record_inventory_change()?;
+        ctx.accounts.inventory_page
+            .load_mut()?
+            .sync_draw_window(list_item.inventory_slot, &list_item_key, list_item, version)?;
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+        NftBucket::is_listed(&ctx.accounts.list_item)
+    )]
+    pub fn set_nft_expiry(
+        ctx: Context<SetNftExpiry>,
+        expires_at: Option<i64>,
+    ) -> ProgramResult {
+        validate_list_item(&ctx.accounts.collection, &ctx.accounts.list_item)?;
+
+        let list_item_key = ctx.accounts.list_item.to_account_info().key();
+        let list_item = &mut ctx.accounts.list_item.load_mut()?;
+        list_item.expires_at = expires_at.unwrap_or(0);
+        let version = ctx.accounts.collection.record_inventory_change()?;
+        ctx.accounts.inventory_page
+            .load_mut()?
+            .sync_draw_window(list_item.inventory_slot, &list_item_key, list_item, version)?;
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn set_mystery_price_model(
+        ctx: Context<SetMysteryPriceModel>,
+        enabled: bool,
+    ) -> ProgramResult {
+        let price_model_key = ctx.accounts.price_model.to_account_info().key();
+        let collection = &mut ctx.accounts.collection;
+        collection.mystery_price_model = if enabled { Some(price_model_key) } else { None };
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn update_collection_metadata(
+        ctx: Context<UpdateCollectionMetadata>,
+        title: String,
+        metadata: Metadata,
+    ) -> ProgramResult {
+        validate_title(&title)?;
+        metadata.validate()?;
+
+        // LEN covers every optional field set, so size from it rather than
+        // the current contents
+        let new_len =
+            Collection::LEN + metadata.try_to_vec()?.len() - Metadata::default().try_to_vec()?.len();
+
+        let collection = &mut ctx.accounts.collection;
+        collection.title = title;
+        collection.metadata = metadata;
+
+        realloc_account(
+            &ctx.accounts.collection.to_account_info(),
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            new_len,
+        )
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn init_inventory_page(ctx: Context<InitInventoryPage>) -> ProgramResult {
+        let collection = &mut ctx.accounts.collection;
+        let index = collection.num_inventory_pages;
+
+        collection.num_inventory_pages = collection
+            .num_inventory_pages
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let (_, nonce) = Pubkey::find_program_address(
+            &[
+                collection.to_account_info().key.as_ref(),
+                &index.to_le_bytes(),
+                b"inventory_page".as_ref(),
+            ],
+            ctx.program_id,
+        );
+
+        let inventory_page = &mut ctx.accounts.inventory_page.load_init()?;
+        inventory_page.collection = collection.to_account_info().key();
+        inventory_page.index = index;
+        inventory_page.nonce = nonce;
+        inventory_page.free_head = 0;
+        for slot_index in 0..INVENTORY_PAGE_SLOTS {
+            let next_free = if slot_index + 1 < INVENTORY_PAGE_SLOTS {
+                (slot_index + 1) as u32
+            } else {
+                INVENTORY_SLOT_NONE
+            };
+            inventory_page.slots[slot_index].next_free = next_free;
+        }
+
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn close_inventory_page(ctx: Context<CloseInventoryPage>) -> ProgramResult {
+        let collection = &mut ctx.accounts.collection;
+        let inventory_page = ctx.accounts.inventory_page.load()?;
+
+        // Only the last page may be closed so page indices stay contiguous
+        if inventory_page.collection != collection.to_account_info().key() ||
+        inventory_page.index + 1 != collection.num_inventory_pages {
+            return Err(ErrorCode::InvalidInventoryPage.into());
+        }
+        if inventory_page.num_occupied > 0 {
+            return Err(ErrorCode::InventoryPageNonEmpty.into());
+        }
+
+        // The page's slot history goes with it, so pending draws can't be made
+        collection.pages_closed_version = collection.record_inventory_change()?;
+        collection.num_inventory_pages = inventory_page.index;
+        Ok(())
+    }
+
     #[access_control(
         Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
     )]
     pub fn init_price_model(
         ctx: Context<InitPriceModel>,
-        nonce: u8,
         sale_prices: Vec<SalePrice>,
     ) -> ProgramResult {
-        let market = &mut ctx.accounts.market;
-        let price_model = &mut ctx.accounts.price_model;
-        price_model.index = market.num_price_models;
+        if sale_prices.len() > SALE_PRICES_MAX_LEN {
+            return Err(ErrorCode::SalePricesOutOfBounds.into());
+        }
+
+        let market_key = ctx.accounts.market.to_account_info().key();
+        let market = &mut ctx.accounts.market.load_mut()?;
+        let price_model = &mut ctx.accounts.price_model.load_init()?;
+        let index = market.num_price_models;
 
         market.num_price_models = market
             .num_price_models
             .checked_add(1)
-            .ok_or_else(|| ErrorCode::Overflow)?;
+            .ok_or(ErrorCode::Overflow)?;
+        market.num_open_price_models = market
+            .num_open_price_models
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let (_, nonce) = Pubkey::find_program_address(
+            &[
+                market_key.as_ref(),
+                &index.to_le_bytes(),
+                b"price_model".as_ref(),
+            ],
+            ctx.program_id,
+        );
 
+        price_model.version = ACCOUNT_VERSION;
+        price_model.index = index;
         price_model.nonce = nonce;
-        price_model.sale_prices = sale_prices;
-        price_model.market = market.to_account_info().key();
+        price_model.sale_prices[..sale_prices.len()].copy_from_slice(&sale_prices);
+        price_model.num_sale_prices = sale_prices.len() as u8;
+        price_model.market = market_key;
+
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn close_price_model(ctx: Context<ClosePriceModel>) -> ProgramResult {
+        if ctx.accounts.price_model.load()?.num_listings > 0 {
+            return Err(ErrorCode::PriceModelInUse.into());
+        }
 
+        let market = &mut ctx.accounts.market.load_mut()?;
+        market.num_open_price_models = market
+            .num_open_price_models
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
         Ok(())
     }
 
     #[access_control(
         Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+        Collection::is_unpaused(&ctx.accounts.market, &ctx.accounts.collection)
     )]
     pub fn add_nft(
         ctx: Context<AddNft>,
     ) -> ProgramResult {
-        let list_head = &mut ctx.accounts.list_head;
-        list_head.next_list_item = ctx.accounts.new_item.to_account_info().key();
+        if ctx.accounts.prev_list_item.to_account_info().key() != ctx.accounts.collection.list_head {
+            return Err(ErrorCode::InvalidListItem.into());
+        }
+        insert_nft(ctx)
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+        Collection::is_unpaused(&ctx.accounts.market, &ctx.accounts.collection)
+    )]
+    pub fn insert_nft_after(
+        ctx: Context<AddNft>,
+    ) -> ProgramResult {
+        validate_list_item(&ctx.accounts.collection, &ctx.accounts.prev_list_item)?;
+        if ctx.accounts.prev_list_item.to_account_info().key() == ctx.accounts.collection.list_tail {
+            return Err(ErrorCode::InvalidListItem.into());
+        }
+        insert_nft(ctx)
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+        NftBucket::is_listed(&ctx.accounts.list_item)
+    )]
+    pub fn move_item(
+        ctx: Context<MoveItem>,
+    ) -> ProgramResult {
+        let collection = &ctx.accounts.collection;
+        let list_item_key = ctx.accounts.list_item.to_account_info().key();
+        validate_list_item(collection, &ctx.accounts.list_item)?;
+        if list_item_key == collection.list_head || list_item_key == collection.list_tail {
+            return Err(ErrorCode::InvalidListItem.into());
+        }
 
-        let next_list_item = &mut ctx.accounts.next_list_item;
-        next_list_item.prev_list_item = ctx.accounts.new_item.to_account_info().key();
+        let prev_key = ctx.accounts.prev_list_item.key();
+        let next_key = ctx.accounts.next_list_item.key();
+        let new_prev_key = ctx.accounts.new_prev_list_item.key();
+        let new_next_key = ctx.accounts.new_next_list_item.key();
+        if new_prev_key == list_item_key || new_prev_key == prev_key {
+            return Err(ErrorCode::InvalidMove.into());
+        }
 
-        let new_item = &mut ctx.accounts.new_item;
-        new_item.nft_mint = ctx.accounts.admin_nft_mint.to_account_info().key();
-        new_item.price_model = ctx.accounts.price_model.to_account_info().key();
-        new_item.prev_list_item = ctx.accounts.list_head.to_account_info().key();
-        new_item.next_list_item = ctx.accounts.next_list_item.to_account_info().key();
-        new_item.payer = ctx.accounts.admin.key();
-        
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.admin_nft_account.to_account_info(),
-            to: ctx.accounts.program_nft_account.to_account_info(),
-            authority: ctx.accounts.admin.to_account_info(),
+        // Neighbours alias each other when moving by one position, so each
+        // write goes straight to account data instead of a cached copy
+        let prev_list_item = AccountLoader::<NftBucket>::try_from(&ctx.accounts.prev_list_item)?;
+        let next_list_item = AccountLoader::<NftBucket>::try_from(&ctx.accounts.next_list_item)?;
+        let new_prev_list_item = AccountLoader::<NftBucket>::try_from(&ctx.accounts.new_prev_list_item)?;
+        let new_next_list_item = AccountLoader::<NftBucket>::try_from(&ctx.accounts.new_next_list_item)?;
+
+        validate_list_item(collection, &new_prev_list_item)?;
+        if new_prev_key == collection.list_tail ||
+        new_prev_list_item.load()?.next_list_item != new_next_key {
+            return Err(ErrorCode::InvalidListItem.into());
+        }
+
+        // Unlink from current position
+        prev_list_item.load_mut()?.next_list_item = next_key;
+        next_list_item.load_mut()?.prev_list_item = prev_key;
+
+        // Link into new position
+        new_prev_list_item.load_mut()?.next_list_item = list_item_key;
+        new_next_list_item.load_mut()?.prev_list_item = list_item_key;
+
+        let list_item = &mut ctx.accounts.list_item.load_mut()?;
+        list_item.prev_list_item = new_prev_key;
+        list_item.next_list_item = new_next_key;
+
+        Ok(())
+    }
+
+    // View, sets a ListPageData as return data. Remaining accounts are the
+    // buckets following cursor, in list order; tombstoned buckets are walked
+    // but left out of the page.
+    pub fn list_page<'info>(
+        ctx: Context<'_, '_, '_, 'info, ListPage<'info>>,
+        limit: u8,
+    ) -> ProgramResult {
+        let collection = &ctx.accounts.collection;
+        validate_list_item(collection, &ctx.accounts.cursor)?;
+        let limit = (limit as usize).min(LIST_PAGE_MAX_LEN);
+
+        let mut items = Vec::with_capacity(limit);
+        let mut prev_key = ctx.accounts.cursor.to_account_info().key();
+        let mut next_key = ctx.accounts.cursor.load()?.next_list_item;
+        let mut remaining_accounts = ctx.remaining_accounts.iter();
+        while next_key != collection.list_tail && items.len() < limit {
+            let list_item_info = match remaining_accounts.next() {
+                Some(list_item_info) => list_item_info,
+                None => break,
+            };
+            if *list_item_info.key != next_key {
+                return Err(ErrorCode::InvalidListItem.into());
+            }
+            let list_item = AccountLoader::<NftBucket>::try_from(list_item_info)?;
+            validate_list_item(collection, &list_item)?;
+            let nft_bucket = list_item.load()?;
+            if nft_bucket.prev_list_item != prev_key {
+                return Err(ErrorCode::InvalidListItem.into());
+            }
+            if nft_bucket.tombstoned == 0 {
+                items.push(ListPageItem {
+                    nft_bucket: next_key,
+                    nft_mint: nft_bucket.nft_mint,
+                });
+            }
+            prev_key = next_key;
+            next_key = nft_bucket.next_list_item;
+        }
+
+        let page = ListPageData {
+            items,
+            next_cursor: if next_key == collection.list_tail {
+                None
+            } else {
+                Some(prev_key)
+            },
         };
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        transfer(cpi_ctx, 1)?;
+        set_return_data(&page.try_to_vec()?);
+        Ok(())
+    }
+
+    // View, sets a ListVerification as return data. Remaining accounts are the
+    // buckets following cursor, in the order its next pointers give; the walk
+    // stops at the tail, the first inconsistency, or the last account passed.
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn verify_collection<'info>(
+        ctx: Context<'_, '_, '_, 'info, VerifyCollection<'info>>,
+    ) -> ProgramResult {
+        let collection = &ctx.accounts.collection;
+        validate_list_item(collection, &ctx.accounts.cursor)?;
+
+        let mut verification = ListVerification::default();
+        let mut prev_key = ctx.accounts.cursor.to_account_info().key();
+        let mut next_key = ctx.accounts.cursor.load()?.next_list_item;
+        for list_item_info in ctx.remaining_accounts.iter() {
+            if prev_key == collection.list_tail {
+                break;
+            }
+            if *list_item_info.key != next_key {
+                return Err(ErrorCode::InvalidListItem.into());
+            }
+            let list_item = match AccountLoader::<NftBucket>::try_from(list_item_info) {
+                Ok(list_item) if validate_list_item(collection, &list_item).is_ok() => list_item,
+                _ => {
+                    verification.inconsistency = Some(ListInconsistency::InvalidItem {
+                        list_item: next_key,
+                    });
+                    break;
+                }
+            };
+            let nft_bucket = list_item.load()?;
+            if nft_bucket.prev_list_item != prev_key {
+                verification.inconsistency = Some(ListInconsistency::BrokenPrevLink {
+                    list_item: next_key,
+                    expected: prev_key,
+                    found: nft_bucket.prev_list_item,
+                });
+                break;
+            }
+            if next_key != collection.list_tail {
+                verification.count += 1;
+                if nft_bucket.tombstoned != 0 {
+                    verification.num_tombstoned += 1;
+                }
+            }
+            prev_key = next_key;
+            next_key = nft_bucket.next_list_item;
+        }
+
+        if verification.inconsistency.is_none() && prev_key != collection.list_tail {
+            verification.next_cursor = Some(prev_key);
+        }
+        set_return_data(&verification.try_to_vec()?);
+        Ok(())
+    }
+
+    // Points prev_list_item and next_list_item at each other. Only for links
+    // verify_collection reports as broken: the item prev_list_item links to
+    // must not link back to it. Remaining accounts are the items dropped
+    // from between the two, in the order their next pointers give, and each
+    // must be tombstoned or not an item of the collection at all.
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn repair_link<'info>(
+        ctx: Context<'_, '_, '_, 'info, RepairLink<'info>>,
+    ) -> ProgramResult {
+        let collection = &ctx.accounts.collection;
+        validate_list_item(collection, &ctx.accounts.prev_list_item)?;
+        validate_list_item(collection, &ctx.accounts.next_list_item)?;
+
+        let prev_key = ctx.accounts.prev_list_item.to_account_info().key();
+        let next_key = ctx.accounts.next_list_item.to_account_info().key();
+        if prev_key == next_key ||
+        prev_key == collection.list_tail ||
+        next_key == collection.list_head {
+            return Err(ErrorCode::InvalidRepair.into());
+        }
+
+        let linked_key = ctx.accounts.prev_list_item.load()?.next_list_item;
+        if linked_key == next_key && ctx.accounts.next_list_item.load()?.prev_list_item == prev_key {
+            return Err(ErrorCode::ListLinkIntact.into());
+        }
+
+        let mut skipped_key = linked_key;
+        let mut skipped_items = ctx.remaining_accounts.iter();
+        while skipped_key != next_key {
+            let skipped_info = skipped_items.next().ok_or(ErrorCode::InvalidRepair)?;
+            if *skipped_info.key != skipped_key {
+                return Err(ErrorCode::InvalidListItem.into());
+            }
+            let skipped = match AccountLoader::<NftBucket>::try_from(skipped_info) {
+                Ok(skipped) if validate_list_item(collection, &skipped).is_ok() => skipped,
+                // Its next pointer can't be trusted, nothing past it is linked
+                _ => break,
+            };
+            let nft_bucket = skipped.load()?;
+            if skipped_key == linked_key && nft_bucket.prev_list_item == prev_key {
+                return Err(ErrorCode::ListLinkIntact.into());
+            }
+            if nft_bucket.tombstoned == 0 {
+                return Err(ErrorCode::InvalidRepair.into());
+            }
+            skipped_key = nft_bucket.next_list_item;
+        }
+
+        ctx.accounts.prev_list_item.load_mut()?.next_list_item = next_key;
+        ctx.accounts.next_list_item.load_mut()?.prev_list_item = prev_key;
 
         Ok(())
     }
 
     #[access_control(
         Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+        NftBucket::is_listed(&ctx.accounts.withdraw_list_item)
     )]
     pub fn withdraw_nft(
         ctx: Context<WithdrawNft>,
@@ -206,39 +804,76 @@ pub mod vibe_market {
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
         close_account(cpi_ctx)?;
 
-        // Remove item from linked-list
-        let prev_list_item = &mut ctx.accounts.prev_list_item;
-        let next_list_item = &mut ctx.accounts.next_list_item;
-        prev_list_item.next_list_item = next_list_item.to_account_info().key();
-        next_list_item.prev_list_item = prev_list_item.to_account_info().key();
+        // Release inventory slot
+        let withdraw_list_item = &mut ctx.accounts.withdraw_list_item.load_mut()?;
+        let inventory_page = &mut ctx.accounts.inventory_page.load_mut()?;
+        let version = ctx.accounts.collection.record_inventory_change()?;
+        inventory_page.remove(
+            withdraw_list_item.inventory_slot,
+            &ctx.accounts.withdraw_list_item.to_account_info().key(),
+            version,
+        )?;
+
+        // Tombstone item, it is unlinked later by compact_list_item
+        withdraw_list_item.tombstoned = 1;
+
+        let price_model = &mut ctx.accounts.price_model.load_mut()?;
+        price_model.num_listings = price_model
+            .num_listings
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        ctx.accounts.market.load_mut()?.stats.record_delisting()?;
+        ctx.accounts.collection.stats.record_delisting()?;
+
+        Ok(())
+    }
 
+    // View, sets the Vec<PriceQuote> purchase_nft would charge as return data.
+    // Fails with the same errors as purchase_nft when the item can't be bought.
+    #[access_control(
+        Collection::is_unpaused(&ctx.accounts.market, &ctx.accounts.collection)
+        NftBucket::is_listed(&ctx.accounts.list_item)
+        NftBucket::is_on_sale(&ctx.accounts.collection, &ctx.accounts.list_item)
+    )]
+    pub fn quote_purchase(
+        ctx: Context<QuotePurchase>,
+    ) -> ProgramResult {
+        validate_list_item(&ctx.accounts.collection, &ctx.accounts.list_item)?;
+        let price_model = ctx.accounts.price_model.load()?;
+        let quotes = price_model.sale_prices[..price_model.num_sale_prices as usize]
+            .iter()
+            .map(|sale_price| ctx.accounts.global_state.quote(sale_price.mint, sale_price.amount))
+            .collect::<Result<Vec<PriceQuote>>>()?;
+        set_return_data(&quotes.try_to_vec()?);
         Ok(())
     }
 
+    #[access_control(
+        Collection::is_unpaused(&ctx.accounts.market, &ctx.accounts.collection)
+        NftBucket::is_listed(&ctx.accounts.purchase_list_item)
+        NftBucket::is_on_sale(&ctx.accounts.collection, &ctx.accounts.purchase_list_item)
+    )]
     pub fn purchase_nft(
         ctx: Context<PurchaseNft>,
     ) -> ProgramResult {
-        let price_model = &ctx.accounts.price_model;
-        let debit_mint = &ctx.accounts.debit_mint;
-
         // Check debit mint
-        let sale_price_option = price_model.sale_prices.iter().find(|sp| sp.mint == debit_mint.key());
-        let sale_price = match sale_price_option {
-            Some(sale_price) => sale_price,
-            None => return Err(ErrorCode::InvalidPurchaseMint.into())
-        };
+        let sale_amount = ctx.accounts.price_model
+            .load()?
+            .sale_price(&ctx.accounts.debit_mint.key())?;
+        let quote = ctx.accounts.global_state.quote(ctx.accounts.debit_mint.key(), sale_amount)?;
 
         // Collect payment
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.debit_account.to_account_info(),
-            to: ctx.accounts.program_credit_account.to_account_info(),
-            authority: ctx.accounts.owner.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        transfer(cpi_ctx, sale_price.amount)?;
+        let amount = collect_payment(
+            &quote,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.debit_account.to_account_info(),
+            &ctx.accounts.fee_recipient,
+            &ctx.accounts.fee_credit_account,
+            &ctx.accounts.program_credit_account.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
 
-        // Transfer NFT
         let market = &ctx.accounts.market;
         let collection = &ctx.accounts.collection;
 
@@ -249,7 +884,16 @@ pub mod vibe_market {
             &[collection.nonce],
         ];
         let signer = &[&seeds[..]];
-        
+
+        track_treasury_account(
+            &ctx.accounts.program_credit_account,
+            &ctx.accounts.collection.to_account_info(),
+            &mut ctx.accounts.collection.num_treasury_accounts,
+            &ctx.accounts.token_program.to_account_info(),
+            signer,
+        )?;
+
+        // Transfer NFT
         let cpi_program = ctx.accounts.token_program.to_account_info();
         let cpi_accounts = Transfer {
             from: ctx.accounts.program_nft_account.to_account_info(),
@@ -269,27 +913,382 @@ pub mod vibe_market {
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
         close_account(cpi_ctx)?;
 
-        // Remove item from linked-list
-        let prev_list_item = &mut ctx.accounts.prev_list_item;
-        let next_list_item = &mut ctx.accounts.next_list_item;
-        prev_list_item.next_list_item = next_list_item.to_account_info().key();
-        next_list_item.prev_list_item = prev_list_item.to_account_info().key();
+        // Release inventory slot
+        let purchase_list_item = &mut ctx.accounts.purchase_list_item.load_mut()?;
+        let inventory_page = &mut ctx.accounts.inventory_page.load_mut()?;
+        let version = ctx.accounts.collection.record_inventory_change()?;
+        inventory_page.remove(
+            purchase_list_item.inventory_slot,
+            &ctx.accounts.purchase_list_item.to_account_info().key(),
+            version,
+        )?;
+
+        // Tombstone item, it is unlinked later by compact_list_item
+        purchase_list_item.tombstoned = 1;
+
+        let price_model = &mut ctx.accounts.price_model.load_mut()?;
+        price_model.num_listings = price_model
+            .num_listings
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let last_sale = LastSale {
+            nft_mint: purchase_list_item.nft_mint,
+            debit_mint: ctx.accounts.debit_mint.key(),
+            amount,
+            buyer: ctx.accounts.owner.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        };
+        ctx.accounts.market.load_mut()?.stats.record_sale(&last_sale)?;
+        ctx.accounts.collection.stats.record_sale(&last_sale)?;
 
         Ok(())
     }
 
     #[access_control(
-        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+        NftBucket::is_listed(&ctx.accounts.expired_list_item)
+        NftBucket::is_expired(&ctx.accounts.expired_list_item)
     )]
-    pub fn withdraw_liquidity(
-        ctx: Context<WithdrawLiquidity>, amount: u64
+    pub fn crank_expired(
+        ctx: Context<CrankExpired>,
     ) -> ProgramResult {
+        validate_list_item(&ctx.accounts.collection, &ctx.accounts.expired_list_item)?;
+
+        // Return NFT to original lister
         let market = &ctx.accounts.market;
-        let global_state_key = ctx.accounts.global_state.to_account_info().key();
+        let collection = &ctx.accounts.collection;
+
         let seeds = &[
-            global_state_key.as_ref(),
-            &market.index.to_le_bytes(),
-            &[market.nonce],
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+            &[collection.nonce],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.program_nft_account.to_account_info(),
+            to: ctx.accounts.payer_nft_account.to_account_info(),
+            authority: ctx.accounts.collection.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        transfer(cpi_ctx, 1)?;
+
+        // Close NFT token account
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.program_nft_account.to_account_info(),
+            destination: ctx.accounts.rent_refund.to_account_info(),
+            authority: ctx.accounts.collection.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        close_account(cpi_ctx)?;
+
+        // Release inventory slot
+        let inventory_page = &mut ctx.accounts.inventory_page.load_mut()?;
+        let version = ctx.accounts.collection.record_inventory_change()?;
+        inventory_page.remove(
+            ctx.accounts.expired_list_item.load()?.inventory_slot,
+            &ctx.accounts.expired_list_item.to_account_info().key(),
+            version,
+        )?;
+
+        // Remove item from linked-list, the item itself is closed to rent_refund
+        let prev_list_item_key = ctx.accounts.prev_list_item.to_account_info().key();
+        let next_list_item_key = ctx.accounts.next_list_item.to_account_info().key();
+        ctx.accounts.prev_list_item.load_mut()?.next_list_item = next_list_item_key;
+        ctx.accounts.next_list_item.load_mut()?.prev_list_item = prev_list_item_key;
+
+        let price_model = &mut ctx.accounts.price_model.load_mut()?;
+        price_model.num_listings = price_model
+            .num_listings
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        ctx.accounts.market.load_mut()?.stats.record_delisting()?;
+        ctx.accounts.collection.stats.record_delisting()?;
+
+        Ok(())
+    }
+
+    #[access_control(
+        Collection::is_unpaused(&ctx.accounts.market, &ctx.accounts.collection)
+    )]
+    pub fn commit_random_purchase(
+        ctx: Context<CommitRandomPurchase>,
+    ) -> ProgramResult {
+        let collection = &ctx.accounts.collection;
+        if collection.mystery_price_model != Some(ctx.accounts.price_model.to_account_info().key()) {
+            return Err(ErrorCode::MysteryBoxDisabled.into());
+        }
+        let clock = Clock::get()?;
+        check_sale_window(collection.sale_start, collection.sale_end, clock.unix_timestamp)?;
+
+        // Check debit mint
+        let sale_amount = ctx.accounts.price_model
+            .load()?
+            .sale_price(&ctx.accounts.debit_mint.key())?;
+        let quote = ctx.accounts.global_state.quote(ctx.accounts.debit_mint.key(), sale_amount)?;
+
+        // Collect payment up front, a draw that isn't revealed in time is forfeited
+        let amount = collect_payment(
+            &quote,
+            &ctx.accounts.owner.to_account_info(),
+            &ctx.accounts.debit_account.to_account_info(),
+            &ctx.accounts.fee_recipient,
+            &ctx.accounts.fee_credit_account,
+            &ctx.accounts.program_credit_account.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+        )?;
+
+        let seeds = &[
+            ctx.accounts.market.to_account_info().key.as_ref(),
+            &ctx.accounts.collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+            &[ctx.accounts.collection.nonce],
+        ];
+        let signer = &[&seeds[..]];
+        track_treasury_account(
+            &ctx.accounts.program_credit_account,
+            &ctx.accounts.collection.to_account_info(),
+            &mut ctx.accounts.collection.num_treasury_accounts,
+            &ctx.accounts.token_program.to_account_info(),
+            signer,
+        )?;
+
+        let (_, nonce) = Pubkey::find_program_address(
+            &[
+                ctx.accounts.collection.to_account_info().key.as_ref(),
+                ctx.accounts.owner.key.as_ref(),
+                b"mystery_commit".as_ref(),
+            ],
+            ctx.program_id,
+        );
+
+        let mystery_commit = &mut ctx.accounts.mystery_commit;
+        mystery_commit.nonce = nonce;
+        mystery_commit.owner = ctx.accounts.owner.key();
+        mystery_commit.collection = ctx.accounts.collection.to_account_info().key();
+        mystery_commit.commit_slot = clock.slot;
+        mystery_commit.debit_mint = ctx.accounts.debit_mint.key();
+        mystery_commit.amount = amount;
+        mystery_commit.commit_timestamp = clock.unix_timestamp;
+        mystery_commit.inventory_version = ctx.accounts.collection.inventory_version;
+
+        Ok(())
+    }
+
+    // Anyone can reveal a draw, it only depends on the slot hash following the
+    // commit. Draws are taken from the buckets on sale at the commit, and are
+    // delivered even if their sale terms have changed since.
+    #[access_control(
+        NftBucket::is_listed(&ctx.accounts.purchase_list_item)
+    )]
+    pub fn reveal_random_purchase<'info>(
+        ctx: Context<'_, '_, '_, 'info, RevealRandomPurchase<'info>>,
+    ) -> ProgramResult {
+        let mystery_commit = &ctx.accounts.mystery_commit;
+        if mystery_commit.is_void(&*ctx.accounts.market.load()?, &ctx.accounts.collection) {
+            return Err(ErrorCode::DrawVoided.into());
+        }
+        let slot_hash = match reveal_slot_hash(&ctx.accounts.slot_hashes, mystery_commit.commit_slot)? {
+            Some(slot_hash) => slot_hash,
+            None => return Err(ErrorCode::RevealWindowElapsed.into()),
+        };
+
+        let (page_position, slot_index, nft_bucket) = match draw_inventory_slot(
+            &ctx.accounts.collection,
+            ctx.remaining_accounts,
+            mystery_commit,
+            draw_random(&slot_hash, &mystery_commit.key()),
+        )? {
+            Draw::Empty => return Err(ErrorCode::InventoryEmpty.into()),
+            Draw::Unknown | Draw::Slot { nft_bucket: None, .. } => {
+                return Err(ErrorCode::DrawVoided.into());
+            }
+            Draw::Slot { page_position, slot_index, nft_bucket: Some(nft_bucket) } => {
+                (page_position, slot_index, nft_bucket)
+            }
+        };
+        if nft_bucket != ctx.accounts.purchase_list_item.to_account_info().key() {
+            return Err(ErrorCode::InvalidRandomSelection.into());
+        }
+
+        // Transfer NFT
+        let market = &ctx.accounts.market;
+        let collection = &ctx.accounts.collection;
+
+        let seeds = &[
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+            &[collection.nonce],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.program_nft_account.to_account_info(),
+            to: ctx.accounts.owner_nft_account.to_account_info(),
+            authority: ctx.accounts.collection.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        transfer(cpi_ctx, 1)?;
+
+        // Close NFT token account
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = CloseAccount {
+            account: ctx.accounts.program_nft_account.to_account_info(),
+            destination: ctx.accounts.rent_refund.to_account_info(),
+            authority: ctx.accounts.collection.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        close_account(cpi_ctx)?;
+
+        // Release inventory slot
+        let inventory_page = AccountLoader::<InventoryPage>::try_from(
+            &ctx.remaining_accounts[page_position],
+        )?;
+        let version = ctx.accounts.collection.record_inventory_change()?;
+        inventory_page.load_mut()?.remove(slot_index, &nft_bucket, version)?;
+
+        // Tombstone item, it is unlinked later by compact_list_item
+        let purchase_list_item = &mut ctx.accounts.purchase_list_item.load_mut()?;
+        purchase_list_item.tombstoned = 1;
+
+        let price_model = &mut ctx.accounts.price_model.load_mut()?;
+        price_model.num_listings = price_model
+            .num_listings
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let last_sale = LastSale {
+            nft_mint: purchase_list_item.nft_mint,
+            debit_mint: ctx.accounts.mystery_commit.debit_mint,
+            amount: ctx.accounts.mystery_commit.amount,
+            buyer: ctx.accounts.owner.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        };
+        ctx.accounts.market.load_mut()?.stats.record_sale(&last_sale)?;
+        ctx.accounts.collection.stats.record_sale(&last_sale)?;
+
+        Ok(())
+    }
+
+    // Only a draw that can't be delivered is refunded: one voided since the
+    // commit, one with nothing to draw from, or one whose drawn bucket has
+    // left the inventory while it could still be revealed
+    pub fn refund_random_purchase<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefundRandomPurchase<'info>>,
+    ) -> ProgramResult {
+        let mystery_commit = &ctx.accounts.mystery_commit;
+        if !mystery_commit.is_void(&*ctx.accounts.market.load()?, &ctx.accounts.collection) {
+            let draw = draw_inventory_slot(
+                &ctx.accounts.collection,
+                ctx.remaining_accounts,
+                mystery_commit,
+                0,
+            )?;
+            if let Draw::Slot { .. } = draw {
+                let commit_slot = mystery_commit.commit_slot;
+                let slot_hash = match reveal_slot_hash(&ctx.accounts.slot_hashes, commit_slot) {
+                    Ok(Some(slot_hash)) => slot_hash,
+                    _ => return Err(ErrorCode::RefundUnavailable.into()),
+                };
+                let draw = draw_inventory_slot(
+                    &ctx.accounts.collection,
+                    ctx.remaining_accounts,
+                    mystery_commit,
+                    draw_random(&slot_hash, &mystery_commit.key()),
+                )?;
+                if let Draw::Slot { nft_bucket: Some(_), .. } = draw {
+                    return Err(ErrorCode::RefundUnavailable.into());
+                }
+            }
+        }
+
+        let market = &ctx.accounts.market;
+        let collection = &ctx.accounts.collection;
+
+        let seeds = &[
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+            &[collection.nonce],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.program_credit_account.to_account_info(),
+            to: ctx.accounts.debit_account.to_account_info(),
+            authority: ctx.accounts.collection.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        transfer(cpi_ctx, mystery_commit.amount)?;
+
+        Ok(())
+    }
+
+    pub fn expire_random_purchase<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExpireRandomPurchase<'info>>,
+    ) -> ProgramResult {
+        // A draw that could have been revealed but wasn't keeps its payment in
+        // the treasury, draws that couldn't be delivered are refunded instead
+        let mystery_commit = &ctx.accounts.mystery_commit;
+        if reveal_slot_hash(&ctx.accounts.slot_hashes, mystery_commit.commit_slot)?.is_some() {
+            return Err(ErrorCode::RevealWindowOpen.into());
+        }
+        if mystery_commit.is_void(&*ctx.accounts.market.load()?, &ctx.accounts.collection) {
+            return Err(ErrorCode::DrawVoided.into());
+        }
+        let draw = draw_inventory_slot(
+            &ctx.accounts.collection,
+            ctx.remaining_accounts,
+            mystery_commit,
+            0,
+        )?;
+        match draw {
+            Draw::Empty => return Err(ErrorCode::InventoryEmpty.into()),
+            Draw::Unknown => return Err(ErrorCode::DrawVoided.into()),
+            Draw::Slot { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn compact_list_item(
+        ctx: Context<CompactListItem>,
+    ) -> ProgramResult {
+        if ctx.accounts.list_item.load()?.tombstoned == 0 {
+            return Err(ErrorCode::ListItemNotTombstoned.into());
+        }
+
+        // Remove item from linked-list
+        let prev_list_item_key = ctx.accounts.prev_list_item.to_account_info().key();
+        let next_list_item_key = ctx.accounts.next_list_item.to_account_info().key();
+        ctx.accounts.prev_list_item.load_mut()?.next_list_item = next_list_item_key;
+        ctx.accounts.next_list_item.load_mut()?.prev_list_item = prev_list_item_key;
+
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn withdraw_liquidity(
+        ctx: Context<WithdrawLiquidity>, amount: u64
+    ) -> ProgramResult {
+        let (market_index, market_nonce) = {
+            let market = ctx.accounts.market.load()?;
+            (market.index, market.nonce)
+        };
+        let global_state_key = ctx.accounts.global_state.to_account_info().key();
+        let seeds = &[
+            global_state_key.as_ref(),
+            &market_index.to_le_bytes(),
+            &[market_nonce],
         ];
         let signer = &[&seeds[..]];
         
@@ -301,164 +1300,2028 @@ pub mod vibe_market {
         };
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
         transfer(cpi_ctx, amount)?;
+
+        track_market_treasury_account(
+            &ctx.accounts.program_debit_account,
+            &ctx.accounts.market,
+            &ctx.accounts.token_program.to_account_info(),
+            signer,
+        )
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn withdraw_collection_liquidity(
+        ctx: Context<WithdrawCollectionLiquidity>, amount: u64
+    ) -> ProgramResult {
+        let market = &ctx.accounts.market;
+        let collection = &ctx.accounts.collection;
+
+        let seeds = &[
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+            &[collection.nonce],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.program_debit_account.to_account_info(),
+            to: ctx.accounts.admin_credit_account.to_account_info(),
+            authority: ctx.accounts.collection.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        transfer(cpi_ctx, amount)?;
         Ok(())
     }
 
+    // Lists an NFT that was transferred straight to the collection's
+    // associated token account, directly after prev_list_item. Every
+    // inventory page follows the accounts, in index order, so an NFT already
+    // held by a migrated keypair bucket isn't listed twice.
     #[access_control(
         Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+        Collection::is_unpaused(&ctx.accounts.market, &ctx.accounts.collection)
     )]
-    pub fn close_collection(ctx: Context<CloseCollection>) -> ProgramResult {
-        let list_head = &ctx.accounts.list_head;
-        let list_tail = &ctx.accounts.list_tail;
-        if list_head.next_list_item != list_tail.to_account_info().key() ||
-        list_tail.prev_list_item != list_head.to_account_info().key() {
-            return Err(ErrorCode::CollectionNonEmpty.into());
+    pub fn adopt_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, AdoptNft<'info>>,
+    ) -> ProgramResult {
+        validate_list_item(&ctx.accounts.collection, &ctx.accounts.prev_list_item)?;
+        if ctx.accounts.prev_list_item.to_account_info().key() == ctx.accounts.collection.list_tail {
+            return Err(ErrorCode::InvalidListItem.into());
+        }
+        if ctx.accounts.program_nft_account.amount != 1 {
+            return Err(ErrorCode::InvalidRescueAmount.into());
+        }
+        // Unmigrated legacy buckets hold their NFTs in the same accounts
+        let collection = &ctx.accounts.collection;
+        if collection.legacy_cursor != Pubkey::default() || collection.num_legacy_buckets > 0 {
+            return Err(ErrorCode::LegacyBucketsPending.into());
+        }
+        let nft_mint_key = ctx.accounts.nft_mint.to_account_info().key();
+        if inventory_holds_mint(collection, ctx.remaining_accounts, &nft_mint_key)? {
+            return Err(ErrorCode::InvalidListItem.into());
+        }
+
+        let (_, nonce) = Pubkey::find_program_address(
+            &[
+                ctx.accounts.collection.to_account_info().key.as_ref(),
+                ctx.accounts.nft_mint.to_account_info().key.as_ref(),
+                b"nft_bucket".as_ref(),
+            ],
+            ctx.program_id,
+        );
+
+        let new_item_key = ctx.accounts.new_item.to_account_info().key();
+        ctx.accounts.prev_list_item.load_mut()?.next_list_item = new_item_key;
+        ctx.accounts.next_list_item.load_mut()?.prev_list_item = new_item_key;
+
+        let inventory_page = &mut ctx.accounts.inventory_page.load_mut()?;
+        if inventory_page.collection != ctx.accounts.collection.to_account_info().key() {
+            return Err(ErrorCode::InvalidInventoryPage.into());
         }
+        let version = ctx.accounts.collection.record_inventory_change()?;
+        let inventory_slot = inventory_page.insert(
+            new_item_key,
+            ctx.accounts.nft_mint.to_account_info().key(),
+            version,
+        )?;
+
+        let new_item = &mut ctx.accounts.new_item.load_init()?;
+        new_item.version = ACCOUNT_VERSION;
+        new_item.nonce = nonce;
+        new_item.nft_mint = ctx.accounts.nft_mint.to_account_info().key();
+        new_item.price_model = ctx.accounts.price_model.to_account_info().key();
+        new_item.prev_list_item = ctx.accounts.prev_list_item.to_account_info().key();
+        new_item.next_list_item = ctx.accounts.next_list_item.to_account_info().key();
+        new_item.payer = ctx.accounts.admin.key();
+        new_item.inventory_page = ctx.accounts.inventory_page.to_account_info().key();
+        new_item.inventory_slot = inventory_slot;
+
+        let price_model = &mut ctx.accounts.price_model.load_mut()?;
+        price_model.num_listings = price_model
+            .num_listings
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        ctx.accounts.market.load_mut()?.stats.record_listing()?;
+        ctx.accounts.collection.stats.record_listing()?;
+
+        emit!(NftAdopted {
+            admin: ctx.accounts.admin.key(),
+            market: ctx.accounts.market.to_account_info().key(),
+            collection: ctx.accounts.collection.to_account_info().key(),
+            nft_mint: ctx.accounts.nft_mint.to_account_info().key(),
+            nft_bucket: new_item_key,
+        });
         Ok(())
     }
-}
 
-/************************/
-/* INSTRUCTION ACCOUNTS */
-/************************/
+    // Sends tokens held by the collection to recipient. Tokens of a mint with
+    // a listing that isn't tombstoned can only leave through withdraw_nft.
+    // Every inventory page follows the accounts, in index order, so listings
+    // migrated from keypair accounts are found by mint.
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn return_collection_tokens<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReturnCollectionTokens<'info>>, amount: u64
+    ) -> ProgramResult {
+        let market = &ctx.accounts.market;
+        let collection = &ctx.accounts.collection;
+        let mint_key = ctx.accounts.mint.to_account_info().key();
 
-#[derive(Accounts)]
-#[instruction(
-    nonce: u8
-)]
-pub struct InitGlobalState<'info> {
-    admin: Signer<'info>,
+        let (nft_bucket_key, _) = Pubkey::find_program_address(
+            &[
+                collection.to_account_info().key.as_ref(),
+                mint_key.as_ref(),
+                b"nft_bucket".as_ref(),
+            ],
+            ctx.program_id,
+        );
+        let nft_bucket_info = ctx.accounts.nft_bucket.to_account_info();
+        if nft_bucket_info.key() != nft_bucket_key {
+            return Err(ErrorCode::InvalidListItem.into());
+        }
+        if !nft_bucket_info.data_is_empty() {
+            let nft_bucket = AccountLoader::<NftBucket>::try_from(&nft_bucket_info)?;
+            if nft_bucket.load()?.tombstoned == 0 {
+                return Err(ErrorCode::ListItemNotTombstoned.into());
+            }
+        }
+        if inventory_holds_mint(collection, ctx.remaining_accounts, &mint_key)? {
+            return Err(ErrorCode::ListItemNotTombstoned.into());
+        }
+        if amount == 0 || amount > ctx.accounts.program_debit_account.amount {
+            return Err(ErrorCode::InvalidRescueAmount.into());
+        }
+
+        let seeds = &[
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+            &[collection.nonce],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.program_debit_account.to_account_info(),
+            to: ctx.accounts.recipient_credit_account.to_account_info(),
+            authority: ctx.accounts.collection.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        transfer(cpi_ctx, amount)?;
+
+        emit!(TokensReturned {
+            admin: ctx.accounts.admin.key(),
+            market: market.to_account_info().key(),
+            collection: Some(collection.to_account_info().key()),
+            mint: mint_key,
+            amount,
+            recipient: ctx.accounts.recipient.key(),
+        });
+        Ok(())
+    }
+
+    // Sends tokens held by the market to recipient
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn return_market_tokens(
+        ctx: Context<ReturnMarketTokens>, amount: u64
+    ) -> ProgramResult {
+        if amount == 0 || amount > ctx.accounts.program_debit_account.amount {
+            return Err(ErrorCode::InvalidRescueAmount.into());
+        }
+
+        let (market_index, market_nonce) = {
+            let market = ctx.accounts.market.load()?;
+            (market.index, market.nonce)
+        };
+        let global_state_key = ctx.accounts.global_state.to_account_info().key();
+        let seeds = &[
+            global_state_key.as_ref(),
+            &market_index.to_le_bytes(),
+            &[market_nonce],
+        ];
+        let signer = &[&seeds[..]];
+
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.program_debit_account.to_account_info(),
+            to: ctx.accounts.recipient_credit_account.to_account_info(),
+            authority: ctx.accounts.market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        transfer(cpi_ctx, amount)?;
+        track_market_treasury_account(
+            &ctx.accounts.program_debit_account,
+            &ctx.accounts.market,
+            &ctx.accounts.token_program.to_account_info(),
+            signer,
+        )?;
+
+        emit!(TokensReturned {
+            admin: ctx.accounts.admin.key(),
+            market: ctx.accounts.market.to_account_info().key(),
+            collection: None,
+            mint: ctx.accounts.mint.to_account_info().key(),
+            amount,
+            recipient: ctx.accounts.recipient.key(),
+        });
+        Ok(())
+    }
+
+    // Tracks a token account held by the market, e.g. one holding proceeds
+    // from before they were paid to collections, so close_market requires it.
+    // Anyone can call this, so funds can't be left behind by an admin.
+    pub fn track_market_treasury(ctx: Context<TrackMarketTreasury>) -> ProgramResult {
+        let (market_index, market_nonce) = {
+            let market = ctx.accounts.market.load()?;
+            (market.index, market.nonce)
+        };
+        let global_state_key = ctx.accounts.global_state.to_account_info().key();
+        let seeds = &[
+            global_state_key.as_ref(),
+            &market_index.to_le_bytes(),
+            &[market_nonce],
+        ];
+        let signer = &[&seeds[..]];
+        track_market_treasury_account(
+            &ctx.accounts.treasury,
+            &ctx.accounts.market,
+            &ctx.accounts.token_program.to_account_info(),
+            signer,
+        )
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn close_collection<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseCollection<'info>>,
+    ) -> ProgramResult {
+        let list_head_key = ctx.accounts.list_head.to_account_info().key();
+        let list_tail_key = ctx.accounts.list_tail.to_account_info().key();
+        if ctx.accounts.list_head.load()?.next_list_item != list_tail_key ||
+        ctx.accounts.list_tail.load()?.prev_list_item != list_head_key ||
+        ctx.accounts.collection.num_inventory_pages > 0 {
+            return Err(ErrorCode::CollectionNonEmpty.into());
+        }
+
+        // Close treasury accounts passed in remaining accounts, which must
+        // include every treasury the collection has been paid into
+        let market = &ctx.accounts.market;
+        let collection = &ctx.accounts.collection;
+        let seeds = &[
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+            &[collection.nonce],
+        ];
+        let signer = &[&seeds[..]];
+        let num_tracked = close_treasury_accounts(
+            ctx.remaining_accounts,
+            &ctx.accounts.collection.to_account_info(),
+            &ctx.accounts.rent_refund.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            signer,
+        )?;
+        if num_tracked != ctx.accounts.collection.num_treasury_accounts {
+            return Err(ErrorCode::TreasuryNotClosed.into());
+        }
+
+        let market = &mut ctx.accounts.market.load_mut()?;
+        market.num_open_collections = market
+            .num_open_collections
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn close_market<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseMarket<'info>>,
+    ) -> ProgramResult {
+        let (market_index, market_nonce) = {
+            let market = ctx.accounts.market.load()?;
+            if market.num_open_collections > 0 || market.num_open_price_models > 0 {
+                return Err(ErrorCode::MarketNonEmpty.into());
+            }
+            if !market.whitelist().contains(ctx.accounts.rent_refund.key) {
+                return Err(ErrorCode::AdminNotFound.into());
+            }
+            (market.index, market.nonce)
+        };
+
+        // Close treasury accounts passed in remaining accounts, which must
+        // include every treasury tracked for the market. Proceeds are only paid
+        // into collection treasuries, so these hold liquidity from before that.
+        let global_state_key = ctx.accounts.global_state.to_account_info().key();
+        let seeds = &[
+            global_state_key.as_ref(),
+            &market_index.to_le_bytes(),
+            &[market_nonce],
+        ];
+        let signer = &[&seeds[..]];
+        let num_tracked = close_treasury_accounts(
+            ctx.remaining_accounts,
+            &ctx.accounts.market.to_account_info(),
+            &ctx.accounts.rent_refund.to_account_info(),
+            &ctx.accounts.token_program.to_account_info(),
+            signer,
+        )?;
+        if num_tracked != ctx.accounts.market.load()?.num_treasury_accounts {
+            return Err(ErrorCode::TreasuryNotClosed.into());
+        }
+        Ok(())
+    }
+
+    #[access_control(
+        GlobalState::is_upgrade_authority(&ctx.accounts.program_data, ctx.accounts.admin.key)
+    )]
+    pub fn migrate_global_state(ctx: Context<MigrateGlobalState>) -> ProgramResult {
+        let global_state_info = ctx.accounts.global_state.to_account_info();
+        let legacy: LegacyGlobalState = read_legacy_account::<GlobalState, _>(
+            &global_state_info,
+            LegacyGlobalState::LEN,
+        )?;
+
+        // Legacy global state has no authority, the upgrade authority takes it over
+        let global_state = GlobalState {
+            version: ACCOUNT_VERSION,
+            nonce: legacy.nonce,
+            num_markets: legacy.num_markets,
+            authority: ctx.accounts.admin.key(),
+            fee_recipient: ctx.accounts.admin.key(),
+            ..GlobalState::default()
+        };
+
+        upgrade_account_data(
+            &global_state_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            GlobalState::LEN,
+        )?;
+        write_account(&global_state_info, &global_state)
+    }
+
+    pub fn migrate_market(ctx: Context<MigrateMarket>) -> ProgramResult {
+        let market_info = ctx.accounts.market.to_account_info();
+        let legacy: LegacyMarket = read_legacy_account::<Market, _>(
+            &market_info,
+            LegacyMarket::LEN,
+        )?;
+        if !legacy.whitelist.contains(ctx.accounts.admin.key) {
+            return Err(ErrorCode::Unauthorized.into());
+        }
+        if legacy.whitelist.len() > ADMIN_WHITELIST_MAX_LEN {
+            return Err(ErrorCode::AdminOutOfBounds.into());
+        }
+
+        upgrade_account_data(
+            &market_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            Market::LEN,
+        )?;
+
+        // Open counts are rebuilt as each collection and price model is migrated
+        let market_loader = AccountLoader::<Market>::try_from(&market_info)?;
+        let market = &mut market_loader.load_mut()?;
+        market.version = ACCOUNT_VERSION;
+        market.nonce = legacy.nonce;
+        market.index = legacy.index;
+        market.whitelist[..legacy.whitelist.len()].copy_from_slice(&legacy.whitelist);
+        market.whitelist_len = legacy.whitelist.len() as u8;
+        market.num_collections = legacy.num_collections;
+        market.num_price_models = legacy.num_price_models;
+        market.set_title(truncate_title(&legacy.title));
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn migrate_collection(ctx: Context<MigrateCollection>) -> ProgramResult {
+        let market_key = ctx.accounts.market.to_account_info().key();
+        let collection_info = ctx.accounts.collection.to_account_info();
+        let legacy: LegacyCollection = read_legacy_account::<Collection, _>(
+            &collection_info,
+            LegacyCollection::LEN,
+        )?;
+        let collection_key = Pubkey::create_program_address(
+            &[
+                market_key.as_ref(),
+                &legacy.index.to_le_bytes(),
+                b"collection".as_ref(),
+                &[legacy.nonce],
+            ],
+            ctx.program_id,
+        ).map_err(|_| ErrorCode::InvalidMigrationAccount)?;
+        if collection_key != collection_info.key() ||
+        legacy.list_head != ctx.accounts.list_head.key() ||
+        legacy.list_tail != ctx.accounts.list_tail.key() {
+            return Err(ErrorCode::InvalidMigrationAccount.into());
+        }
+
+        // List sentinels hold no NFT, only their links are carried over
+        let mut legacy_cursor = Pubkey::default();
+        for list_end in [&ctx.accounts.list_head, &ctx.accounts.list_tail].iter() {
+            let list_end_info = list_end.to_account_info();
+            let legacy: LegacyNftBucket = read_legacy_account::<NftBucket, _>(
+                &list_end_info,
+                LegacyNftBucket::LEN,
+            )?;
+            if list_end_info.key() == ctx.accounts.list_head.key() &&
+            legacy.next_list_item != ctx.accounts.list_tail.key() {
+                legacy_cursor = legacy.next_list_item;
+            }
+            upgrade_account_data(
+                &list_end_info,
+                &ctx.accounts.admin.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                &ctx.accounts.rent,
+                NftBucket::LEN,
+            )?;
+
+            let list_end_loader = AccountLoader::<NftBucket>::try_from(&list_end_info)?;
+            let list_end = &mut list_end_loader.load_mut()?;
+            list_end.version = ACCOUNT_VERSION;
+            list_end.nonce = legacy.nonce;
+            list_end.prev_list_item = legacy.prev_list_item;
+            list_end.next_list_item = legacy.next_list_item;
+            list_end.payer = legacy.payer;
+        }
+
+        // Legacy buckets are counted by count_legacy_buckets before any is
+        // migrated, adopt_nft waits until they all have been
+        let collection = Collection {
+            version: ACCOUNT_VERSION,
+            nonce: legacy.nonce,
+            index: legacy.index,
+            list_head: legacy.list_head,
+            list_tail: legacy.list_tail,
+            title: truncate_title(&legacy.title).to_string(),
+            legacy_cursor,
+            ..Collection::default()
+        };
+        upgrade_account_data(
+            &collection_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            Collection::LEN,
+        )?;
+        write_account(&collection_info, &collection)?;
+
+        let market = &mut ctx.accounts.market.load_mut()?;
+        market.num_open_collections = market
+            .num_open_collections
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    // Walks the legacy list from legacy_cursor, over the buckets passed as
+    // remaining accounts in list order. Only versioned items are ever linked
+    // in front of the first legacy bucket, so the rest of the list stays as
+    // the legacy program left it while counting.
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn count_legacy_buckets<'info>(
+        ctx: Context<'_, '_, '_, 'info, CountLegacyBuckets<'info>>,
+    ) -> ProgramResult {
+        let collection = &mut ctx.accounts.collection;
+        for info in ctx.remaining_accounts.iter() {
+            if collection.legacy_cursor == Pubkey::default() ||
+            info.key() != collection.legacy_cursor {
+                return Err(ErrorCode::InvalidMigrationAccount.into());
+            }
+            let legacy: LegacyNftBucket = read_legacy_account::<NftBucket, _>(
+                info,
+                LegacyNftBucket::LEN,
+            )?;
+
+            collection.num_legacy_buckets = collection.num_legacy_buckets
+                .checked_add(1)
+                .ok_or(ErrorCode::Overflow)?;
+            collection.legacy_cursor = if legacy.next_list_item == collection.list_tail {
+                Pubkey::default()
+            } else {
+                legacy.next_list_item
+            };
+        }
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn migrate_price_model(ctx: Context<MigratePriceModel>) -> ProgramResult {
+        let market_key = ctx.accounts.market.to_account_info().key();
+        let price_model_info = ctx.accounts.price_model.to_account_info();
+        let legacy: LegacyPriceModel = read_legacy_account::<PriceModel, _>(
+            &price_model_info,
+            LegacyPriceModel::LEN,
+        )?;
+        let price_model_key = Pubkey::create_program_address(
+            &[
+                market_key.as_ref(),
+                &legacy.index.to_le_bytes(),
+                b"price_model".as_ref(),
+                &[legacy.nonce],
+            ],
+            ctx.program_id,
+        ).map_err(|_| ErrorCode::InvalidMigrationAccount)?;
+        if legacy.market != market_key || price_model_key != price_model_info.key() {
+            return Err(ErrorCode::InvalidMigrationAccount.into());
+        }
+        if legacy.sale_prices.len() > SALE_PRICES_MAX_LEN {
+            return Err(ErrorCode::SalePricesOutOfBounds.into());
+        }
+
+        upgrade_account_data(
+            &price_model_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            PriceModel::LEN,
+        )?;
+
+        // Listings are recounted as each nft bucket is migrated
+        let price_model_loader = AccountLoader::<PriceModel>::try_from(&price_model_info)?;
+        let price_model = &mut price_model_loader.load_mut()?;
+        price_model.version = ACCOUNT_VERSION;
+        price_model.nonce = legacy.nonce;
+        price_model.index = legacy.index;
+        price_model.market = legacy.market;
+        price_model.sale_prices[..legacy.sale_prices.len()].copy_from_slice(&legacy.sale_prices);
+        price_model.num_sale_prices = legacy.sale_prices.len() as u8;
+
+        let market = &mut ctx.accounts.market.load_mut()?;
+        market.num_open_price_models = market
+            .num_open_price_models
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    #[access_control(
+        Market::is_valid_admin(&ctx.accounts.market, ctx.accounts.admin.key)
+    )]
+    pub fn migrate_nft_bucket(ctx: Context<MigrateNftBucket>) -> ProgramResult {
+        let collection_key = ctx.accounts.collection.to_account_info().key();
+        let nft_bucket_info = ctx.accounts.nft_bucket.to_account_info();
+        let legacy: LegacyNftBucket = read_legacy_account::<NftBucket, _>(
+            &nft_bucket_info,
+            LegacyNftBucket::LEN,
+        )?;
+        // Legacy buckets may be keypair accounts rather than PDAs, so they're
+        // recognized by owner and layout, and bound to the collection below
+        if legacy.price_model != ctx.accounts.price_model.key() {
+            return Err(ErrorCode::InvalidMigrationAccount.into());
+        }
+        // Migrating changes the list the count walks, so counting comes first
+        if ctx.accounts.collection.legacy_cursor != Pubkey::default() {
+            return Err(ErrorCode::LegacyBucketsPending.into());
+        }
+        // The collection must hold the NFT, and a neighbour in its list must
+        // link back to the bucket, so it can't be bound to another collection
+        let nft_bucket_key = nft_bucket_info.key();
+        let program_nft_account = &ctx.accounts.program_nft_account;
+        if program_nft_account.key() !=
+        associated_token::get_associated_token_address(&collection_key, &legacy.nft_mint) ||
+        program_nft_account.amount != 1 {
+            return Err(ErrorCode::InvalidMigrationAccount.into());
+        }
+        validate_list_item(&ctx.accounts.collection, &ctx.accounts.linked_list_item)?;
+        {
+            let linked_list_item_key = ctx.accounts.linked_list_item.key();
+            let linked_list_item = ctx.accounts.linked_list_item.load()?;
+            let prev_links_back = linked_list_item_key == legacy.prev_list_item &&
+                linked_list_item.next_list_item == nft_bucket_key;
+            let next_links_back = linked_list_item_key == legacy.next_list_item &&
+                linked_list_item.prev_list_item == nft_bucket_key;
+            if !prev_links_back && !next_links_back {
+                return Err(ErrorCode::InvalidMigrationAccount.into());
+            }
+        }
+
+        let collection = &mut ctx.accounts.collection;
+        collection.num_legacy_buckets = collection.num_legacy_buckets
+            .checked_sub(1)
+            .ok_or(ErrorCode::InvalidMigrationAccount)?;
+
+        // Legacy buckets predate inventory pages, so they take a slot now
+        let inventory_page = &mut ctx.accounts.inventory_page.load_mut()?;
+        if inventory_page.collection != collection_key {
+            return Err(ErrorCode::InvalidInventoryPage.into());
+        }
+        let version = ctx.accounts.collection.record_inventory_change()?;
+        let inventory_slot = inventory_page.insert(nft_bucket_key, legacy.nft_mint, version)?;
+
+        upgrade_account_data(
+            &nft_bucket_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            &ctx.accounts.rent,
+            NftBucket::LEN,
+        )?;
+
+        let nft_bucket_loader = AccountLoader::<NftBucket>::try_from(&nft_bucket_info)?;
+        let nft_bucket = &mut nft_bucket_loader.load_mut()?;
+        nft_bucket.version = ACCOUNT_VERSION;
+        nft_bucket.nonce = legacy.nonce;
+        nft_bucket.nft_mint = legacy.nft_mint;
+        nft_bucket.price_model = legacy.price_model;
+        nft_bucket.prev_list_item = legacy.prev_list_item;
+        nft_bucket.next_list_item = legacy.next_list_item;
+        nft_bucket.payer = legacy.payer;
+        nft_bucket.inventory_page = ctx.accounts.inventory_page.to_account_info().key();
+        nft_bucket.inventory_slot = inventory_slot;
+        nft_bucket.collection = collection_key;
+
+        let price_model = &mut ctx.accounts.price_model.load_mut()?;
+        price_model.num_listings = price_model
+            .num_listings
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        ctx.accounts.market.load_mut()?.stats.record_listing()?;
+        ctx.accounts.collection.stats.record_listing()?;
+        Ok(())
+    }
+}
+
+/***********/
+/* HELPERS */
+/***********/
+
+fn insert_nft(ctx: Context<AddNft>) -> ProgramResult {
+    let (_, nonce) = Pubkey::find_program_address(
+        &[
+            ctx.accounts.collection.to_account_info().key.as_ref(),
+            ctx.accounts.admin_nft_mint.to_account_info().key.as_ref(),
+            b"nft_bucket".as_ref(),
+        ],
+        ctx.program_id,
+    );
+
+    ctx.accounts.prev_list_item.load_mut()?.next_list_item = ctx.accounts.new_item.to_account_info().key();
+    ctx.accounts.next_list_item.load_mut()?.prev_list_item = ctx.accounts.new_item.to_account_info().key();
+
+    let inventory_page = &mut ctx.accounts.inventory_page.load_mut()?;
+    if inventory_page.collection != ctx.accounts.collection.to_account_info().key() {
+        return Err(ErrorCode::InvalidInventoryPage.into());
+    }
+    let version = ctx.accounts.collection.record_inventory_change()?;
+    let inventory_slot = inventory_page.insert(
+        ctx.accounts.new_item.to_account_info().key(),
+        ctx.accounts.admin_nft_mint.to_account_info().key(),
+        version,
+    )?;
+
+    let new_item = &mut ctx.accounts.new_item.load_init()?;
+    new_item.version = ACCOUNT_VERSION;
+    new_item.nonce = nonce;
+    new_item.nft_mint = ctx.accounts.admin_nft_mint.to_account_info().key();
+    new_item.price_model = ctx.accounts.price_model.to_account_info().key();
+    new_item.prev_list_item = ctx.accounts.prev_list_item.to_account_info().key();
+    new_item.next_list_item = ctx.accounts.next_list_item.to_account_info().key();
+    new_item.payer = ctx.accounts.admin.key();
+    new_item.inventory_page = ctx.accounts.inventory_page.to_account_info().key();
+    new_item.inventory_slot = inventory_slot;
+
+    let price_model = &mut ctx.accounts.price_model.load_mut()?;
+    price_model.num_listings = price_model
+        .num_listings
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+
+    ctx.accounts.market.load_mut()?.stats.record_listing()?;
+    ctx.accounts.collection.stats.record_listing()?;
+    
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.admin_nft_account.to_account_info(),
+        to: ctx.accounts.program_nft_account.to_account_info(),
+        authority: ctx.accounts.admin.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    transfer(cpi_ctx, 1)?;
+
+    Ok(())
+}
+
+fn validate_sale_window(sale_start: Option<i64>, sale_end: Option<i64>) -> Result<()> {
+    if let (Some(sale_start), Some(sale_end)) = (sale_start, sale_end) {
+        if sale_start >= sale_end {
+            return Err(ErrorCode::InvalidSaleWindow.into());
+        }
+    }
+    Ok(())
+}
+
+fn check_sale_window(sale_start: Option<i64>, sale_end: Option<i64>, now: i64) -> Result<()> {
+    if let Some(sale_start) = sale_start {
+        if now < sale_start {
+            return Err(ErrorCode::SaleNotStarted.into());
+        }
+    }
+    if let Some(sale_end) = sale_end {
+        if now >= sale_end {
+            return Err(ErrorCode::SaleEnded.into());
+        }
+    }
+    Ok(())
+}
+
+fn reveal_slot_hash(slot_hashes: &AccountInfo, commit_slot: u64) -> Result<Option<[u8; 32]>> {
+    // SlotHashes is a length-prefixed list of (slot, hash) entries, newest first.
+    // The draw uses the first slot after the commit; once that slot has been
+    // evicted from the sysvar the commit can no longer be revealed.
+    let data = slot_hashes.try_borrow_data()?;
+    let mut len = [0u8; 8];
+    len.copy_from_slice(data.get(..8).ok_or(ProgramError::InvalidAccountData)?);
+
+    let mut reveal_hash = None;
+    let mut oldest_slot = u64::MAX;
+    for i in 0..u64::from_le_bytes(len) as usize {
+        let offset = 8 + i * SLOT_HASH_ENTRY_LEN;
+        let entry = data
+            .get(offset..offset + SLOT_HASH_ENTRY_LEN)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        let mut slot = [0u8; 8];
+        slot.copy_from_slice(&entry[..8]);
+        oldest_slot = u64::from_le_bytes(slot);
+        if oldest_slot <= commit_slot {
+            break;
+        }
+        let mut slot_hash = [0u8; 32];
+        slot_hash.copy_from_slice(&entry[8..]);
+        reveal_hash = Some(slot_hash);
+    }
+
+    match reveal_hash {
+        None => Err(ErrorCode::RevealTooEarly.into()),
+        Some(_) if oldest_slot > commit_slot + 1 => Ok(None),
+        Some(_) => Ok(reveal_hash),
+    }
+}
+
+enum Draw {
+    // Nothing could be drawn at the commit
+    Empty,
+    // Slots changed too often since the commit to tell what could be drawn
+    Unknown,
+    // The drawn slot, with the bucket still in it if it can be delivered
+    Slot {
+        page_position: usize,
+        slot_index: u32,
+        nft_bucket: Option<Pubkey>,
+    },
+}
+
+// Maps `random` onto the slots that could be drawn when `mystery_commit` was
+// made, in page order
+fn draw_inventory_slot(
+    collection: &Account<Collection>,
+    inventory_pages: &[AccountInfo],
+    mystery_commit: &MysteryCommit,
+    random: u64,
+) -> Result<Draw> {
+    // Every page of the collection must be passed, in index order
+    if inventory_pages.len() != collection.num_inventory_pages as usize {
+        return Err(ErrorCode::InvalidInventoryPage.into());
+    }
+
+    let version = mystery_commit.inventory_version;
+    let timestamp = mystery_commit.commit_timestamp;
+    let collection_key = collection.to_account_info().key();
+    let mut num_drawable: Vec<u64> = Vec::with_capacity(inventory_pages.len());
+    for (i, info) in inventory_pages.iter().enumerate() {
+        let inventory_page = AccountLoader::<InventoryPage>::try_from(info)?;
+        let inventory_page = inventory_page.load()?;
+        let index = inventory_page.index;
+        if inventory_page.collection != collection_key || index as usize != i {
+            return Err(ErrorCode::InvalidInventoryPage.into());
+        }
+        let mut page_drawable = 0;
+        for slot in inventory_page.slots.iter() {
+            match slot.could_be_drawn(version, timestamp) {
+                Some(true) => page_drawable += 1,
+                Some(false) => {}
+                None => return Ok(Draw::Unknown),
+            }
+        }
+        num_drawable.push(page_drawable);
+    }
+
+    let total: u64 = num_drawable.iter().sum();
+    if total == 0 {
+        return Ok(Draw::Empty);
+    }
+
+    let mut target = random % total;
+    for (i, info) in inventory_pages.iter().enumerate() {
+        if target >= num_drawable[i] {
+            target -= num_drawable[i];
+            continue;
+        }
+
+        let inventory_page = AccountLoader::<InventoryPage>::try_from(info)?;
+        let inventory_page = inventory_page.load()?;
+        for (slot_index, slot) in inventory_page.slots.iter().enumerate() {
+            if slot.could_be_drawn(version, timestamp) != Some(true) {
+                continue;
+            }
+            if target == 0 {
+                // A slot changed once since the commit either had its bucket
+                // removed or its sale terms changed, which don't void the draw
+                let nft_bucket = slot.nft_bucket;
+                return Ok(Draw::Slot {
+                    page_position: i,
+                    slot_index: slot_index as u32,
+                    nft_bucket: Some(nft_bucket).filter(|key| *key != Pubkey::default()),
+                });
+            }
+            target -= 1;
+        }
+    }
+    Err(ErrorCode::InvalidInventoryPage.into())
+}
+
+fn draw_random(slot_hash: &[u8; 32], mystery_commit: &Pubkey) -> u64 {
+    let random_bytes = hashv(&[&slot_hash[..], mystery_commit.as_ref()]).to_bytes();
+    let mut random = [0u8; 8];
+    random.copy_from_slice(&random_bytes[..8]);
+    u64::from_le_bytes(random)
+}
+
+fn inventory_holds_mint(
+    collection: &Account<Collection>,
+    inventory_pages: &[AccountInfo],
+    mint: &Pubkey,
+) -> Result<bool> {
+    // Every page of the collection must be passed, in index order
+    if inventory_pages.len() != collection.num_inventory_pages as usize {
+        return Err(ErrorCode::InvalidInventoryPage.into());
+    }
+
+    let collection_key = collection.to_account_info().key();
+    for (i, info) in inventory_pages.iter().enumerate() {
+        let inventory_page = AccountLoader::<InventoryPage>::try_from(info)?;
+        let inventory_page = inventory_page.load()?;
+        let index = inventory_page.index;
+        if inventory_page.collection != collection_key || index as usize != i {
+            return Err(ErrorCode::InvalidInventoryPage.into());
+        }
+        if inventory_page.slots.iter().any(|slot| slot.nft_mint == *mint) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn validate_list_item(collection: &Account<Collection>, list_item: &AccountLoader<NftBucket>) -> Result<()> {
+    let collection_key = collection.to_account_info().key();
+    let list_item_key = list_item.to_account_info().key();
+    if list_item_key == collection.list_head || list_item_key == collection.list_tail {
+        return Ok(());
+    }
+
+    // Buckets migrated from keypair accounts record their collection, every
+    // other bucket is a PDA of it
+    let list_item = list_item.load()?;
+    if list_item.collection == collection_key {
+        return Ok(());
+    }
+    let nft_bucket_key = Pubkey::create_program_address(
+        &[
+            collection_key.as_ref(),
+            list_item.nft_mint.as_ref(),
+            b"nft_bucket".as_ref(),
+            &[list_item.nonce],
+        ],
+        &crate::ID,
+    ).map_err(|_| ErrorCode::InvalidListItem)?;
+    if nft_bucket_key != list_item_key {
+        return Err(ErrorCode::InvalidListItem.into());
+    }
+    Ok(())
+}
+
+fn validate_fee_account(fee_account: &AccountInfo, fee_recipient: &Pubkey, mint: &Pubkey) -> Result<()> {
+    if fee_account.key != &associated_token::get_associated_token_address(fee_recipient, mint) {
+        return Err(ErrorCode::InvalidFeeAccount.into());
+    }
+    Ok(())
+}
+
+fn validate_title(title: &str) -> Result<()> {
+    if title.len() > TITLE_MAX_LEN {
+        return Err(ErrorCode::TitleTooLong.into());
+    }
+    Ok(())
+}
+
+fn truncate_title(title: &str) -> &str {
+    let mut end = title.len().min(TITLE_MAX_LEN);
+    while !title.is_char_boundary(end) {
+        end -= 1;
+    }
+    &title[..end]
+}
+
+fn optional_timestamp(timestamp: i64) -> Option<i64> {
+    // Fixed-layout accounts store unset timestamps as zero
+    if timestamp == 0 { None } else { Some(timestamp) }
+}
+
+fn copy_str(dest: &mut [u8], value: &str) {
+    for byte in dest.iter_mut() {
+        *byte = 0;
+    }
+    dest[..value.len()].copy_from_slice(value.as_bytes());
+}
+
+fn realloc_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &Rent,
+    new_len: usize,
+) -> ProgramResult {
+    // Accounts only grow, so shorter metadata keeps the existing allocation
+    if new_len <= account.data_len() {
+        return Ok(());
+    }
+
+    let required_lamports = rent.minimum_balance(new_len);
+    if required_lamports > account.lamports() {
+        invoke(
+            &system_instruction::transfer(
+                payer.key,
+                account.key,
+                required_lamports - account.lamports(),
+            ),
+            &[
+                payer.clone(),
+                account.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+    account.realloc(new_len, false)
+}
+
+fn account_version<T: Discriminator>(account: &AccountInfo, legacy_len: usize) -> Result<u8> {
+    let data = account.try_borrow_data()?;
+    if account.owner != &crate::ID || data.len() <= 8 || data[..8] != T::discriminator() {
+        return Err(ErrorCode::InvalidAccountVersion.into());
+    }
+
+    // Accounts written before versioning keep their original allocation and
+    // have no version byte, versioned accounts store it after the discriminator
+    if data.len() == legacy_len {
+        return Ok(0);
+    }
+    Ok(data[8])
+}
+
+fn read_legacy_account<T: Discriminator, L: AnchorDeserialize>(
+    account: &AccountInfo,
+    legacy_len: usize,
+) -> Result<L> {
+    if account_version::<T>(account, legacy_len)? != 0 {
+        return Err(ErrorCode::InvalidAccountVersion.into());
+    }
+
+    let data = account.try_borrow_data()?;
+    L::deserialize(&mut &data[8..]).map_err(|_| ErrorCode::InvalidAccountVersion.into())
+}
+
+fn upgrade_account_data<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &Rent,
+    new_len: usize,
+) -> ProgramResult {
+    realloc_account(account, payer, system_program, rent, new_len)?;
+
+    // Clear the legacy layout so fields without a legacy value start zeroed
+    let mut data = account.try_borrow_mut_data()?;
+    for byte in data[8..].iter_mut() {
+        *byte = 0;
+    }
+    Ok(())
+}
+
+fn write_account<T: AccountSerialize>(account: &AccountInfo, value: &T) -> ProgramResult {
+    let mut data = account.try_borrow_mut_data()?;
+    let dst: &mut [u8] = &mut data;
+    let mut cursor = std::io::Cursor::new(dst);
+    value.try_serialize(&mut cursor)
+}
+
+// Moves a purchase's payment out of debit_account: the protocol fee to the fee
+// recipient's ATA and the proceeds to the collection treasury. Returns the
+// proceeds. The fee is waived while the fee recipient has no ATA for the mint,
+// so a missing one can't block sales.
+fn collect_payment<'info>(
+    quote: &PriceQuote,
+    owner: &AccountInfo<'info>,
+    debit_account: &AccountInfo<'info>,
+    fee_recipient: &AccountInfo<'info>,
+    fee_credit_account: &AccountInfo<'info>,
+    program_credit_account: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<u64> {
+    let mut proceeds = quote.proceeds;
+    if quote.protocol_fee > 0 {
+        validate_fee_account(fee_credit_account, fee_recipient.key, &quote.mint)?;
+        if *fee_credit_account.owner == token::ID {
+            let cpi_accounts = Transfer {
+                from: debit_account.clone(),
+                to: fee_credit_account.clone(),
+                authority: owner.clone(),
+            };
+            let cpi_ctx = CpiContext::new(token_program.clone(), cpi_accounts);
+            transfer(cpi_ctx, quote.protocol_fee)?;
+        } else {
+            proceeds = quote.amount;
+        }
+    }
+
+    let cpi_accounts = Transfer {
+        from: debit_account.clone(),
+        to: program_credit_account.clone(),
+        authority: owner.clone(),
+    };
+    let cpi_ctx = CpiContext::new(token_program.clone(), cpi_accounts);
+    transfer(cpi_ctx, proceeds)?;
+    Ok(proceeds)
+}
+
+// Marks a treasury the first time the program pays into it, by making its
+// owner (a collection or market) the close authority, and counts it so closing
+// the owner can require every one of them. The associated token account may
+// have been created by anyone, so its existence alone says nothing.
+fn track_treasury_account<'info>(
+    treasury: &Account<'info, TokenAccount>,
+    authority: &AccountInfo<'info>,
+    num_treasury_accounts: &mut u32,
+    token_program: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> ProgramResult {
+    if treasury.close_authority == COption::Some(*authority.key) {
+        return Ok(());
+    }
+
+    let cpi_accounts = SetAuthority {
+        current_authority: authority.clone(),
+        account_or_mint: treasury.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
+    set_authority(cpi_ctx, AuthorityType::CloseAccount, Some(*authority.key))?;
+
+    *num_treasury_accounts = num_treasury_accounts
+        .checked_add(1)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}
+
+// Tracks a market treasury, see track_treasury_account. The market must not be
+// borrowed across the CPI, so its counter is copied out and written back.
+fn track_market_treasury_account<'info>(
+    treasury: &Account<'info, TokenAccount>,
+    market: &AccountLoader<'info, Market>,
+    token_program: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> ProgramResult {
+    let mut num_treasury_accounts = market.load()?.num_treasury_accounts;
+    track_treasury_account(
+        treasury,
+        &market.to_account_info(),
+        &mut num_treasury_accounts,
+        token_program,
+        signer,
+    )?;
+    market.load_mut()?.num_treasury_accounts = num_treasury_accounts;
+    Ok(())
+}
+
+// Closes empty treasuries, returning how many of them were tracked
+fn close_treasury_accounts<'info>(
+    treasury_accounts: &[AccountInfo<'info>],
+    authority: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+) -> Result<u32> {
+    let mut num_tracked: u32 = 0;
+    for (i, treasury_account) in treasury_accounts.iter().enumerate() {
+        let treasury: Account<TokenAccount> = Account::try_from(treasury_account)?;
+        if treasury.owner != *authority.key ||
+        treasury_accounts[..i].iter().any(|account| account.key == treasury_account.key) {
+            return Err(ErrorCode::InvalidTreasury.into());
+        }
+        if treasury.amount > 0 {
+            return Err(ErrorCode::TreasuryNonEmpty.into());
+        }
+        if treasury.close_authority == COption::Some(*authority.key) {
+            num_tracked += 1;
+        }
+
+        let cpi_accounts = CloseAccount {
+            account: treasury_account.clone(),
+            destination: destination.clone(),
+            authority: authority.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(token_program.clone(), cpi_accounts, signer);
+        close_account(cpi_ctx)?;
+    }
+    Ok(num_tracked)
+}
+
+/************************/
+/* INSTRUCTION ACCOUNTS */
+/************************/
+
+#[derive(Accounts)]
+pub struct InitGlobalState<'info> {
+    admin: Signer<'info>,
+    program_data: UncheckedAccount<'info>,
+    #[account(
+        init,
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump,
+        payer = admin,
+    )]
+    global_state: Account<'info, GlobalState>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolFee<'info> {
+    authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Account<'info, GlobalState>,
+    fee_recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetGlobalAuthority<'info> {
+    authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Account<'info, GlobalState>,
+    new_authority: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarketCreation<'info> {
+    authority: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct InitMarket<'info> {
+    admin: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Account<'info, GlobalState>,
+    #[account(mut, address = global_state.fee_recipient)]
+    fee_recipient: UncheckedAccount<'info>,
+    #[account(
+        init,
+        seeds = [
+            global_state.to_account_info().key.as_ref(),
+            &global_state.num_markets.to_le_bytes(),
+        ],
+        bump,
+        payer = admin,
+        space = Market::LEN
+    )]
+    market: AccountLoader<'info, Market>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddAdmin<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    add_admin: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAdmin<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    remove_admin: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetMarketPaused<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarketMetadata<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollectionPaused<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Account<'info, Collection>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollectionSaleWindow<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Account<'info, Collection>,
+}
+
+#[derive(Accounts)]
+pub struct SetNftSaleWindow<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Account<'info, Collection>,
+    #[account(mut)]
+    list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut, address = list_item.load()?.inventory_page)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+}
+
+#[derive(Accounts)]
+pub struct SetNftExpiry<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Account<'info, Collection>,
+    #[account(mut)]
+    list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut, address = list_item.load()?.inventory_page)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+}
+
+#[derive(Accounts)]
+pub struct SetMysteryPriceModel<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Account<'info, Collection>,
+    #[account(constraint = price_model.load()?.market == market.key())]
+    price_model: AccountLoader<'info, PriceModel>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCollectionMetadata<'info> {
+    #[account(mut)]
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Account<'info, Collection>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitCollection<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        init,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &market.load()?.num_collections.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump,
+        payer = admin,
+        space = Collection::LEN
+    )]
+    collection: Account<'info, Collection>,
+    #[account(
+        init,
+        seeds = [
+            collection.to_account_info().key.as_ref(),
+            b"head".as_ref(),
+        ],
+        bump,
+        payer = admin,
+        space = NftBucket::LEN
+    )]
+    list_head: AccountLoader<'info, NftBucket>,
+    #[account(
+        init,
+        seeds = [
+            collection.to_account_info().key.as_ref(),
+            b"tail".as_ref(),
+        ],
+        bump,
+        payer = admin,
+        space = NftBucket::LEN
+    )]
+    list_tail: AccountLoader<'info, NftBucket>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitInventoryPage<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(
+        init,
+        seeds = [
+            collection.to_account_info().key.as_ref(),
+            &collection.num_inventory_pages.to_le_bytes(),
+            b"inventory_page".as_ref(),
+        ],
+        bump,
+        payer = admin,
+        space = InventoryPage::LEN
+    )]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseInventoryPage<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut, close = admin)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+}
+
+#[derive(Accounts)]
+pub struct InitPriceModel<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        init,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &market.load()?.num_price_models.to_le_bytes(),
+            b"price_model".as_ref(),
+        ],
+        bump,
+        payer = admin,
+        space = PriceModel::LEN
+    )]
+    price_model: AccountLoader<'info, PriceModel>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePriceModel<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &price_model.load()?.index.to_le_bytes(),
+            b"price_model".as_ref(),
+        ],
+        bump = price_model.load()?.nonce,
+        close = admin
+    )]
+    price_model: AccountLoader<'info, PriceModel>,
+}
+
+#[derive(Accounts)]
+pub struct AddNft<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut)]
+    prev_list_item: AccountLoader<'info, NftBucket>,
+    #[account(
+        mut,
+        address = prev_list_item.load()?.next_list_item
+    )]
+    next_list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut)]
+    admin_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = admin_nft_account.mint)]
+    admin_nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init,
+        seeds = [
+            collection.to_account_info().key.as_ref(),
+            admin_nft_mint.to_account_info().key.as_ref(),
+            b"nft_bucket".as_ref(),
+        ],
+        bump,
+        payer = admin,
+        space = NftBucket::LEN
+    )]
+    new_item: AccountLoader<'info, NftBucket>,
+    #[account(mut)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &price_model.load()?.index.to_le_bytes(),
+            b"price_model".as_ref(),
+        ],
+        bump = price_model.load()?.nonce,
+    )]
+    price_model: AccountLoader<'info, PriceModel>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+	    associated_token::mint = admin_nft_mint,
+        associated_token::authority = collection,
+    )]
+    program_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = associated_token::ID)]
+    associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MoveItem<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut)]
+    list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut, address = list_item.load()?.prev_list_item)]
+    prev_list_item: UncheckedAccount<'info>,
+    #[account(mut, address = list_item.load()?.next_list_item)]
+    next_list_item: UncheckedAccount<'info>,
+    #[account(mut)]
+    new_prev_list_item: UncheckedAccount<'info>,
+    #[account(mut)]
+    new_next_list_item: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ListPage<'info> {
+    collection: Box<Account<'info, Collection>>,
+    cursor: AccountLoader<'info, NftBucket>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyCollection<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    cursor: AccountLoader<'info, NftBucket>,
+}
+
+#[derive(Accounts)]
+pub struct RepairLink<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut)]
+    prev_list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut)]
+    next_list_item: AccountLoader<'info, NftBucket>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawNft<'info> {
+    admin: Signer<'info>,
+    #[account(mut, address = withdraw_list_item.load()?.payer)]
+    rent_refund: UncheckedAccount<'info>,
+    #[account(mut, address = withdraw_list_item.load()?.price_model)]
+    price_model: AccountLoader<'info, PriceModel>,
+    #[account(mut, address = price_model.load()?.market)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut)]
+    withdraw_list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut, address = withdraw_list_item.load()?.inventory_page)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+    #[account(
+        mut,
+	    associated_token::mint = withdraw_list_item.load()?.nft_mint,
+        associated_token::authority = collection,
+    )]
+    program_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = withdraw_list_item.load()?.nft_mint)]
+    program_nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+	    associated_token::mint = program_nft_mint,
+        associated_token::authority = admin,
+    )]
+    admin_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = associated_token::ID)]
+    associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CrankExpired<'info> {
+    #[account(mut)]
+    cranker: Signer<'info>,
+    #[account(mut, address = expired_list_item.load()?.payer)]
+    rent_refund: UncheckedAccount<'info>,
+    #[account(mut, address = expired_list_item.load()?.price_model)]
+    price_model: AccountLoader<'info, PriceModel>,
+    #[account(mut, address = price_model.load()?.market)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut, close = rent_refund)]
+    expired_list_item: AccountLoader<'info, NftBucket>,
+    #[account(
+        mut,
+        address = expired_list_item.load()?.prev_list_item,
+        constraint = prev_list_item.load()?.next_list_item == expired_list_item.key(),
+    )]
+    prev_list_item: AccountLoader<'info, NftBucket>,
+    #[account(
+        mut,
+        address = expired_list_item.load()?.next_list_item,
+        constraint = next_list_item.load()?.prev_list_item == expired_list_item.key(),
+    )]
+    next_list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut, address = expired_list_item.load()?.inventory_page)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+    #[account(
+        mut,
+        associated_token::mint = expired_list_item.load()?.nft_mint,
+        associated_token::authority = collection,
+    )]
+    program_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = expired_list_item.load()?.nft_mint)]
+    program_nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = program_nft_mint,
+        associated_token::authority = rent_refund,
+    )]
+    payer_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = associated_token::ID)]
+    associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct QuotePurchase<'info> {
+    #[account(
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Box<Account<'info, GlobalState>>,
+    #[account(address = list_item.load()?.price_model)]
+    price_model: AccountLoader<'info, PriceModel>,
+    #[account(address = price_model.load()?.market)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    list_item: AccountLoader<'info, NftBucket>,
+}
+
+#[derive(Accounts)]
+pub struct PurchaseNft<'info> {
+    owner: Signer<'info>,
+    #[account(mut, address = purchase_list_item.load()?.payer)]
+    rent_refund: UncheckedAccount<'info>,
+    #[account(
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Box<Account<'info, GlobalState>>,
+    #[account(mut, address = purchase_list_item.load()?.price_model)]
+    price_model: AccountLoader<'info, PriceModel>,
+    #[account(mut, address = price_model.load()?.market)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut)]
+    purchase_list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut, address = purchase_list_item.load()?.inventory_page)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+    #[account(address = debit_account.mint)]
+    debit_mint: Box<Account<'info, Mint>>,
+    #[account(mut, has_one = owner)]
+    debit_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+	    associated_token::mint = debit_mint,
+        associated_token::authority = collection,
+    )]
+    program_credit_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = global_state.fee_recipient)]
+    fee_recipient: UncheckedAccount<'info>,
+    // Only used when a fee is charged, the fee recipient creates it and the
+    // fee is waived until then
+    #[account(mut)]
+    fee_credit_account: UncheckedAccount<'info>,
     #[account(
-        init,
-        seeds = [
-            b"global".as_ref(),
-        ],
-        bump = nonce,
-        payer = admin,
+        mut,
+	    associated_token::mint = purchase_list_item.load()?.nft_mint,
+        associated_token::authority = collection,
     )]
-    global_state: Account<'info, GlobalState>,
+    program_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = purchase_list_item.load()?.nft_mint)]
+    program_nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+	    associated_token::mint = program_nft_mint,
+        associated_token::authority = owner,
+    )]
+    owner_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = associated_token::ID)]
+    associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
     #[account(address = system_program::ID)]
     system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(
-    nonce: u8,
-    whitelist: Vec<Pubkey>,
-    title: String,
-)]
-pub struct InitMarket<'info> {
-    admin: Signer<'info>,
+pub struct CommitRandomPurchase<'info> {
     #[account(mut)]
-    global_state: Account<'info, GlobalState>,
+    owner: Signer<'info>,
+    #[account(
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Box<Account<'info, GlobalState>>,
+    #[account(address = price_model.load()?.market)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    price_model: AccountLoader<'info, PriceModel>,
     #[account(
         init,
         seeds = [
-            global_state.to_account_info().key.as_ref(),
-            &global_state.num_markets.to_le_bytes(),
+            collection.to_account_info().key.as_ref(),
+            owner.key.as_ref(),
+            b"mystery_commit".as_ref(),
         ],
-        bump = nonce,
-        payer = admin,
-        space = Market::LEN
+        bump,
+        payer = owner,
+        space = MysteryCommit::LEN,
+    )]
+    mystery_commit: Box<Account<'info, MysteryCommit>>,
+    #[account(address = debit_account.mint)]
+    debit_mint: Box<Account<'info, Mint>>,
+    #[account(mut, has_one = owner)]
+    debit_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = debit_mint,
+        associated_token::authority = collection,
     )]
-    market: Account<'info, Market>,
+    program_credit_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = global_state.fee_recipient)]
+    fee_recipient: UncheckedAccount<'info>,
+    // Only used when a fee is charged, the fee recipient creates it and the
+    // fee is waived until then
+    #[account(mut)]
+    fee_credit_account: UncheckedAccount<'info>,
+    #[account(address = associated_token::ID)]
+    associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
     #[account(address = system_program::ID)]
     system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct AddAdmin<'info> {
-    admin: Signer<'info>,
-    #[account(mut)]
-    market: Account<'info, Market>,
-    add_admin: UncheckedAccount<'info>,
-}
-
-#[derive(Accounts)]
-pub struct RemoveAdmin<'info> {
-    admin: Signer<'info>,
+pub struct RevealRandomPurchase<'info> {
     #[account(mut)]
-    market: Account<'info, Market>,
-    remove_admin: UncheckedAccount<'info>,
-}
-
-#[derive(Accounts)]
-#[instruction(
-    collection_nonce: u8,
-    list_head_nonce: u8,
-    list_tail_nonce: u8,
-    title: String,
-)]
-pub struct InitCollection<'info> {
-    admin: Signer<'info>,
+    cranker: Signer<'info>,
     #[account(mut)]
-    market: Account<'info, Market>,
+    owner: UncheckedAccount<'info>,
+    #[account(mut, address = purchase_list_item.load()?.payer)]
+    rent_refund: UncheckedAccount<'info>,
+    #[account(mut, address = purchase_list_item.load()?.price_model)]
+    price_model: AccountLoader<'info, PriceModel>,
+    #[account(mut, address = price_model.load()?.market)]
+    market: AccountLoader<'info, Market>,
     #[account(
-        init,
+        mut,
         seeds = [
             market.to_account_info().key.as_ref(),
-            &market.num_collections.to_le_bytes(),
+            &collection.index.to_le_bytes(),
             b"collection".as_ref(),
         ],
-        bump = collection_nonce,
-        payer = admin,
-        space = Collection::LEN
+        bump = collection.nonce,
     )]
-    collection: Account<'info, Collection>,
+    collection: Box<Account<'info, Collection>>,
     #[account(
-        init,
+        mut,
         seeds = [
             collection.to_account_info().key.as_ref(),
-            b"head".as_ref(),
+            owner.key.as_ref(),
+            b"mystery_commit".as_ref(),
         ],
-        bump = list_head_nonce,
-        payer = admin,
-        space = NftBucket::LEN
+        bump = mystery_commit.nonce,
+        has_one = owner,
+        close = owner,
     )]
-    list_head: Account<'info, NftBucket>,
+    mystery_commit: Box<Account<'info, MysteryCommit>>,
+    #[account(mut)]
+    purchase_list_item: AccountLoader<'info, NftBucket>,
     #[account(
-        init,
-        seeds = [
-            collection.to_account_info().key.as_ref(),
-            b"tail".as_ref(),
-        ],
-        bump = list_tail_nonce,
-        payer = admin,
-        space = NftBucket::LEN
+        mut,
+        associated_token::mint = purchase_list_item.load()?.nft_mint,
+        associated_token::authority = collection,
+    )]
+    program_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = purchase_list_item.load()?.nft_mint)]
+    program_nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = program_nft_mint,
+        associated_token::authority = owner,
     )]
-    list_tail: Account<'info, NftBucket>,
+    owner_nft_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = sysvar::slot_hashes::ID)]
+    slot_hashes: UncheckedAccount<'info>,
+    #[account(address = associated_token::ID)]
+    associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
     #[account(address = system_program::ID)]
     system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-#[instruction(
-    nonce: u8,
-)]
-pub struct InitPriceModel<'info> {
-    admin: Signer<'info>,
+pub struct RefundRandomPurchase<'info> {
     #[account(mut)]
-    market: Account<'info, Market>,
+    owner: Signer<'info>,
+    market: AccountLoader<'info, Market>,
     #[account(
-        init,
         seeds = [
             market.to_account_info().key.as_ref(),
-            &market.num_price_models.to_le_bytes(),
-            b"price_model".as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
         ],
-        bump = nonce,
-        payer = admin,
-        space = PriceModel::LEN
+        bump = collection.nonce,
     )]
-    price_model: Account<'info, PriceModel>,
-    #[account(address = system_program::ID)]
-    system_program: Program<'info, System>,
+    collection: Box<Account<'info, Collection>>,
+    #[account(
+        mut,
+        seeds = [
+            collection.to_account_info().key.as_ref(),
+            owner.key.as_ref(),
+            b"mystery_commit".as_ref(),
+        ],
+        bump = mystery_commit.nonce,
+        has_one = owner,
+        close = owner,
+    )]
+    mystery_commit: Box<Account<'info, MysteryCommit>>,
+    #[account(
+        mut,
+        has_one = owner,
+        constraint = debit_account.mint == mystery_commit.debit_mint,
+    )]
+    debit_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mystery_commit.debit_mint,
+        associated_token::authority = collection,
+    )]
+    program_credit_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = sysvar::slot_hashes::ID)]
+    slot_hashes: UncheckedAccount<'info>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct AddNft<'info> {
-    admin: Signer<'info>,
-    market: Box<Account<'info, Market>>,
+pub struct ExpireRandomPurchase<'info> {
+    #[account(mut)]
+    owner: UncheckedAccount<'info>,
+    market: AccountLoader<'info, Market>,
     #[account(
         seeds = [
             market.to_account_info().key.as_ref(),
@@ -468,39 +3331,75 @@ pub struct AddNft<'info> {
         bump = collection.nonce,
     )]
     collection: Box<Account<'info, Collection>>,
-    #[account(mut)]
-    list_head: Box<Account<'info, NftBucket>>,
     #[account(
         mut,
-        address = list_head.next_list_item
+        seeds = [
+            collection.to_account_info().key.as_ref(),
+            owner.key.as_ref(),
+            b"mystery_commit".as_ref(),
+        ],
+        bump = mystery_commit.nonce,
+        has_one = owner,
+        close = owner,
+    )]
+    mystery_commit: Box<Account<'info, MysteryCommit>>,
+    #[account(address = sysvar::slot_hashes::ID)]
+    slot_hashes: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CompactListItem<'info> {
+    #[account(mut, address = list_item.load()?.payer)]
+    rent_refund: UncheckedAccount<'info>,
+    #[account(mut, close = rent_refund)]
+    list_item: AccountLoader<'info, NftBucket>,
+    #[account(
+        mut,
+        address = list_item.load()?.prev_list_item,
+        constraint = prev_list_item.load()?.next_list_item == list_item.key(),
     )]
-    next_list_item: Box<Account<'info, NftBucket>>,
+    prev_list_item: AccountLoader<'info, NftBucket>,
     #[account(
-        init,
-        payer = admin,
-        space = NftBucket::LEN
+        mut,
+        address = list_item.load()?.next_list_item,
+        constraint = next_list_item.load()?.prev_list_item == list_item.key(),
     )]
-    new_item: Box<Account<'info, NftBucket>>,
+    next_list_item: AccountLoader<'info, NftBucket>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawLiquidity<'info> {
+    admin: Signer<'info>,
     #[account(
         seeds = [
-            market.to_account_info().key.as_ref(),
-            &price_model.index.to_le_bytes(),
-            b"price_model".as_ref(),
+            b"global".as_ref(),
         ],
-        bump = price_model.nonce,
+        bump = global_state.nonce,
     )]
-    price_model: Box<Account<'info, PriceModel>>,
-    #[account(mut)]
-    admin_nft_account: Box<Account<'info, TokenAccount>>,
-    #[account(address = admin_nft_account.mint)]
-    admin_nft_mint: Box<Account<'info, Mint>>,
+    global_state: Box<Account<'info, GlobalState>>,
+    #[account(
+        mut,
+        seeds = [
+            global_state.to_account_info().key.as_ref(),
+            &market.load()?.index.to_le_bytes(),
+        ],
+        bump = market.load()?.nonce,
+    )]
+    market: AccountLoader<'info, Market>,
+    withdraw_mint: Box<Account<'info, Mint>>,
+    #[account(
+        mut,
+	    associated_token::mint = withdraw_mint,
+        associated_token::authority = market,
+    )]
+    program_debit_account: Box<Account<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = admin,
-	    associated_token::mint = admin_nft_mint,
-        associated_token::authority = collection,
+	    associated_token::mint = withdraw_mint,
+        associated_token::authority = admin,
     )]
-    program_nft_account: Box<Account<'info, TokenAccount>>,
+    admin_credit_account: Box<Account<'info, TokenAccount>>,
     #[account(address = associated_token::ID)]
     associated_token_program: Program<'info, AssociatedToken>,
     #[account(address = token::ID)]
@@ -511,14 +3410,9 @@ pub struct AddNft<'info> {
 }
 
 #[derive(Accounts)]
-pub struct WithdrawNft<'info> {
+pub struct WithdrawCollectionLiquidity<'info> {
     admin: Signer<'info>,
-    #[account(mut, address = withdraw_list_item.payer)]
-    rent_refund: UncheckedAccount<'info>,
-    #[account(address = withdraw_list_item.price_model)]
-    price_model: Box<Account<'info, PriceModel>>,
-    #[account(address = price_model.market)]
-    market: Box<Account<'info, Market>>,
+    market: AccountLoader<'info, Market>,
     #[account(
         seeds = [
             market.to_account_info().key.as_ref(),
@@ -528,51 +3422,90 @@ pub struct WithdrawNft<'info> {
         bump = collection.nonce,
     )]
     collection: Box<Account<'info, Collection>>,
-    #[account(mut, close = rent_refund)]
-    withdraw_list_item: Box<Account<'info, NftBucket>>,
+    withdraw_mint: Box<Account<'info, Mint>>,
     #[account(
         mut,
-	    associated_token::mint = withdraw_list_item.nft_mint,
+	    associated_token::mint = withdraw_mint,
         associated_token::authority = collection,
     )]
-    program_nft_account: Box<Account<'info, TokenAccount>>,
-    #[account(address = withdraw_list_item.nft_mint)]
-    program_nft_mint: Box<Account<'info, Mint>>,
+    program_debit_account: Box<Account<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = admin,
-	    associated_token::mint = program_nft_mint,
+	    associated_token::mint = withdraw_mint,
         associated_token::authority = admin,
     )]
-    admin_nft_account: Box<Account<'info, TokenAccount>>,
+    admin_credit_account: Box<Account<'info, TokenAccount>>,
+    #[account(address = associated_token::ID)]
+    associated_token_program: Program<'info, AssociatedToken>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AdoptNft<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
     #[account(
         mut,
-        address = withdraw_list_item.prev_list_item
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
     )]
-    prev_list_item: Account<'info, NftBucket>,
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut)]
+    prev_list_item: AccountLoader<'info, NftBucket>,
     #[account(
         mut,
-        address = withdraw_list_item.next_list_item
+        address = prev_list_item.load()?.next_list_item
     )]
-    next_list_item: Account<'info, NftBucket>,
-    #[account(address = associated_token::ID)]
-    associated_token_program: Program<'info, AssociatedToken>,
-    #[account(address = token::ID)]
-    token_program: Program<'info, Token>,
+    next_list_item: AccountLoader<'info, NftBucket>,
+    #[account(
+	    associated_token::mint = nft_mint,
+        associated_token::authority = collection,
+    )]
+    program_nft_account: Box<Account<'info, TokenAccount>>,
+    nft_mint: Box<Account<'info, Mint>>,
+    #[account(
+        init,
+        seeds = [
+            collection.to_account_info().key.as_ref(),
+            nft_mint.to_account_info().key.as_ref(),
+            b"nft_bucket".as_ref(),
+        ],
+        bump,
+        payer = admin,
+        space = NftBucket::LEN
+    )]
+    new_item: AccountLoader<'info, NftBucket>,
+    #[account(mut)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &price_model.load()?.index.to_le_bytes(),
+            b"price_model".as_ref(),
+        ],
+        bump = price_model.load()?.nonce,
+    )]
+    price_model: AccountLoader<'info, PriceModel>,
     #[account(address = system_program::ID)]
     system_program: Program<'info, System>,
     rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct PurchaseNft<'info> {
-    owner: Signer<'info>,
-    #[account(mut, address = purchase_list_item.payer)]
-    rent_refund: UncheckedAccount<'info>,
-    #[account(address = purchase_list_item.price_model)]
-    price_model: Box<Account<'info, PriceModel>>,
-    #[account(address = price_model.market)]
-    market: Box<Account<'info, Market>>,
+pub struct ReturnCollectionTokens<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
     #[account(
         seeds = [
             market.to_account_info().key.as_ref(),
@@ -582,44 +3515,23 @@ pub struct PurchaseNft<'info> {
         bump = collection.nonce,
     )]
     collection: Box<Account<'info, Collection>>,
-    #[account(mut, close = rent_refund)]
-    purchase_list_item: Box<Account<'info, NftBucket>>,
-    #[account(address = debit_account.mint)]
-    debit_mint: Box<Account<'info, Mint>>,
-    #[account(mut, has_one = owner)]
-    debit_account: Box<Account<'info, TokenAccount>>,
-    #[account(
-        init_if_needed,
-        payer = owner,
-	    associated_token::mint = debit_mint,
-        associated_token::authority = market,
-    )]
-    program_credit_account: Box<Account<'info, TokenAccount>>,
+    mint: Box<Account<'info, Mint>>,
+    // NftBucket address of mint, which may not exist
+    nft_bucket: UncheckedAccount<'info>,
     #[account(
         mut,
-	    associated_token::mint = purchase_list_item.nft_mint,
+	    associated_token::mint = mint,
         associated_token::authority = collection,
     )]
-    program_nft_account: Box<Account<'info, TokenAccount>>,
-    #[account(address = purchase_list_item.nft_mint)]
-    program_nft_mint: Box<Account<'info, Mint>>,
+    program_debit_account: Box<Account<'info, TokenAccount>>,
+    recipient: UncheckedAccount<'info>,
     #[account(
         init_if_needed,
-        payer = owner,
-	    associated_token::mint = program_nft_mint,
-        associated_token::authority = owner,
-    )]
-    owner_nft_account: Box<Account<'info, TokenAccount>>,
-    #[account(
-        mut,
-        address = purchase_list_item.prev_list_item
-    )]
-    prev_list_item: Account<'info, NftBucket>,
-    #[account(
-        mut,
-        address = purchase_list_item.next_list_item
+        payer = admin,
+	    associated_token::mint = mint,
+        associated_token::authority = recipient,
     )]
-    next_list_item: Account<'info, NftBucket>,
+    recipient_credit_account: Box<Account<'info, TokenAccount>>,
     #[account(address = associated_token::ID)]
     associated_token_program: Program<'info, AssociatedToken>,
     #[account(address = token::ID)]
@@ -630,7 +3542,7 @@ pub struct PurchaseNft<'info> {
 }
 
 #[derive(Accounts)]
-pub struct WithdrawLiquidity<'info> {
+pub struct ReturnMarketTokens<'info> {
     admin: Signer<'info>,
     #[account(
         seeds = [
@@ -640,27 +3552,29 @@ pub struct WithdrawLiquidity<'info> {
     )]
     global_state: Box<Account<'info, GlobalState>>,
     #[account(
+        mut,
         seeds = [
             global_state.to_account_info().key.as_ref(),
-            &market.index.to_le_bytes(),
+            &market.load()?.index.to_le_bytes(),
         ],
-        bump = market.nonce,
+        bump = market.load()?.nonce,
     )]
-    market: Box<Account<'info, Market>>,
-    withdraw_mint: Box<Account<'info, Mint>>,
+    market: AccountLoader<'info, Market>,
+    mint: Box<Account<'info, Mint>>,
     #[account(
         mut,
-	    associated_token::mint = withdraw_mint,
+	    associated_token::mint = mint,
         associated_token::authority = market,
     )]
     program_debit_account: Box<Account<'info, TokenAccount>>,
+    recipient: UncheckedAccount<'info>,
     #[account(
         init_if_needed,
         payer = admin,
-	    associated_token::mint = withdraw_mint,
-        associated_token::authority = admin,
+	    associated_token::mint = mint,
+        associated_token::authority = recipient,
     )]
-    admin_credit_account: Box<Account<'info, TokenAccount>>,
+    recipient_credit_account: Box<Account<'info, TokenAccount>>,
     #[account(address = associated_token::ID)]
     associated_token_program: Program<'info, AssociatedToken>,
     #[account(address = token::ID)]
@@ -670,11 +3584,36 @@ pub struct WithdrawLiquidity<'info> {
     rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct TrackMarketTreasury<'info> {
+    #[account(
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Box<Account<'info, GlobalState>>,
+    #[account(
+        mut,
+        seeds = [
+            global_state.to_account_info().key.as_ref(),
+            &market.load()?.index.to_le_bytes(),
+        ],
+        bump = market.load()?.nonce,
+    )]
+    market: AccountLoader<'info, Market>,
+    #[account(mut, constraint = treasury.owner == market.key())]
+    treasury: Box<Account<'info, TokenAccount>>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct CloseCollection<'info> {
     admin: Signer<'info>,
-    market: Box<Account<'info, Market>>,
-    #[account(address = list_head.payer)]
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    #[account(address = list_head.load()?.payer)]
     rent_refund: Signer<'info>,
     #[account(
         mut,
@@ -692,28 +3631,623 @@ pub struct CloseCollection<'info> {
         address = collection.list_head,
         close = rent_refund
     )]
-    list_head: Account<'info, NftBucket>,
+    list_head: AccountLoader<'info, NftBucket>,
     #[account(
         mut,
         address = collection.list_tail,
         close = rent_refund
     )]
-    list_tail: Account<'info, NftBucket>,
+    list_tail: AccountLoader<'info, NftBucket>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMarket<'info> {
+    admin: Signer<'info>,
+    #[account(mut)]
+    rent_refund: UncheckedAccount<'info>,
+    #[account(
+        seeds = [
+            b"global".as_ref(),
+        ],
+        bump = global_state.nonce,
+    )]
+    global_state: Box<Account<'info, GlobalState>>,
+    #[account(
+        mut,
+        seeds = [
+            global_state.to_account_info().key.as_ref(),
+            &market.load()?.index.to_le_bytes(),
+        ],
+        bump = market.load()?.nonce,
+        close = rent_refund
+    )]
+    market: AccountLoader<'info, Market>,
+    #[account(address = token::ID)]
+    token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateGlobalState<'info> {
+    #[account(mut)]
+    admin: Signer<'info>,
+    program_data: UncheckedAccount<'info>,
+    #[account(mut)]
+    global_state: UncheckedAccount<'info>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarket<'info> {
+    #[account(mut)]
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: UncheckedAccount<'info>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCollection<'info> {
+    #[account(mut)]
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    #[account(mut)]
+    collection: UncheckedAccount<'info>,
+    #[account(mut)]
+    list_head: UncheckedAccount<'info>,
+    #[account(mut)]
+    list_tail: UncheckedAccount<'info>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CountLegacyBuckets<'info> {
+    admin: Signer<'info>,
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePriceModel<'info> {
+    #[account(mut)]
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    #[account(mut)]
+    price_model: UncheckedAccount<'info>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateNftBucket<'info> {
+    #[account(mut)]
+    admin: Signer<'info>,
+    #[account(mut)]
+    market: AccountLoader<'info, Market>,
+    #[account(
+        mut,
+        seeds = [
+            market.to_account_info().key.as_ref(),
+            &collection.index.to_le_bytes(),
+            b"collection".as_ref(),
+        ],
+        bump = collection.nonce,
+    )]
+    collection: Box<Account<'info, Collection>>,
+    #[account(mut)]
+    nft_bucket: UncheckedAccount<'info>,
+    program_nft_account: Box<Account<'info, TokenAccount>>,
+    // Either neighbour of the bucket, once it has been migrated itself
+    linked_list_item: AccountLoader<'info, NftBucket>,
+    #[account(mut, constraint = price_model.load()?.market == market.key())]
+    price_model: AccountLoader<'info, PriceModel>,
+    #[account(mut)]
+    inventory_page: AccountLoader<'info, InventoryPage>,
+    #[account(address = system_program::ID)]
+    system_program: Program<'info, System>,
+    rent: Sysvar<'info, Rent>,
+}
+
+/*******************/
+/* DATA STRUCTURES */
+/*******************/
+
+#[account]
+pub struct GlobalState {
+    pub version: u8,
+    pub nonce: u8,
+    pub num_markets: u32,
+    pub authority: Pubkey,
+    pub fee_basis_points: u16,
+    pub fee_recipient: Pubkey,
+    pub market_creation: MarketCreation,
+    pub market_creation_fee: u64,
+    pub reserved: [u8; 64],
+}
+
+impl GlobalState {
+    pub const LEN: usize = 153;
+
+    fn is_upgrade_authority(program_data: &AccountInfo, authority: &Pubkey) -> Result<()> {
+        let (program_data_address, _) = Pubkey::find_program_address(
+            &[crate::ID.as_ref()],
+            &bpf_loader_upgradeable::ID,
+        );
+        if *program_data.key != program_data_address {
+            return Err(ErrorCode::InvalidProgramData.into());
+        }
+
+        // UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }
+        let data = program_data.try_borrow_data()?;
+        if data.len() < 45 || data[0..4] != 3u32.to_le_bytes() {
+            return Err(ErrorCode::InvalidProgramData.into());
+        }
+        if data[12] != 1 || data[13..45] != authority.to_bytes() {
+            return Err(ErrorCode::Unauthorized.into());
+        }
+        Ok(())
+    }
+
+    fn is_valid_authority(global_state: &GlobalState, authority: &Pubkey) -> Result<()> {
+        if global_state.authority != *authority {
+            return Err(ErrorCode::Unauthorized.into());
+        };
+        Ok(())
+    }
+
+    pub fn protocol_fee(&self, amount: u64) -> Result<u64> {
+        let fee = (amount as u128)
+            .checked_mul(self.fee_basis_points as u128)
+            .ok_or(ErrorCode::Overflow)?
+            / FEE_BASIS_POINTS_MAX as u128;
+        Ok(fee as u64)
+    }
+
+    pub fn quote(&self, mint: Pubkey, amount: u64) -> Result<PriceQuote> {
+        let protocol_fee = self.protocol_fee(amount)?;
+        Ok(PriceQuote {
+            mint,
+            amount,
+            protocol_fee,
+            proceeds: amount - protocol_fee,
+        })
+    }
+}
+
+impl Default for GlobalState {
+    fn default() -> Self {
+        GlobalState {
+            version: 0,
+            nonce: 0,
+            num_markets: 0,
+            authority: Pubkey::default(),
+            fee_basis_points: 0,
+            fee_recipient: Pubkey::default(),
+            market_creation: MarketCreation::default(),
+            market_creation_fee: 0,
+            reserved: [0; 64],
+        }
+    }
+}
+
+#[account(zero_copy)]
+pub struct Market {
+    pub version: u8,
+    pub nonce: u8,
+    pub index: u32,
+    pub whitelist: [Pubkey; 16],
+    pub whitelist_len: u8,
+    pub num_collections: u32,
+    pub num_price_models: u32,
+    pub title: [u8; 32],
+    pub title_len: u8,
+    pub paused: u8,
+    pub num_open_collections: u32,
+    pub num_open_price_models: u32,
+    pub metadata: MarketMetadata,
+    pub stats: SalesStats,
+    // Slot the market was last unpaused in, mystery box draws committed
+    // before it are void
+    pub unpaused_slot: u64,
+    pub num_treasury_accounts: u32,
+    pub reserved: [u8; 116],
+}
+
+impl Market {
+    pub const LEN: usize = 8 + std::mem::size_of::<Market>();
+
+    fn is_valid_admin(market: &AccountLoader<Market>, admin: &Pubkey) -> Result<()> {
+        if !market.load()?.whitelist().contains(admin) {
+            return Err(ErrorCode::Unauthorized.into());
+        };
+        Ok(())
+    }
+
+    pub fn whitelist(&self) -> &[Pubkey] {
+        &self.whitelist[..self.whitelist_len as usize]
+    }
+
+    fn set_title(&mut self, title: &str) {
+        copy_str(&mut self.title, title);
+        self.title_len = title.len() as u8;
+    }
+}
+
+#[zero_copy]
+pub struct MarketMetadata {
+    pub description: [u8; 256],
+    pub description_len: u16,
+    pub image_uri: [u8; 256],
+    pub image_uri_len: u16,
+    pub external_url: [u8; 256],
+    pub external_url_len: u16,
+    pub sort_priority: u32,
+}
+
+impl MarketMetadata {
+    fn set(&mut self, metadata: &Metadata) {
+        copy_str(&mut self.description, &metadata.description);
+        self.description_len = metadata.description.len() as u16;
+        copy_str(&mut self.image_uri, &metadata.image_uri);
+        self.image_uri_len = metadata.image_uri.len() as u16;
+        copy_str(&mut self.external_url, &metadata.external_url);
+        self.external_url_len = metadata.external_url.len() as u16;
+        self.sort_priority = metadata.sort_priority;
+    }
+}
+
+#[account]
+pub struct Collection {
+    pub version: u8,
+    pub nonce: u8,
+    pub index: u32,
+    pub list_head: Pubkey,
+    pub list_tail: Pubkey,
+    pub title: String,
+    pub paused: bool,
+    pub metadata: Metadata,
+    pub num_inventory_pages: u32,
+    pub sale_start: Option<i64>,
+    pub sale_end: Option<i64>,
+    pub mystery_price_model: Option<Pubkey>,
+    pub stats: SalesStats,
+    pub num_treasury_accounts: u32,
+    // Bumped on every inventory slot change, slots record the version they
+    // changed at so mystery box draws can see the inventory at their commit
+    pub inventory_version: u64,
+    // Last version an inventory page was closed at
+    pub pages_closed_version: u64,
+    // Slot the collection was last unpaused in, as for Market
+    pub unpaused_slot: u64,
+    // Next legacy bucket to count after migrate_collection, default once the
+    // legacy list has been walked to its tail
+    pub legacy_cursor: Pubkey,
+    // Legacy buckets counted but not yet migrated
+    pub num_legacy_buckets: u32,
+}
+
+impl Collection {
+    pub const LEN: usize = 691;
+
+    fn is_unpaused(market: &AccountLoader<Market>, collection: &Collection) -> Result<()> {
+        if market.load()?.paused != 0 || collection.paused {
+            return Err(ErrorCode::Paused.into());
+        };
+        Ok(())
+    }
+
+    fn record_inventory_change(&mut self) -> Result<u64> {
+        self.inventory_version = self.inventory_version
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(self.inventory_version)
+    }
+}
+
+impl Default for Collection {
+    fn default() -> Self {
+        Collection {
+            version: 0,
+            nonce: 0,
+            index: 0,
+            list_head: Pubkey::default(),
+            list_tail: Pubkey::default(),
+            title: String::with_capacity(32),
+            paused: false,
+            metadata: Metadata::default(),
+            num_inventory_pages: 0,
+            sale_start: None,
+            sale_end: None,
+            mystery_price_model: None,
+            stats: SalesStats::default(),
+            num_treasury_accounts: 0,
+            inventory_version: 0,
+            pages_closed_version: 0,
+            unpaused_slot: 0,
+            legacy_cursor: Pubkey::default(),
+            num_legacy_buckets: 0,
+        }
+    }
+}
+
+#[account(zero_copy)]
+pub struct NftBucket {
+    pub version: u8,
+    pub nonce: u8,
+    pub nft_mint: Pubkey,
+    pub price_model: Pubkey,
+    pub prev_list_item: Pubkey,
+    pub next_list_item: Pubkey,
+    pub payer: Pubkey,
+    pub inventory_page: Pubkey,
+    pub inventory_slot: u32,
+    pub tombstoned: u8,
+    pub sale_start: i64,
+    pub sale_end: i64,
+    pub expires_at: i64,
+    // Set on buckets migrated from keypair accounts, PDA buckets are derived
+    // from their collection instead
+    pub collection: Pubkey,
+    pub reserved: [u8; 32],
 }
 
-/*******************/
-/* DATA STRUCTURES */
-/*******************/
+impl NftBucket {
+    pub const LEN: usize = 8 + std::mem::size_of::<NftBucket>();
+
+    fn is_listed(nft_bucket: &AccountLoader<NftBucket>) -> Result<()> {
+        if nft_bucket.load()?.tombstoned != 0 {
+            return Err(ErrorCode::ListItemTombstoned.into());
+        };
+        Ok(())
+    }
+
+    fn is_on_sale(collection: &Collection, nft_bucket: &AccountLoader<NftBucket>) -> Result<()> {
+        let nft_bucket = nft_bucket.load()?;
+        let now = Clock::get()?.unix_timestamp;
+        check_sale_window(collection.sale_start, collection.sale_end, now)?;
+        check_sale_window(
+            optional_timestamp(nft_bucket.sale_start),
+            optional_timestamp(nft_bucket.sale_end),
+            now,
+        )?;
+        if let Some(expires_at) = optional_timestamp(nft_bucket.expires_at) {
+            if now >= expires_at {
+                return Err(ErrorCode::ListingExpired.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn is_expired(nft_bucket: &AccountLoader<NftBucket>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        match optional_timestamp(nft_bucket.load()?.expires_at) {
+            Some(expires_at) if now >= expires_at => Ok(()),
+            _ => Err(ErrorCode::ListingNotExpired.into()),
+        }
+    }
+}
 
 #[account]
 #[derive(Default)]
-pub struct GlobalState {
+pub struct MysteryCommit {
+    pub nonce: u8,
+    pub owner: Pubkey,
+    pub collection: Pubkey,
+    pub commit_slot: u64,
+    pub debit_mint: Pubkey,
+    pub amount: u64,
+    pub commit_timestamp: i64,
+    pub inventory_version: u64,
+}
+
+impl MysteryCommit {
+    pub const LEN: usize = 137;
+
+    // A pause could have blocked the reveal and a closed page loses the slots
+    // the draw is made over, either voids the draw
+    pub fn is_void(&self, market: &Market, collection: &Collection) -> bool {
+        // Copied out of the packed account before comparing
+        let market_unpaused_slot = market.unpaused_slot;
+        market.paused != 0 ||
+        collection.paused ||
+        market_unpaused_slot > self.commit_slot ||
+        collection.unpaused_slot > self.commit_slot ||
+        collection.pages_closed_version > self.inventory_version
+    }
+}
+
+#[account(zero_copy)]
+pub struct InventoryPage {
+    pub collection: Pubkey,
+    pub index: u32,
+    pub nonce: u8,
+    pub num_occupied: u32,
+    pub free_head: u32,
+    pub slots: [InventorySlot; 64],
+}
+
+impl InventoryPage {
+    pub const LEN: usize = 8 + std::mem::size_of::<InventoryPage>();
+
+    fn insert(&mut self, nft_bucket: Pubkey, nft_mint: Pubkey, version: u64) -> Result<u32> {
+        let slot_index = self.free_head;
+        if slot_index == INVENTORY_SLOT_NONE {
+            return Err(ErrorCode::InventoryPageFull.into());
+        }
+
+        let slot = &mut self.slots[slot_index as usize];
+        self.free_head = slot.next_free;
+        slot.nft_bucket = nft_bucket;
+        slot.nft_mint = nft_mint;
+        slot.next_free = INVENTORY_SLOT_NONE;
+        // New buckets are on sale until given a sale window or expiry
+        slot.set_draw_window(DrawWindow {
+            start: i64::MIN,
+            end: i64::MAX,
+            version,
+        });
+
+        self.num_occupied = self.num_occupied
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(slot_index)
+    }
+
+    fn remove(&mut self, slot_index: u32, nft_bucket: &Pubkey, version: u64) -> Result<()> {
+        let free_head = self.free_head;
+        let slot = self.slots
+            .get_mut(slot_index as usize)
+            .ok_or(ErrorCode::InvalidInventorySlot)?;
+        if slot.nft_bucket != *nft_bucket {
+            return Err(ErrorCode::InvalidInventorySlot.into());
+        }
+
+        slot.nft_bucket = Pubkey::default();
+        slot.nft_mint = Pubkey::default();
+        slot.next_free = free_head;
+        slot.set_draw_window(DrawWindow {
+            start: 0,
+            end: 0,
+            version,
+        });
+        self.free_head = slot_index;
+
+        self.num_occupied = self.num_occupied
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    fn sync_draw_window(
+        &mut self,
+        slot_index: u32,
+        nft_bucket_key: &Pubkey,
+        nft_bucket: &NftBucket,
+        version: u64,
+    ) -> Result<()> {
+        let slot = self.slots
+            .get_mut(slot_index as usize)
+            .ok_or(ErrorCode::InvalidInventorySlot)?;
+        if slot.nft_bucket != *nft_bucket_key {
+            return Err(ErrorCode::InvalidInventorySlot.into());
+        }
+
+        let sale_end = optional_timestamp(nft_bucket.sale_end).unwrap_or(i64::MAX);
+        let expires_at = optional_timestamp(nft_bucket.expires_at).unwrap_or(i64::MAX);
+        slot.set_draw_window(DrawWindow {
+            start: optional_timestamp(nft_bucket.sale_start).unwrap_or(i64::MIN),
+            end: sale_end.min(expires_at),
+            version,
+        });
+        Ok(())
+    }
+}
+
+// Mystery box draws are made over the inventory as it was at the commit, so
+// each slot keeps the window its bucket could be bought in along with the one
+// it replaced. A slot changed twice since a commit no longer tells what it held.
+#[zero_copy]
+pub struct InventorySlot {
+    pub nft_bucket: Pubkey,
+    pub nft_mint: Pubkey,
+    pub next_free: u32,
+    pub draw_window: DrawWindow,
+    pub prior_draw_window: DrawWindow,
+}
+
+impl InventorySlot {
+    fn set_draw_window(&mut self, draw_window: DrawWindow) {
+        self.prior_draw_window = self.draw_window;
+        self.draw_window = draw_window;
+    }
+
+    /// Whether a draw committed at inventory `version` and `timestamp` could
+    /// select this slot, or `None` if the slot has changed too often since.
+    pub fn could_be_drawn(&self, version: u64, timestamp: i64) -> Option<bool> {
+        let (draw_window, prior_draw_window) = (self.draw_window, self.prior_draw_window);
+        if draw_window.version <= version {
+            Some(draw_window.contains(timestamp))
+        } else if prior_draw_window.version <= version {
+            Some(prior_draw_window.contains(timestamp))
+        } else {
+            None
+        }
+    }
+}
+
+// Times a slot's bucket is on sale, [start, end), from the collection
+// inventory version it was set at. Empty slots have an empty window.
+#[zero_copy]
+pub struct DrawWindow {
+    pub start: i64,
+    pub end: i64,
+    pub version: u64,
+}
+
+impl DrawWindow {
+    pub fn contains(&self, timestamp: i64) -> bool {
+        let (start, end) = (self.start, self.end);
+        start <= timestamp && timestamp < end
+    }
+}
+
+#[account(zero_copy)]
+pub struct PriceModel {
+    pub version: u8,
+    pub nonce: u8,
+    pub index: u32,
+    pub market: Pubkey,
+    pub sale_prices: [SalePrice; 8],
+    pub num_sale_prices: u8,
+    pub num_listings: u32,
+    pub reserved: [u8; 64],
+}
+
+impl PriceModel {
+    pub const LEN: usize = 8 + std::mem::size_of::<PriceModel>();
+
+    fn sale_price(&self, mint: &Pubkey) -> Result<u64> {
+        self.sale_prices[..self.num_sale_prices as usize]
+            .iter()
+            .find(|sale_price| sale_price.mint == *mint)
+            .map(|sale_price| sale_price.amount)
+            .ok_or_else(|| ErrorCode::InvalidPurchaseMint.into())
+    }
+}
+
+// Layouts written before accounts were versioned, read only by the migrate_*
+// instructions. LEN is the original allocation, which identifies them.
+#[derive(AnchorDeserialize)]
+pub struct LegacyGlobalState {
     pub nonce: u8,
     pub num_markets: u32,
 }
 
-#[account]
-pub struct Market {
+impl LegacyGlobalState {
+    pub const LEN: usize = 13;
+}
+
+#[derive(AnchorDeserialize)]
+pub struct LegacyMarket {
     pub nonce: u8,
     pub index: u32,
     pub whitelist: Vec<Pubkey>,
@@ -722,35 +4256,12 @@ pub struct Market {
     pub title: String,
 }
 
-impl Market {
+impl LegacyMarket {
     pub const LEN: usize = 575;
-
-    fn is_valid_admin(market: &Market, admin: &Pubkey) -> Result<()> {
-        if !market.whitelist.contains(&admin) {
-            return Err(ErrorCode::Unauthorized.into());
-        };
-        return Ok(())
-    }
-}
-
-impl Default for Market {
-    fn default() -> Self {
-        Market {
-            nonce: 0,
-            index: 0,
-            whitelist: vec![
-                Pubkey::default();
-                ADMIN_WHITELIST_MAX_LEN
-            ],
-            num_collections: 0,
-            num_price_models: 0,
-            title: String::with_capacity(32),
-        }
-    }
 }
 
-#[account]
-pub struct Collection {
+#[derive(AnchorDeserialize)]
+pub struct LegacyCollection {
     pub nonce: u8,
     pub index: u32,
     pub list_head: Pubkey,
@@ -758,25 +4269,12 @@ pub struct Collection {
     pub title: String,
 }
 
-impl Collection {
+impl LegacyCollection {
     pub const LEN: usize = 115;
 }
 
-impl Default for Collection {
-    fn default() -> Self {
-        Collection {
-            nonce: 0,
-            index: 0,
-            list_head: Pubkey::default(),
-            list_tail: Pubkey::default(),
-            title: String::with_capacity(32),
-        }
-    }
-}
-
-#[account]
-#[derive(Default)]
-pub struct NftBucket {
+#[derive(AnchorDeserialize)]
+pub struct LegacyNftBucket {
     pub nonce: u8,
     pub nft_mint: Pubkey,
     pub price_model: Pubkey,
@@ -785,49 +4283,307 @@ pub struct NftBucket {
     pub payer: Pubkey,
 }
 
-impl NftBucket {
+impl LegacyNftBucket {
     pub const LEN: usize = 169;
 }
 
-#[account]
-pub struct PriceModel {
+#[derive(AnchorDeserialize)]
+pub struct LegacyPriceModel {
     pub nonce: u8,
     pub index: u32,
     pub market: Pubkey,
     pub sale_prices: Vec<SalePrice>,
 }
 
-impl PriceModel {
+impl LegacyPriceModel {
     pub const LEN: usize = 370;
 }
 
-impl Default for PriceModel {
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Default,
+)]
+pub struct Metadata {
+    pub description: String,
+    pub image_uri: String,
+    pub external_url: String,
+    pub sort_priority: u32,
+}
+
+impl Metadata {
+    fn validate(&self) -> Result<()> {
+        if self.description.len() > DESCRIPTION_MAX_LEN {
+            return Err(ErrorCode::DescriptionTooLong.into());
+        }
+        if self.image_uri.len() > URI_MAX_LEN || self.external_url.len() > URI_MAX_LEN {
+            return Err(ErrorCode::UriTooLong.into());
+        }
+        Ok(())
+    }
+}
+
+#[zero_copy]
+pub struct SalesStats {
+    pub num_listed: u32,
+    pub num_sold: u32,
+    pub volume: [MintVolume; 8],
+    pub num_volume: u8,
+    pub last_sale: LastSale,
+}
+
+impl SalesStats {
+    fn record_listing(&mut self) -> Result<()> {
+        self.num_listed = self.num_listed
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    fn record_delisting(&mut self) -> Result<()> {
+        self.num_listed = self.num_listed
+            .checked_sub(1)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    fn record_sale(&mut self, sale: &LastSale) -> Result<()> {
+        self.record_delisting()?;
+        self.num_sold = self.num_sold
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+
+        // The volume table is bounded, mints beyond the first
+        // MINT_VOLUME_MAX_LEN are counted in num_sold only. Fields of packed
+        // structs can't be borrowed, so work on copies and write them back.
+        let mut volume = self.volume;
+        let mut num_volume = self.num_volume as usize;
+        match volume[..num_volume].iter().position(|mv| mv.mint == sale.debit_mint) {
+            Some(index) => {
+                let amount = volume[index].amount
+                    .checked_add(sale.amount)
+                    .ok_or(ErrorCode::Overflow)?;
+                volume[index].amount = amount;
+            }
+            None if num_volume < MINT_VOLUME_MAX_LEN => {
+                volume[num_volume] = MintVolume {
+                    mint: sale.debit_mint,
+                    amount: sale.amount,
+                };
+                num_volume += 1;
+            }
+            None => {}
+        }
+
+        self.volume = volume;
+        self.num_volume = num_volume as u8;
+        self.last_sale = *sale;
+        Ok(())
+    }
+}
+
+impl Default for SalesStats {
     fn default() -> Self {
-        PriceModel {
-            nonce: 0,
-            index: 0,
-            market: Pubkey::default(),
-            sale_prices: vec![
-                SalePrice {
+        SalesStats {
+            num_listed: 0,
+            num_sold: 0,
+            volume: [
+                MintVolume {
                     mint: Pubkey::default(),
                     amount: 0,
                 };
-                8
+                MINT_VOLUME_MAX_LEN
             ],
+            num_volume: 0,
+            last_sale: LastSale {
+                nft_mint: Pubkey::default(),
+                debit_mint: Pubkey::default(),
+                amount: 0,
+                buyer: Pubkey::default(),
+                timestamp: 0,
+            },
         }
     }
 }
 
+#[zero_copy]
+pub struct MintVolume {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[zero_copy]
+pub struct LastSale {
+    pub nft_mint: Pubkey,
+    pub debit_mint: Pubkey,
+    pub amount: u64,
+    pub buyer: Pubkey,
+    pub timestamp: i64,
+}
+
 #[derive(
     AnchorSerialize,
     AnchorDeserialize,
     Clone,
+    Copy,
+    PartialEq,
 )]
+pub enum MarketCreation {
+    Open,
+    Permissioned,
+    FeeGated,
+}
+
+// #[default] on enum variants needs a newer compiler than the BPF toolchain
+#[allow(clippy::derivable_impls)]
+impl Default for MarketCreation {
+    fn default() -> Self {
+        MarketCreation::Permissioned
+    }
+}
+
+#[zero_copy]
 pub struct SalePrice {
     pub mint: Pubkey,
     pub amount: u64,
 }
 
+// Returned by quote_purchase, one per sale price
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+)]
+pub struct PriceQuote {
+    pub mint: Pubkey,
+    // Debited from the buyer
+    pub amount: u64,
+    pub protocol_fee: u64,
+    // Credited to the collection
+    pub proceeds: u64,
+}
+
+// Returned by list_page
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Debug,
+    PartialEq,
+)]
+pub struct ListPageData {
+    pub items: Vec<ListPageItem>,
+    // Pass as the cursor for the next page, None once the tail is reached
+    pub next_cursor: Option<Pubkey>,
+}
+
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+)]
+pub struct ListPageItem {
+    pub nft_bucket: Pubkey,
+    pub nft_mint: Pubkey,
+}
+
+// Returned by verify_collection
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+)]
+pub struct ListVerification {
+    // Buckets walked before the tail or the first inconsistency
+    pub count: u32,
+    pub num_tombstoned: u32,
+    pub inconsistency: Option<ListInconsistency>,
+    // Pass as the cursor to keep verifying when the accounts ran out first
+    pub next_cursor: Option<Pubkey>,
+}
+
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+)]
+pub enum ListInconsistency {
+    // A next pointer leads to an account that isn't a bucket of the collection
+    InvalidItem {
+        list_item: Pubkey,
+    },
+    // A bucket's prev pointer doesn't lead back to the bucket before it
+    BrokenPrevLink {
+        list_item: Pubkey,
+        expected: Pubkey,
+        found: Pubkey,
+    },
+}
+
+#[event]
+pub struct NftAdopted {
+    pub admin: Pubkey,
+    pub market: Pubkey,
+    pub collection: Pubkey,
+    pub nft_mint: Pubkey,
+    pub nft_bucket: Pubkey,
+}
+
+#[event]
+pub struct TokensReturned {
+    pub admin: Pubkey,
+    pub market: Pubkey,
+    // None when returned from the market's account
+    pub collection: Option<Pubkey>,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub recipient: Pubkey,
+}
+
+// Packed fixed-size types encode to the same bytes under Borsh, which lets them
+// be embedded in Borsh accounts and instruction arguments as well as zero-copy
+// accounts. #[zero_copy] doesn't derive Pod; the types are repr(packed) with
+// only Pod fields, so they have no padding and every bit pattern is valid.
+macro_rules! impl_pod_borsh {
+    ($($ty:ty),*) => {$(
+        unsafe impl bytemuck::Zeroable for $ty {}
+        unsafe impl bytemuck::Pod for $ty {}
+
+        impl AnchorSerialize for $ty {
+            fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                writer.write_all(bytemuck::bytes_of(self))
+            }
+        }
+
+        impl AnchorDeserialize for $ty {
+            fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+                let len = std::mem::size_of::<$ty>();
+                if buf.len() < len {
+                    return Err(std::io::ErrorKind::UnexpectedEof.into());
+                }
+                let value = *bytemuck::from_bytes::<$ty>(&buf[..len]);
+                *buf = &buf[len..];
+                Ok(value)
+            }
+        }
+    )*};
+}
+
+impl_pod_borsh!(SalePrice, SalesStats, MintVolume, LastSale);
+
 #[error]
 pub enum ErrorCode {
     #[msg("Instruction invoked without a valid admin.")]
@@ -844,4 +4600,271 @@ pub enum ErrorCode {
     AdminOutOfBounds,
     #[msg("Collections cannot be closed until all NFTs are removed.")]
     CollectionNonEmpty,
-}
\ No newline at end of file
+    #[msg("Fee basis points cannot exceed 10000.")]
+    InvalidFeeBasisPoints,
+    #[msg("Program data account does not match the deployed program.")]
+    InvalidProgramData,
+    #[msg("Market or collection is paused.")]
+    Paused,
+    #[msg("Price models cannot be closed while NFTs are listed with them.")]
+    PriceModelInUse,
+    #[msg("Markets cannot be closed until all collections and price models are closed.")]
+    MarketNonEmpty,
+    #[msg("Treasury account is not owned by the closing account.")]
+    InvalidTreasury,
+    #[msg("Treasury accounts must be emptied before closing.")]
+    TreasuryNonEmpty,
+    #[msg("Title exceeded max length of 32.")]
+    TitleTooLong,
+    #[msg("Description exceeded max length of 256.")]
+    DescriptionTooLong,
+    #[msg("URI exceeded max length of 200.")]
+    UriTooLong,
+    #[msg("Inventory page does not belong to the collection.")]
+    InvalidInventoryPage,
+    #[msg("Inventory page has no free slots.")]
+    InventoryPageFull,
+    #[msg("Inventory slot does not hold the NFT bucket.")]
+    InvalidInventorySlot,
+    #[msg("Inventory pages cannot be closed until all NFTs are removed.")]
+    InventoryPageNonEmpty,
+    #[msg("List item has already been purchased or withdrawn.")]
+    ListItemTombstoned,
+    #[msg("Only purchased or withdrawn list items can be compacted.")]
+    ListItemNotTombstoned,
+    #[msg("List item does not belong to the collection or cannot be linked there.")]
+    InvalidListItem,
+    #[msg("List item is already at the requested position.")]
+    InvalidMove,
+    #[msg("Sale has not started yet.")]
+    SaleNotStarted,
+    #[msg("Sale has ended.")]
+    SaleEnded,
+    #[msg("Sale start must be before sale end.")]
+    InvalidSaleWindow,
+    #[msg("Listing has expired.")]
+    ListingExpired,
+    #[msg("Listing has not expired.")]
+    ListingNotExpired,
+    #[msg("Collection does not offer mystery box purchases with this price model.")]
+    MysteryBoxDisabled,
+    #[msg("Purchase commitment must be revealed in a later slot.")]
+    RevealTooEarly,
+    #[msg("Purchase commitment can no longer be revealed.")]
+    RevealWindowElapsed,
+    #[msg("List item does not match the random selection.")]
+    InvalidRandomSelection,
+    #[msg("Purchase can only be refunded once its draw can't be delivered.")]
+    RefundUnavailable,
+    #[msg("Price model exceeded max length of 8 sale prices.")]
+    SalePricesOutOfBounds,
+    #[msg("Account is not at a version that can be migrated.")]
+    InvalidAccountVersion,
+    #[msg("Account does not belong to the account being migrated.")]
+    InvalidMigrationAccount,
+    #[msg("List items cannot be linked in this order.")]
+    InvalidRepair,
+    #[msg("List items are already linked.")]
+    ListLinkIntact,
+    #[msg("Account does not hold the amount to rescue.")]
+    InvalidRescueAmount,
+    #[msg("Fee account is not the fee recipient's associated token account.")]
+    InvalidFeeAccount,
+    #[msg("Purchase commitment can still be revealed.")]
+    RevealWindowOpen,
+    #[msg("Nothing could be drawn, the purchase can be refunded.")]
+    InventoryEmpty,
+    #[msg("Every tracked treasury account must be closed with its collection or market.")]
+    TreasuryNotClosed,
+    #[msg("Draw can no longer be delivered, the purchase can be refunded.")]
+    DrawVoided,
+    #[msg("Collection has legacy buckets left to count or migrate.")]
+    LegacyBucketsPending,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Accounts created through a CPI to the system program are capped at 10KiB
+    const MAX_INIT_ACCOUNT_LEN: usize = 10_240;
+
+    fn serialized_len<T: AccountSerialize>(account: &T) -> usize {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.len()
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn zero_copy_accounts_fit_init_limit() {
+        assert!(Market::LEN <= MAX_INIT_ACCOUNT_LEN);
+        assert!(NftBucket::LEN <= MAX_INIT_ACCOUNT_LEN);
+        assert!(PriceModel::LEN <= MAX_INIT_ACCOUNT_LEN);
+        assert!(InventoryPage::LEN <= MAX_INIT_ACCOUNT_LEN);
+    }
+
+    #[test]
+    fn zero_copy_accounts_hold_max_lengths() {
+        // Array lengths are literals for the IDL, keep them in step with the limits
+        let market: Market = bytemuck::Zeroable::zeroed();
+        assert_eq!(market.whitelist.len(), ADMIN_WHITELIST_MAX_LEN);
+        assert_eq!(market.title.len(), TITLE_MAX_LEN);
+        assert_eq!(market.metadata.description.len(), DESCRIPTION_MAX_LEN);
+        assert!(market.metadata.image_uri.len() >= URI_MAX_LEN);
+        assert!(market.metadata.external_url.len() >= URI_MAX_LEN);
+        assert_eq!(market.stats.volume.len(), MINT_VOLUME_MAX_LEN);
+
+        let price_model: PriceModel = bytemuck::Zeroable::zeroed();
+        assert_eq!(price_model.sale_prices.len(), SALE_PRICES_MAX_LEN);
+
+        let inventory_page: InventoryPage = bytemuck::Zeroable::zeroed();
+        assert_eq!(inventory_page.slots.len(), INVENTORY_PAGE_SLOTS);
+    }
+
+    #[test]
+    fn collection_fits_allocated_space() {
+        let collection = Collection {
+            title: "t".repeat(TITLE_MAX_LEN),
+            sale_start: Some(i64::MAX),
+            sale_end: Some(i64::MAX),
+            mystery_price_model: Some(Pubkey::new_unique()),
+            ..Collection::default()
+        };
+        assert_eq!(serialized_len(&collection), Collection::LEN);
+    }
+
+    #[test]
+    fn global_state_fits_allocated_space() {
+        assert_eq!(serialized_len(&GlobalState::default()), GlobalState::LEN);
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn versioned_accounts_outgrow_legacy_layouts() {
+        // Migration tells legacy accounts apart by their original allocation
+        assert!(GlobalState::LEN > LegacyGlobalState::LEN);
+        assert!(Market::LEN > LegacyMarket::LEN);
+        assert!(Collection::LEN > LegacyCollection::LEN);
+        assert!(NftBucket::LEN > LegacyNftBucket::LEN);
+        assert!(PriceModel::LEN > LegacyPriceModel::LEN);
+    }
+
+    #[test]
+    fn legacy_accounts_are_read_once() {
+        let key = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = Market::discriminator().to_vec();
+        data.push(254);
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(admin.as_ref());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        "Vibe".to_string().serialize(&mut data).unwrap();
+        data.resize(LegacyMarket::LEN, 0);
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+
+        let legacy: LegacyMarket = read_legacy_account::<Market, _>(&info, LegacyMarket::LEN).unwrap();
+        assert_eq!(legacy.nonce, 254);
+        assert_eq!(legacy.index, 3);
+        assert_eq!(legacy.whitelist, vec![admin]);
+        assert_eq!(legacy.title, "Vibe");
+
+        // The wrong account type and already migrated accounts are rejected
+        assert!(read_legacy_account::<Collection, LegacyCollection>(&info, LegacyCollection::LEN).is_err());
+
+        let mut lamports = 0;
+        let mut data = Market::discriminator().to_vec();
+        data.push(ACCOUNT_VERSION);
+        data.resize(Market::LEN, 0);
+        let info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &crate::ID, false, 0);
+        assert_eq!(account_version::<Market>(&info, LegacyMarket::LEN).unwrap(), ACCOUNT_VERSION);
+        assert!(read_legacy_account::<Market, LegacyMarket>(&info, LegacyMarket::LEN).is_err());
+    }
+
+    #[test]
+    fn inventory_slots_keep_the_draw_window_at_a_commit() {
+        let mut inventory_page: InventoryPage = bytemuck::Zeroable::zeroed();
+        let nft_bucket_key = Pubkey::new_unique();
+        let slot_index = inventory_page.insert(nft_bucket_key, Pubkey::new_unique(), 1).unwrap();
+        let slot = inventory_page.slots[slot_index as usize];
+        assert_eq!(slot.could_be_drawn(0, 100), Some(false));
+        assert_eq!(slot.could_be_drawn(1, 100), Some(true));
+
+        // One change since a commit leaves the window it was made over
+        let mut nft_bucket: NftBucket = bytemuck::Zeroable::zeroed();
+        nft_bucket.sale_start = 200;
+        inventory_page.sync_draw_window(slot_index, &nft_bucket_key, &nft_bucket, 2).unwrap();
+        let slot = inventory_page.slots[slot_index as usize];
+        assert_eq!(slot.could_be_drawn(1, 100), Some(true));
+        assert_eq!(slot.could_be_drawn(2, 100), Some(false));
+        assert_eq!(slot.could_be_drawn(2, 200), Some(true));
+
+        inventory_page.remove(slot_index, &nft_bucket_key, 3).unwrap();
+        let slot = inventory_page.slots[slot_index as usize];
+        assert_eq!(slot.could_be_drawn(1, 100), None);
+        assert_eq!(slot.could_be_drawn(2, 200), Some(true));
+        assert_eq!(slot.could_be_drawn(3, 200), Some(false));
+    }
+
+    #[test]
+    fn mystery_commit_fits_allocated_space() {
+        assert_eq!(serialized_len(&MysteryCommit::default()), MysteryCommit::LEN);
+    }
+
+    #[test]
+    fn views_fit_return_data() {
+        const MAX_RETURN_DATA: usize = 1024;
+
+        let global_state = GlobalState {
+            fee_basis_points: 250,
+            ..GlobalState::default()
+        };
+        let quote = global_state.quote(Pubkey::new_unique(), 1_000).unwrap();
+        assert_eq!((quote.protocol_fee, quote.proceeds), (25, 975));
+        let quotes = vec![quote; SALE_PRICES_MAX_LEN];
+        assert!(quotes.try_to_vec().unwrap().len() <= MAX_RETURN_DATA);
+
+        let page = ListPageData {
+            items: vec![
+                ListPageItem {
+                    nft_bucket: Pubkey::new_unique(),
+                    nft_mint: Pubkey::new_unique(),
+                };
+                LIST_PAGE_MAX_LEN
+            ],
+            next_cursor: Some(Pubkey::new_unique()),
+        };
+        assert!(page.try_to_vec().unwrap().len() <= MAX_RETURN_DATA);
+    }
+
+    #[test]
+    fn pod_types_roundtrip_through_borsh() {
+        let sale_price = SalePrice {
+            mint: Pubkey::new_unique(),
+            amount: 100,
+        };
+        let data = sale_price.try_to_vec().unwrap();
+        assert_eq!(data.len(), std::mem::size_of::<SalePrice>());
+        let decoded = SalePrice::try_from_slice(&data).unwrap();
+        assert_eq!({ decoded.mint }, { sale_price.mint });
+        assert_eq!({ decoded.amount }, 100);
+
+        let mut stats = SalesStats::default();
+        stats.record_listing().unwrap();
+        stats.record_sale(&LastSale {
+            nft_mint: Pubkey::new_unique(),
+            debit_mint: sale_price.mint,
+            amount: 95,
+            buyer: Pubkey::new_unique(),
+            timestamp: 1,
+        }).unwrap();
+        let data = stats.try_to_vec().unwrap();
+        assert_eq!(data.len(), std::mem::size_of::<SalesStats>());
+        let decoded = SalesStats::try_from_slice(&data).unwrap();
+        assert_eq!({ decoded.num_sold }, 1);
+        assert_eq!({ decoded.volume[0].amount }, 95);
+        assert!(SalesStats::try_from_slice(&data[1..]).is_err());
+    }
+}
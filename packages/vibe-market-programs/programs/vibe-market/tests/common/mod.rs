@@ -0,0 +1,626 @@
+//! Shared solana-program-test harness.
+//!
+//! The bank loads the program's BPF build, so the code under test is the code
+//! that ships, alongside the SPL token and associated token programs the bank
+//! ships with. `cargo test-bpf` builds it and points `BPF_OUT_DIR` at the
+//! output. The admin keypair is injected as the program upgrade authority so
+//! `init_global_state` and the migrations can run.
+
+#![allow(dead_code)]
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use anchor_lang::{AccountDeserialize, Discriminator, ZeroCopy};
+use solana_program_test::{ProgramTest, ProgramTestContext};
+use solana_sdk::account::{from_account, Account, AccountSharedData};
+use solana_sdk::bpf_loader_upgradeable;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::slot_hashes::SlotHashes;
+use solana_sdk::system_instruction;
+use solana_sdk::sysvar;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_sdk::transport::TransportError;
+use spl_associated_token_account::{create_associated_token_account, get_associated_token_address};
+use vibe_market::{
+    Collection, GlobalState, InventoryPage, Market, NftBucket, PriceModel, SalePrice,
+};
+use vibe_market_client::account::{decode_account, decode_zero_copy, walk_list, ListItem};
+use vibe_market_client::instruction::{self as ix, ListNft};
+use vibe_market_client::pda::*;
+use vibe_market_client::ClientError;
+
+// Anchor offsets custom program errors by 300
+const ERROR_CODE_OFFSET: u32 = 300;
+
+pub const SOL: u64 = 1_000_000_000;
+pub const PRICE: u64 = 1_000;
+
+pub fn error_code(error: vibe_market::ErrorCode) -> u32 {
+    ERROR_CODE_OFFSET + error as u32
+}
+
+/// Custom program error a transaction failed with.
+pub fn custom_error(result: Result<(), TransportError>) -> u32 {
+    match result {
+        Err(TransportError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => code,
+        other => panic!("expected a custom program error, got {:?}", other),
+    }
+}
+
+pub fn clone_keypair(keypair: &Keypair) -> Keypair {
+    Keypair::from_bytes(&keypair.to_bytes()).unwrap()
+}
+
+/// A program-owned account holding `data`, funded for rent exemption.
+pub fn program_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: vibe_market::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// An SPL token account or mint holding `state`, funded for rent exemption.
+pub fn token_program_account<T: Pack>(state: T) -> Account {
+    let mut data = vec![0; T::LEN];
+    T::pack(state, &mut data).unwrap();
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Account data for a legacy `T`: the current discriminator followed by the
+/// unversioned Borsh layout, padded to its original allocation.
+pub fn legacy_account<T: Discriminator>(
+    legacy: &impl anchor_lang::AnchorSerialize,
+    len: usize,
+) -> Account {
+    let mut data = T::discriminator().to_vec();
+    legacy.serialize(&mut data).unwrap();
+    data.resize(len, 0);
+    program_account(data)
+}
+
+/// Market with one collection, one inventory page, a price model charging
+/// `PRICE` of `payment_mint` and one listed NFT.
+pub struct Listing {
+    pub market: Pubkey,
+    pub collection: Pubkey,
+    pub inventory_page: Pubkey,
+    pub price_model: Pubkey,
+    pub payment_mint: Pubkey,
+    pub nft_mint: Pubkey,
+    pub nft_bucket: Pubkey,
+}
+
+pub struct Test {
+    pub context: ProgramTestContext,
+    pub admin: Keypair,
+    // Signatures sent with `context.last_blockhash`
+    signatures: HashSet<Signature>,
+}
+
+impl Test {
+    pub async fn start() -> Test {
+        Test::start_with_accounts(Keypair::new(), Vec::new()).await
+    }
+
+    /// Starts a bank preloaded with `accounts`, e.g. legacy layouts to migrate.
+    pub async fn start_with_accounts(admin: Keypair, accounts: Vec<(Pubkey, Account)>) -> Test {
+        let mut program_test = ProgramTest::new("vibe_market", vibe_market::id(), None);
+        program_test.prefer_bpf(true);
+
+        // UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }
+        let mut program_data = vec![0u8; 45];
+        program_data[..4].copy_from_slice(&3u32.to_le_bytes());
+        program_data[12] = 1;
+        program_data[13..45].copy_from_slice(admin.pubkey().as_ref());
+        program_test.add_account(
+            find_program_data_address().0,
+            Account {
+                lamports: Rent::default().minimum_balance(program_data.len()),
+                data: program_data,
+                owner: bpf_loader_upgradeable::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        program_test.add_account(
+            admin.pubkey(),
+            Account::new(100 * SOL, 0, &solana_sdk::system_program::id()),
+        );
+        for (address, account) in accounts {
+            program_test.add_account(address, account);
+        }
+
+        Test {
+            context: program_test.start_with_context().await,
+            admin,
+            signatures: HashSet::new(),
+        }
+    }
+
+    pub fn admin(&self) -> Keypair {
+        clone_keypair(&self.admin)
+    }
+
+    pub fn payer(&self) -> Keypair {
+        clone_keypair(&self.context.payer)
+    }
+
+    /// Sends `instructions` in one transaction paid by the first signer.
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransportError> {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&signers[0].pubkey()));
+        loop {
+            let blockhash = self.context.banks_client.get_latest_blockhash().await?;
+            if blockhash != self.context.last_blockhash {
+                self.context.last_blockhash = blockhash;
+                self.signatures.clear();
+            }
+            transaction.sign(&signers.to_vec(), blockhash);
+
+            // Identical transactions are deduplicated, so a verbatim retry of
+            // an earlier transaction waits for the next blockhash
+            if self.signatures.insert(transaction.signatures[0]) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    pub async fn rent(&mut self) -> Rent {
+        self.context.banks_client.get_rent().await.unwrap()
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+    }
+
+    /// Advances the bank past the current slot, so SlotHashes gains an entry.
+    pub async fn advance_slot(&mut self) {
+        // The working bank runs ahead of the root the sysvar getter reads, and
+        // warping lands one slot short of the target so it can freeze
+        let clock = self
+            .context
+            .banks_client
+            .get_account_with_commitment(sysvar::clock::id(), CommitmentLevel::Processed)
+            .await
+            .unwrap()
+            .unwrap();
+        let slot = from_account::<Clock, _>(&clock).unwrap().slot;
+        self.context.warp_to_slot(slot + 2).unwrap();
+    }
+
+    /// Drops SlotHashes entries up to and including `slot` from the working
+    /// bank, as if the sysvar had since filled up with newer slots.
+    pub async fn evict_slot_hashes(&mut self, slot: u64) {
+        let account = self
+            .context
+            .banks_client
+            .get_account_with_commitment(sysvar::slot_hashes::id(), CommitmentLevel::Processed)
+            .await
+            .unwrap()
+            .unwrap();
+        let newer: Vec<_> = from_account::<SlotHashes, _>(&account)
+            .unwrap()
+            .iter()
+            .filter(|(entry_slot, _)| *entry_slot > slot)
+            .copied()
+            .collect();
+        assert!(!newer.is_empty(), "no slot has been hashed after {}", slot);
+        self.context.set_sysvar(&SlotHashes::new(&newer));
+    }
+
+    pub async fn account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+    }
+
+    /// Overwrites the links of `list_item`, the way a corrupted list would
+    /// have them.
+    pub async fn set_list_links(
+        &mut self,
+        list_item: &Pubkey,
+        prev_list_item: &Pubkey,
+        next_list_item: &Pubkey,
+    ) {
+        let mut account = self.account(list_item).await.unwrap();
+        let nft_bucket: &mut NftBucket =
+            bytemuck::from_bytes_mut(&mut account.data[8..NftBucket::LEN]);
+        nft_bucket.prev_list_item = *prev_list_item;
+        nft_bucket.next_list_item = *next_list_item;
+        self.context
+            .set_account(list_item, &AccountSharedData::from(account));
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.account(address)
+            .await
+            .map(|account| account.lamports)
+            .unwrap_or(0)
+    }
+
+    pub async fn decode<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self.account(address).await.expect("account not found");
+        decode_account(&account.data).unwrap()
+    }
+
+    pub async fn decode_zero_copy<T: ZeroCopy>(&mut self, address: &Pubkey) -> T {
+        let account = self.account(address).await.expect("account not found");
+        decode_zero_copy(&account.data).unwrap()
+    }
+
+    pub async fn global_state(&mut self) -> GlobalState {
+        self.decode(&find_global_state_address().0).await
+    }
+
+    pub async fn market(&mut self, address: &Pubkey) -> Market {
+        self.decode_zero_copy(address).await
+    }
+
+    pub async fn collection(&mut self, address: &Pubkey) -> Collection {
+        self.decode(address).await
+    }
+
+    pub async fn nft_bucket(&mut self, address: &Pubkey) -> NftBucket {
+        self.decode_zero_copy(address).await
+    }
+
+    pub async fn price_model(&mut self, address: &Pubkey) -> PriceModel {
+        self.decode_zero_copy(address).await
+    }
+
+    pub async fn inventory_page(&mut self, address: &Pubkey) -> InventoryPage {
+        self.decode_zero_copy(address).await
+    }
+
+    /// Walks the list of `collection` with `walk_list`, so a corrupt list
+    /// fails with `ClientError::BrokenList`.
+    pub async fn list(&mut self, collection: &Collection) -> Result<Vec<ListItem>, ClientError> {
+        // walk_list loads synchronously, so buckets are fetched up front by
+        // following next pointers from the head
+        let mut buckets = HashMap::new();
+        let mut next = Some(collection.list_head);
+        while let Some(address) = next {
+            let bucket = match self.account(&address).await {
+                Some(account) => match decode_zero_copy::<NftBucket>(&account.data) {
+                    Ok(bucket) => bucket,
+                    Err(_) => break,
+                },
+                None => break,
+            };
+            buckets.insert(address, bucket);
+            next = Some(bucket.next_list_item)
+                .filter(|next| address != collection.list_tail && !buckets.contains_key(next));
+        }
+        if let Entry::Vacant(entry) = buckets.entry(collection.list_tail) {
+            if let Some(account) = self.account(&collection.list_tail).await {
+                if let Ok(bucket) = decode_zero_copy::<NftBucket>(&account.data) {
+                    entry.insert(bucket);
+                }
+            }
+        }
+
+        walk_list(collection, |address| {
+            buckets
+                .get(address)
+                .copied()
+                .ok_or(ClientError::BrokenList(*address))
+        })
+    }
+
+    /// Token balance of `address`, or `None` if the account does not exist.
+    pub async fn token_balance(&mut self, address: &Pubkey) -> Option<u64> {
+        self.account(address).await.map(|account| {
+            spl_token::state::Account::unpack(&account.data)
+                .unwrap()
+                .amount
+        })
+    }
+
+    pub async fn funded_keypair(&mut self) -> Keypair {
+        let keypair = Keypair::new();
+        let payer = self.payer();
+        self.process(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &keypair.pubkey(),
+                10 * SOL,
+            )],
+            &[&payer],
+        )
+        .await
+        .unwrap();
+        keypair
+    }
+
+    /// Creates a mint with no decimals, controlled by the admin.
+    pub async fn create_mint(&mut self) -> Pubkey {
+        let admin = self.admin();
+        let mint = Keypair::new();
+        let space = spl_token::state::Mint::LEN;
+        let lamports = self.rent().await.minimum_balance(space);
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &admin.pubkey(),
+                    &mint.pubkey(),
+                    lamports,
+                    space as u64,
+                    &spl_token::id(),
+                ),
+                spl_token::instruction::initialize_mint(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    &admin.pubkey(),
+                    None,
+                    0,
+                )
+                .unwrap(),
+            ],
+            &[&admin, &mint],
+        )
+        .await
+        .unwrap();
+        mint.pubkey()
+    }
+
+    /// Mints `amount` to the associated token account of `owner`, creating it
+    /// if needed, and returns the token account.
+    pub async fn mint_to(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let admin = self.admin();
+        let token_account = get_associated_token_address(owner, mint);
+        let mut instructions = Vec::new();
+        if self.account(&token_account).await.is_none() {
+            instructions.push(create_associated_token_account(
+                &admin.pubkey(),
+                owner,
+                mint,
+            ));
+        }
+        instructions.push(
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint,
+                &token_account,
+                &admin.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        );
+        self.process(&instructions, &[&admin]).await.unwrap();
+        token_account
+    }
+
+    /// A funded buyer holding `amount` of `mint`, and its token account.
+    pub async fn buyer(&mut self, mint: &Pubkey, amount: u64) -> (Keypair, Pubkey) {
+        let buyer = self.funded_keypair().await;
+        let debit_account = self.mint_to(mint, &buyer.pubkey(), amount).await;
+        (buyer, debit_account)
+    }
+
+    pub async fn init_global_state(&mut self) {
+        let admin = self.admin();
+        self.process(&[ix::init_global_state(&admin.pubkey())], &[&admin])
+            .await
+            .unwrap();
+    }
+
+    /// Creates the next market with the admin as its only admin.
+    pub async fn init_market(&mut self) -> Pubkey {
+        let admin = self.admin();
+        let global_state = self.global_state().await;
+        let market =
+            find_market_address(&find_global_state_address().0, global_state.num_markets).0;
+        self.process(
+            &[ix::init_market(
+                &admin.pubkey(),
+                &global_state,
+                vec![admin.pubkey()],
+                "Market".to_string(),
+            )],
+            &[&admin],
+        )
+        .await
+        .unwrap();
+        market
+    }
+
+    /// Creates the next collection of `market` with one inventory page.
+    pub async fn init_collection(&mut self, market: &Pubkey) -> Pubkey {
+        let admin = self.admin();
+        let market_state = self.market(market).await;
+        let collection = find_collection_address(market, market_state.num_collections).0;
+        self.process(
+            &[ix::init_collection(
+                &admin.pubkey(),
+                market,
+                &market_state,
+                "Collection".to_string(),
+            )],
+            &[&admin],
+        )
+        .await
+        .unwrap();
+        self.init_inventory_page(market, &collection).await;
+        collection
+    }
+
+    pub async fn init_inventory_page(&mut self, market: &Pubkey, collection: &Pubkey) -> Pubkey {
+        let admin = self.admin();
+        let collection_state = self.collection(collection).await;
+        let inventory_page =
+            find_inventory_page_address(collection, collection_state.num_inventory_pages).0;
+        self.process(
+            &[ix::init_inventory_page(
+                &admin.pubkey(),
+                market,
+                collection,
+                &collection_state,
+            )],
+            &[&admin],
+        )
+        .await
+        .unwrap();
+        inventory_page
+    }
+
+    pub async fn init_price_model(
+        &mut self,
+        market: &Pubkey,
+        sale_prices: Vec<SalePrice>,
+    ) -> Pubkey {
+        let admin = self.admin();
+        let market_state = self.market(market).await;
+        let price_model = find_price_model_address(market, market_state.num_price_models).0;
+        self.process(
+            &[ix::init_price_model(
+                &admin.pubkey(),
+                market,
+                &market_state,
+                sale_prices,
+            )],
+            &[&admin],
+        )
+        .await
+        .unwrap();
+        price_model
+    }
+
+    /// Mints a new NFT to the admin and lists it at the front of `collection`
+    /// on its first inventory page.
+    pub async fn list_nft(
+        &mut self,
+        market: &Pubkey,
+        collection: &Pubkey,
+        price_model: &Pubkey,
+    ) -> (Pubkey, Pubkey) {
+        let admin = self.admin();
+        let nft_mint = self.create_mint().await;
+        let admin_nft_account = self.mint_to(&nft_mint, &admin.pubkey(), 1).await;
+        let args = self
+            .list_nft_args(
+                market,
+                collection,
+                price_model,
+                &nft_mint,
+                &admin_nft_account,
+            )
+            .await;
+        self.process(&[ix::add_nft(&args)], &[&admin])
+            .await
+            .unwrap();
+        (nft_mint, find_nft_bucket_address(collection, &nft_mint).0)
+    }
+
+    /// Accounts listing `nft_mint` at the front of `collection`.
+    pub async fn list_nft_args(
+        &mut self,
+        market: &Pubkey,
+        collection: &Pubkey,
+        price_model: &Pubkey,
+        nft_mint: &Pubkey,
+        admin_nft_account: &Pubkey,
+    ) -> ListNft {
+        let list_head = self.collection(collection).await.list_head;
+        let next_list_item = self.nft_bucket(&list_head).await.next_list_item;
+        ListNft {
+            admin: self.admin.pubkey(),
+            market: *market,
+            collection: *collection,
+            prev_list_item: list_head,
+            next_list_item,
+            nft_mint: *nft_mint,
+            admin_nft_account: *admin_nft_account,
+            inventory_page: find_inventory_page_address(collection, 0).0,
+            price_model: *price_model,
+        }
+    }
+
+    /// Global state, market, collection and price model with one NFT listed.
+    pub async fn listing(&mut self) -> Listing {
+        self.init_global_state().await;
+        let market = self.init_market().await;
+        let collection = self.init_collection(&market).await;
+        let payment_mint = self.create_mint().await;
+        let price_model = self
+            .init_price_model(
+                &market,
+                vec![SalePrice {
+                    mint: payment_mint,
+                    amount: PRICE,
+                }],
+            )
+            .await;
+        let (nft_mint, nft_bucket) = self.list_nft(&market, &collection, &price_model).await;
+        Listing {
+            market,
+            collection,
+            inventory_page: find_inventory_page_address(&collection, 0).0,
+            price_model,
+            payment_mint,
+            nft_mint,
+            nft_bucket,
+        }
+    }
+
+    /// Buys `nft_bucket` of `listing` as `buyer`, paying from `debit_account`.
+    pub async fn purchase(
+        &mut self,
+        listing: &Listing,
+        nft_bucket: &Pubkey,
+        buyer: &Keypair,
+        debit_mint: &Pubkey,
+        debit_account: &Pubkey,
+    ) -> Result<(), TransportError> {
+        let global_state = self.global_state().await;
+        let nft_bucket_state = self.nft_bucket(nft_bucket).await;
+        self.process(
+            &[ix::purchase_nft(
+                &buyer.pubkey(),
+                &global_state,
+                &listing.market,
+                &listing.collection,
+                nft_bucket,
+                &nft_bucket_state,
+                debit_mint,
+                debit_account,
+            )],
+            &[buyer],
+        )
+        .await
+    }
+}
@@ -0,0 +1,520 @@
+//! Random sequences of list operations run against a model of the collection.
+//!
+//! After every step the on-chain list must match the model, every listed mint
+//! must sit in the collection ATA, closed accounts must refund their rent to
+//! the bucket payer and every token must be accounted for. Set
+//! `VIBE_FUZZ_SEED` to replay a single seed and `VIBE_FUZZ_STEPS` to change
+//! the sequence length.
+
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use std::collections::HashMap;
+use std::env;
+
+use common::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+use vibe_market::{ErrorCode, SalePrice};
+use vibe_market_client::instruction as ix;
+use vibe_market_client::pda::{find_inventory_page_address, find_nft_bucket_address};
+
+const DEFAULT_SEEDS: [u64; 3] = [1, 2, 3];
+const DEFAULT_STEPS: usize = 40;
+// Upper bound on the fees paid by an admin signed transaction
+const MAX_FEE: u64 = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Holder {
+    Collection(Pubkey),
+    Admin,
+    Buyer(Pubkey),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Item {
+    bucket: Pubkey,
+    nft_mint: Pubkey,
+    tombstoned: bool,
+}
+
+#[derive(Debug)]
+enum Op {
+    // Lists a new NFT after the item at the index, or at the front
+    Add(Option<usize>),
+    Withdraw(usize),
+    Purchase(usize),
+    Compact(usize),
+    CloseCollection,
+}
+
+struct Model {
+    market: Pubkey,
+    collection: Pubkey,
+    list_head: Pubkey,
+    list_tail: Pubkey,
+    price_model: Pubkey,
+    payment_mint: Pubkey,
+    // Linked items between the head and tail, in list order
+    items: Vec<Item>,
+    holders: HashMap<Pubkey, Holder>,
+    // Every collection opened, which may hold sale proceeds
+    collections: Vec<Pubkey>,
+    debit_accounts: Vec<Pubkey>,
+    payment_minted: u64,
+}
+
+impl Model {
+    fn choose(&self, rng: &mut StdRng) -> Op {
+        let tombstoned: Vec<usize> = (0..self.items.len())
+            .filter(|index| self.items[*index].tombstoned)
+            .collect();
+        match rng.gen_range(0, 10) {
+            0..=3 => {
+                if self.items.is_empty() || rng.gen_bool(0.5) {
+                    Op::Add(None)
+                } else {
+                    Op::Add(Some(rng.gen_range(0, self.items.len())))
+                }
+            }
+            // Tombstoned items are picked too, which must fail
+            4 | 5 if !self.items.is_empty() => Op::Purchase(rng.gen_range(0, self.items.len())),
+            6 if !self.items.is_empty() => Op::Withdraw(rng.gen_range(0, self.items.len())),
+            7 | 8 if !tombstoned.is_empty() => {
+                Op::Compact(tombstoned[rng.gen_range(0, tombstoned.len())])
+            }
+            9 => Op::CloseCollection,
+            _ => Op::Add(None),
+        }
+    }
+
+    fn num_listed(&self) -> u32 {
+        self.items.iter().filter(|item| !item.tombstoned).count() as u32
+    }
+}
+
+async fn open_collection(test: &mut Test, model: &mut Model) {
+    let collection = test.init_collection(&model.market).await;
+    let collection_state = test.collection(&collection).await;
+    model.collection = collection;
+    model.list_head = collection_state.list_head;
+    model.list_tail = collection_state.list_tail;
+    model.collections.push(collection);
+}
+
+async fn apply(test: &mut Test, model: &mut Model, cranker: &Keypair, op: &Op, context: &str) {
+    let admin = test.admin();
+    match *op {
+        Op::Add(after) => {
+            let nft_mint = test.create_mint().await;
+            let admin_nft_account = test.mint_to(&nft_mint, &admin.pubkey(), 1).await;
+            let mut args = test
+                .list_nft_args(
+                    &model.market,
+                    &model.collection,
+                    &model.price_model,
+                    &nft_mint,
+                    &admin_nft_account,
+                )
+                .await;
+            let instruction = match after {
+                None => ix::add_nft(&args),
+                Some(index) => {
+                    args.prev_list_item = model.items[index].bucket;
+                    args.next_list_item = model
+                        .items
+                        .get(index + 1)
+                        .map(|item| item.bucket)
+                        .unwrap_or(model.list_tail);
+                    ix::insert_nft_after(&args)
+                }
+            };
+            test.process(&[instruction], &[&admin])
+                .await
+                .unwrap_or_else(|err| panic!("{}: {:?}", context, err));
+
+            model.items.insert(
+                after.map_or(0, |index| index + 1),
+                Item {
+                    bucket: find_nft_bucket_address(&model.collection, &nft_mint).0,
+                    nft_mint,
+                    tombstoned: false,
+                },
+            );
+            model
+                .holders
+                .insert(nft_mint, Holder::Collection(model.collection));
+        }
+        Op::Withdraw(index) => {
+            let item = model.items[index];
+            let bucket = test.nft_bucket(&item.bucket).await;
+            let refund = test
+                .lamports(&get_associated_token_address(
+                    &model.collection,
+                    &item.nft_mint,
+                ))
+                .await;
+            let admin_lamports = test.lamports(&admin.pubkey()).await;
+
+            // The cranker pays the fee so the admin balance only moves by the refund
+            let result = test
+                .process(
+                    &[ix::withdraw_nft(
+                        &admin.pubkey(),
+                        &model.market,
+                        &model.collection,
+                        &item.bucket,
+                        &bucket,
+                    )],
+                    &[cranker, &admin],
+                )
+                .await;
+            if item.tombstoned {
+                assert!(result.is_err(), "{}: withdrew a tombstoned item", context);
+                return;
+            }
+            result.unwrap_or_else(|err| panic!("{}: {:?}", context, err));
+            assert_eq!(
+                test.lamports(&admin.pubkey()).await,
+                admin_lamports + refund,
+                "{}: NFT account rent refunded to payer",
+                context
+            );
+
+            model.items[index].tombstoned = true;
+            model.holders.insert(item.nft_mint, Holder::Admin);
+        }
+        Op::Purchase(index) => {
+            let item = model.items[index];
+            let (buyer, debit_account) = test.buyer(&model.payment_mint, PRICE).await;
+            model.payment_minted += PRICE;
+            model.debit_accounts.push(debit_account);
+
+            let global_state = test.global_state().await;
+            let bucket = test.nft_bucket(&item.bucket).await;
+            let refund = test
+                .lamports(&get_associated_token_address(
+                    &model.collection,
+                    &item.nft_mint,
+                ))
+                .await;
+            let admin_lamports = test.lamports(&admin.pubkey()).await;
+            let result = test
+                .process(
+                    &[ix::purchase_nft(
+                        &buyer.pubkey(),
+                        &global_state,
+                        &model.market,
+                        &model.collection,
+                        &item.bucket,
+                        &bucket,
+                        &model.payment_mint,
+                        &debit_account,
+                    )],
+                    &[&buyer],
+                )
+                .await;
+            if item.tombstoned {
+                assert!(result.is_err(), "{}: bought a tombstoned item", context);
+                return;
+            }
+            result.unwrap_or_else(|err| panic!("{}: {:?}", context, err));
+            assert_eq!(
+                test.lamports(&admin.pubkey()).await,
+                admin_lamports + refund,
+                "{}: NFT account rent refunded to payer",
+                context
+            );
+
+            model.items[index].tombstoned = true;
+            model
+                .holders
+                .insert(item.nft_mint, Holder::Buyer(buyer.pubkey()));
+        }
+        Op::Compact(index) => {
+            let item = model.items[index];
+            let bucket = test.nft_bucket(&item.bucket).await;
+            let refund = test.lamports(&item.bucket).await;
+            let admin_lamports = test.lamports(&admin.pubkey()).await;
+            test.process(&[ix::compact_list_item(&item.bucket, &bucket)], &[cranker])
+                .await
+                .unwrap_or_else(|err| panic!("{}: {:?}", context, err));
+            assert_eq!(
+                test.lamports(&admin.pubkey()).await,
+                admin_lamports + refund,
+                "{}: bucket rent refunded to payer",
+                context
+            );
+            assert!(
+                test.account(&item.bucket).await.is_none(),
+                "{}: compacted bucket still open",
+                context
+            );
+
+            model.items.remove(index);
+        }
+        Op::CloseCollection => {
+            let inventory_page = find_inventory_page_address(&model.collection, 0).0;
+            let treasury = get_associated_token_address(&model.collection, &model.payment_mint);
+            let proceeds = test.token_balance(&treasury).await;
+
+            // Sale proceeds are withdrawn so the treasury can be closed too
+            let mut instructions = vec![ix::close_inventory_page(
+                &admin.pubkey(),
+                &model.market,
+                &model.collection,
+                0,
+            )];
+            let mut treasury_accounts = Vec::new();
+            if let Some(amount) = proceeds {
+                if amount > 0 {
+                    instructions.push(ix::withdraw_collection_liquidity(
+                        &admin.pubkey(),
+                        &model.market,
+                        &model.collection,
+                        &model.payment_mint,
+                        amount,
+                    ));
+                }
+                treasury_accounts.push(treasury);
+            }
+            instructions.push(ix::close_collection(
+                &admin.pubkey(),
+                &model.market,
+                &model.collection,
+                &admin.pubkey(),
+                &treasury_accounts,
+            ));
+
+            let mut refund = 0;
+            for closed in [
+                model.collection,
+                model.list_head,
+                model.list_tail,
+                inventory_page,
+                treasury,
+            ]
+            .iter()
+            {
+                refund += test.lamports(closed).await;
+            }
+            let admin_lamports = test.lamports(&admin.pubkey()).await;
+
+            let result = test.process(&instructions, &[&admin]).await;
+            if model.num_listed() > 0 {
+                assert_eq!(
+                    custom_error(result),
+                    error_code(ErrorCode::InventoryPageNonEmpty),
+                    "{}",
+                    context
+                );
+                return;
+            }
+            if !model.items.is_empty() {
+                assert_eq!(
+                    custom_error(result),
+                    error_code(ErrorCode::CollectionNonEmpty),
+                    "{}",
+                    context
+                );
+                return;
+            }
+            result.unwrap_or_else(|err| panic!("{}: {:?}", context, err));
+
+            // The admin paid the fee, so the refund is only known to within it
+            let admin_lamports_after = test.lamports(&admin.pubkey()).await;
+            assert!(
+                admin_lamports_after <= admin_lamports + refund
+                    && admin_lamports_after + MAX_FEE > admin_lamports + refund,
+                "{}: collection rent refunded to payer",
+                context
+            );
+            for closed in [model.collection, model.list_head, model.list_tail, treasury].iter() {
+                assert!(
+                    test.account(closed).await.is_none(),
+                    "{}: {} still open",
+                    context,
+                    closed
+                );
+            }
+
+            open_collection(test, model).await;
+        }
+    }
+}
+
+async fn check(test: &mut Test, model: &Model, context: &str) {
+    let collection = test.collection(&model.collection).await;
+    let list = test
+        .list(&collection)
+        .await
+        .unwrap_or_else(|err| panic!("{}: inconsistent list: {:?}", context, err));
+    let actual: Vec<(Pubkey, Pubkey, bool)> = list
+        .iter()
+        .map(|item| (item.address, item.bucket.nft_mint, item.is_tombstoned()))
+        .collect();
+    let expected: Vec<(Pubkey, Pubkey, bool)> = model
+        .items
+        .iter()
+        .map(|item| (item.bucket, item.nft_mint, item.tombstoned))
+        .collect();
+    assert_eq!(actual, expected, "{}: list differs from model", context);
+
+    // Listed buckets own their inventory slot, and the counters agree
+    let inventory_page = test
+        .inventory_page(&find_inventory_page_address(&model.collection, 0).0)
+        .await;
+    for item in list.iter().filter(|item| !item.is_tombstoned()) {
+        let slot = inventory_page.slots[item.bucket.inventory_slot as usize];
+        assert_eq!(slot.nft_bucket, item.address, "{}: inventory slot", context);
+    }
+    let num_occupied = inventory_page.num_occupied;
+    let num_listings = test.price_model(&model.price_model).await.num_listings;
+    let num_listed = collection.stats.num_listed;
+    assert_eq!(
+        (num_occupied, num_listings, num_listed),
+        (model.num_listed(), model.num_listed(), model.num_listed()),
+        "{}: listing counters",
+        context
+    );
+
+    // Each NFT is held by exactly its holder
+    let admin = test.admin.pubkey();
+    for (nft_mint, holder) in model.holders.iter() {
+        let mut balances = Vec::new();
+        for collection in model.collections.iter() {
+            let expected = Some(1).filter(|_| *holder == Holder::Collection(*collection));
+            let balance = test
+                .token_balance(&get_associated_token_address(collection, nft_mint))
+                .await;
+            assert_eq!(
+                balance, expected,
+                "{}: {} in collection {}",
+                context, nft_mint, collection
+            );
+            balances.push(balance.unwrap_or(0));
+        }
+        let admin_balance = test
+            .token_balance(&get_associated_token_address(&admin, nft_mint))
+            .await;
+        assert_eq!(
+            admin_balance,
+            Some((*holder == Holder::Admin) as u64),
+            "{}: {} held by admin",
+            context,
+            nft_mint
+        );
+        balances.push(admin_balance.unwrap_or(0));
+        if let Holder::Buyer(buyer) = holder {
+            let buyer_balance = test
+                .token_balance(&get_associated_token_address(buyer, nft_mint))
+                .await;
+            assert_eq!(
+                buyer_balance,
+                Some(1),
+                "{}: {} held by buyer",
+                context,
+                nft_mint
+            );
+            balances.push(1);
+        }
+        assert_eq!(
+            balances.iter().sum::<u64>(),
+            1,
+            "{}: {} lost",
+            context,
+            nft_mint
+        );
+    }
+
+    // Payment tokens are either unspent, collection proceeds or protocol fees
+    let mut payment_total = test
+        .token_balance(&get_associated_token_address(&admin, &model.payment_mint))
+        .await
+        .unwrap_or(0);
+    for debit_account in model.debit_accounts.iter() {
+        payment_total += test.token_balance(debit_account).await.unwrap_or(0);
+    }
+    for collection in model.collections.iter() {
+        payment_total += test
+            .token_balance(&get_associated_token_address(
+                collection,
+                &model.payment_mint,
+            ))
+            .await
+            .unwrap_or(0);
+    }
+    assert_eq!(
+        payment_total, model.payment_minted,
+        "{}: payment tokens lost",
+        context
+    );
+}
+
+async fn run(seed: u64, steps: usize) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut test = Test::start().await;
+    let cranker = test.funded_keypair().await;
+
+    test.init_global_state().await;
+    let market = test.init_market().await;
+    let payment_mint = test.create_mint().await;
+    // Fees and withdrawn proceeds are credited here, so create it up front to
+    // keep its rent out of the refund checks
+    let admin = test.admin.pubkey();
+    test.mint_to(&payment_mint, &admin, 0).await;
+    let price_model = test
+        .init_price_model(
+            &market,
+            vec![SalePrice {
+                mint: payment_mint,
+                amount: PRICE,
+            }],
+        )
+        .await;
+    let mut model = Model {
+        market,
+        collection: Pubkey::default(),
+        list_head: Pubkey::default(),
+        list_tail: Pubkey::default(),
+        price_model,
+        payment_mint,
+        items: Vec::new(),
+        holders: HashMap::new(),
+        collections: Vec::new(),
+        debit_accounts: Vec::new(),
+        payment_minted: 0,
+    };
+    open_collection(&mut test, &mut model).await;
+
+    for step in 0..steps {
+        let op = model.choose(&mut rng);
+        let context = format!("seed {} step {} {:?}", seed, step, op);
+        apply(&mut test, &mut model, &cranker, &op, &context).await;
+        check(&mut test, &model, &context).await;
+    }
+}
+
+fn env_or<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok().map(|value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid {}: {}", name, value))
+    })
+}
+
+#[tokio::test]
+async fn list_invariants_hold_for_random_sequences() {
+    let steps = env_or("VIBE_FUZZ_STEPS").unwrap_or(DEFAULT_STEPS);
+    let seeds = match env_or("VIBE_FUZZ_SEED") {
+        Some(seed) => vec![seed],
+        None => DEFAULT_SEEDS.to_vec(),
+    };
+    for seed in seeds {
+        run(seed, steps).await;
+    }
+}
@@ -0,0 +1,2665 @@
+//! Instruction coverage and adversarial cases, run with `cargo test-bpf`.
+
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use anchor_lang::prelude::{borsh, AnchorSerialize};
+use common::*;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use spl_associated_token_account::get_associated_token_address;
+use vibe_market::{
+    Collection, ErrorCode, GlobalState, Market, MarketCreation, Metadata, MysteryCommit, NftBucket,
+    PriceModel, SalePrice,
+};
+use vibe_market_client::instruction::{self as ix};
+use vibe_market_client::pda::*;
+
+async fn token_account_rent(test: &mut Test) -> u64 {
+    test.rent()
+        .await
+        .minimum_balance(spl_token::state::Account::LEN)
+}
+
+async fn num_listings(test: &mut Test, price_model: &Pubkey) -> u32 {
+    // Copied out of the packed account before comparing
+    let num_listings = test.price_model(price_model).await.num_listings;
+    num_listings
+}
+
+#[tokio::test]
+async fn init_global_state_requires_upgrade_authority() {
+    let mut test = Test::start().await;
+    let stranger = test.funded_keypair().await;
+
+    let result = test
+        .process(&[ix::init_global_state(&stranger.pubkey())], &[&stranger])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    test.init_global_state().await;
+    let global_state = test.global_state().await;
+    assert_eq!(global_state.authority, test.admin.pubkey());
+    assert_eq!(global_state.fee_recipient, test.admin.pubkey());
+    assert_eq!(global_state.num_markets, 0);
+}
+
+#[tokio::test]
+async fn protocol_fee_and_market_creation_policy() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let stranger = test.funded_keypair().await;
+    let fee_recipient = Pubkey::new_unique();
+    test.init_global_state().await;
+
+    let result = test
+        .process(
+            &[ix::update_protocol_fee(
+                &stranger.pubkey(),
+                &stranger.pubkey(),
+                100,
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    let result = test
+        .process(
+            &[ix::update_protocol_fee(
+                &admin.pubkey(),
+                &fee_recipient,
+                10_001,
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InvalidFeeBasisPoints)
+    );
+
+    test.process(
+        &[ix::update_protocol_fee(
+            &admin.pubkey(),
+            &fee_recipient,
+            250,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let global_state = test.global_state().await;
+    assert_eq!(global_state.fee_basis_points, 250);
+    assert_eq!(global_state.fee_recipient, fee_recipient);
+    // Fee updates leave the authority alone
+    assert_eq!(global_state.authority, admin.pubkey());
+
+    // Markets are permissioned by default
+    let result = test
+        .process(
+            &[ix::init_market(
+                &stranger.pubkey(),
+                &global_state,
+                vec![stranger.pubkey()],
+                "Market".to_string(),
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    let result = test
+        .process(
+            &[ix::update_market_creation(
+                &stranger.pubkey(),
+                MarketCreation::Open,
+                0,
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    test.process(
+        &[ix::update_market_creation(
+            &admin.pubkey(),
+            MarketCreation::FeeGated,
+            SOL,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let global_state = test.global_state().await;
+    test.process(
+        &[ix::init_market(
+            &stranger.pubkey(),
+            &global_state,
+            vec![stranger.pubkey()],
+            "Market".to_string(),
+        )],
+        &[&stranger],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.lamports(&fee_recipient).await, SOL);
+
+    let market = test
+        .market(&find_market_address(&find_global_state_address().0, 0).0)
+        .await;
+    assert_eq!(market.whitelist(), &[stranger.pubkey()]);
+    assert_eq!(test.global_state().await.num_markets, 1);
+
+    let result = test
+        .process(
+            &[ix::set_global_authority(&stranger.pubkey(), &stranger.pubkey())],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    test.process(
+        &[ix::set_global_authority(&admin.pubkey(), &stranger.pubkey())],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.global_state().await.authority, stranger.pubkey());
+}
+
+#[tokio::test]
+async fn init_market_validates_whitelist() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    test.init_global_state().await;
+    let global_state = test.global_state().await;
+
+    let result = test
+        .process(
+            &[ix::init_market(
+                &admin.pubkey(),
+                &global_state,
+                vec![Pubkey::new_unique()],
+                "Market".to_string(),
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::AdminNotFound));
+
+    let mut whitelist: Vec<Pubkey> = (0..16).map(|_| Pubkey::new_unique()).collect();
+    whitelist.push(admin.pubkey());
+    let result = test
+        .process(
+            &[ix::init_market(
+                &admin.pubkey(),
+                &global_state,
+                whitelist,
+                "Market".to_string(),
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::AdminOutOfBounds)
+    );
+}
+
+#[tokio::test]
+async fn market_admins() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let second_admin = test.funded_keypair().await;
+    let stranger = test.funded_keypair().await;
+    test.init_global_state().await;
+    let market = test.init_market().await;
+
+    let result = test
+        .process(
+            &[ix::add_admin(
+                &stranger.pubkey(),
+                &market,
+                &stranger.pubkey(),
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    // The only admin cannot remove itself, which would orphan the market
+    let result = test
+        .process(
+            &[ix::remove_admin(&admin.pubkey(), &market, &admin.pubkey())],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::CannotRemoveSelf)
+    );
+
+    let result = test
+        .process(
+            &[ix::remove_admin(
+                &admin.pubkey(),
+                &market,
+                &stranger.pubkey(),
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::AdminNotFound));
+
+    test.process(
+        &[ix::add_admin(
+            &admin.pubkey(),
+            &market,
+            &second_admin.pubkey(),
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.market(&market).await.whitelist(),
+        &[admin.pubkey(), second_admin.pubkey()]
+    );
+
+    let result = test
+        .process(
+            &[ix::remove_admin(
+                &stranger.pubkey(),
+                &market,
+                &admin.pubkey(),
+            )],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    test.process(
+        &[ix::remove_admin(
+            &second_admin.pubkey(),
+            &market,
+            &admin.pubkey(),
+        )],
+        &[&second_admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.market(&market).await.whitelist(),
+        &[second_admin.pubkey()]
+    );
+
+    // The removed admin has lost access
+    let result = test
+        .process(
+            &[ix::set_market_paused(&admin.pubkey(), &market, true)],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    let result = test
+        .process(
+            &[ix::remove_admin(
+                &second_admin.pubkey(),
+                &market,
+                &second_admin.pubkey(),
+            )],
+            &[&second_admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::CannotRemoveSelf)
+    );
+}
+
+#[tokio::test]
+async fn market_and_collection_settings() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    test.init_global_state().await;
+    let market = test.init_market().await;
+    let collection = test.init_collection(&market).await;
+
+    test.process(
+        &[ix::set_market_paused(&admin.pubkey(), &market, true)],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.market(&market).await.paused, 1);
+
+    let metadata = Metadata {
+        description: "A market".to_string(),
+        image_uri: "https://example.com/market.png".to_string(),
+        external_url: "https://example.com".to_string(),
+        sort_priority: 3,
+    };
+    test.process(
+        &[ix::update_market_metadata(
+            &admin.pubkey(),
+            &market,
+            "Renamed".to_string(),
+            metadata.clone(),
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let market_state = test.market(&market).await;
+    assert_eq!(
+        &market_state.title[..market_state.title_len as usize],
+        b"Renamed"
+    );
+    let sort_priority = market_state.metadata.sort_priority;
+    assert_eq!(sort_priority, 3);
+
+    let result = test
+        .process(
+            &[ix::update_market_metadata(
+                &admin.pubkey(),
+                &market,
+                "x".repeat(33),
+                Metadata::default(),
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::TitleTooLong));
+
+    // Longer metadata grows the collection account
+    let collection_len = test.account(&collection).await.unwrap().data.len();
+    let metadata = Metadata {
+        description: "d".repeat(256),
+        ..metadata
+    };
+    test.process(
+        &[ix::update_collection_metadata(
+            &admin.pubkey(),
+            &market,
+            &collection,
+            "Renamed".to_string(),
+            metadata,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let collection_state = test.collection(&collection).await;
+    assert_eq!(collection_state.title, "Renamed");
+    assert_eq!(collection_state.metadata.description.len(), 256);
+    assert!(test.account(&collection).await.unwrap().data.len() > collection_len);
+
+    test.process(
+        &[
+            ix::set_collection_paused(&admin.pubkey(), &market, &collection, true),
+            ix::set_collection_sale_window(
+                &admin.pubkey(),
+                &market,
+                &collection,
+                Some(100),
+                Some(200),
+            ),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let collection_state = test.collection(&collection).await;
+    assert!(collection_state.paused);
+    assert_eq!(collection_state.sale_start, Some(100));
+    assert_eq!(collection_state.sale_end, Some(200));
+
+    let result = test
+        .process(
+            &[ix::set_collection_sale_window(
+                &admin.pubkey(),
+                &market,
+                &collection,
+                Some(200),
+                Some(100),
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InvalidSaleWindow)
+    );
+
+    // Pages close from the end, and only when empty
+    let second_page = test.init_inventory_page(&market, &collection).await;
+    let result = test
+        .process(
+            &[ix::close_inventory_page(
+                &admin.pubkey(),
+                &market,
+                &collection,
+                0,
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InvalidInventoryPage)
+    );
+    test.process(
+        &[ix::close_inventory_page(
+            &admin.pubkey(),
+            &market,
+            &collection,
+            1,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert!(test.account(&second_page).await.is_none());
+    assert_eq!(test.collection(&collection).await.num_inventory_pages, 1);
+}
+
+#[tokio::test]
+async fn list_move_and_purchase() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let fee_recipient = Pubkey::new_unique();
+    test.process(
+        &[ix::update_protocol_fee(
+            &admin.pubkey(),
+            &fee_recipient,
+            250,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    // A second NFT goes after the first, then moves back to the front
+    let nft_mint = test.create_mint().await;
+    let admin_nft_account = test.mint_to(&nft_mint, &admin.pubkey(), 1).await;
+    let mut args = test
+        .list_nft_args(
+            &listing.market,
+            &listing.collection,
+            &listing.price_model,
+            &nft_mint,
+            &admin_nft_account,
+        )
+        .await;
+    args.prev_list_item = listing.nft_bucket;
+    args.next_list_item = test.nft_bucket(&listing.nft_bucket).await.next_list_item;
+    test.process(&[ix::insert_nft_after(&args)], &[&admin])
+        .await
+        .unwrap();
+    let second_bucket = find_nft_bucket_address(&listing.collection, &nft_mint).0;
+
+    let collection = test.collection(&listing.collection).await;
+    let second_bucket_state = test.nft_bucket(&second_bucket).await;
+    test.process(
+        &[ix::move_item(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &second_bucket,
+            &second_bucket_state,
+            &collection.list_head,
+            &listing.nft_bucket,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        list_order(&mut test, &collection).await,
+        vec![second_bucket, listing.nft_bucket]
+    );
+    assert_eq!(num_listings(&mut test, &listing.price_model).await, 2);
+    let num_occupied = test
+        .inventory_page(&listing.inventory_page)
+        .await
+        .num_occupied;
+    assert_eq!(num_occupied, 2);
+
+    // The fee is paid into the fee recipient's account for the mint
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.mint_to(&listing.payment_mint, &fee_recipient, 0).await;
+
+    let program_nft_account = get_associated_token_address(&listing.collection, &listing.nft_mint);
+    let admin_lamports = test.lamports(&admin.pubkey()).await;
+    test.purchase(
+        &listing,
+        &listing.nft_bucket,
+        &buyer,
+        &listing.payment_mint,
+        &debit_account,
+    )
+    .await
+    .unwrap();
+
+    // The protocol fee is taken from the sale price, the rest goes to the collection
+    let fee = PRICE * 250 / 10_000;
+    assert_eq!(test.token_balance(&debit_account).await, Some(0));
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &fee_recipient,
+            &listing.payment_mint
+        ))
+        .await,
+        Some(fee)
+    );
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &listing.collection,
+            &listing.payment_mint
+        ))
+        .await,
+        Some(PRICE - fee)
+    );
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &buyer.pubkey(),
+            &listing.nft_mint
+        ))
+        .await,
+        Some(1)
+    );
+
+    // The emptied NFT account is closed and its rent refunded to the lister
+    assert!(test.account(&program_nft_account).await.is_none());
+    assert_eq!(
+        test.lamports(&admin.pubkey()).await,
+        admin_lamports + token_account_rent(&mut test).await
+    );
+
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    assert_eq!(bucket.tombstoned, 1);
+    assert_eq!(num_listings(&mut test, &listing.price_model).await, 1);
+    let num_occupied = test
+        .inventory_page(&listing.inventory_page)
+        .await
+        .num_occupied;
+    assert_eq!(num_occupied, 1);
+    let collection = test.collection(&listing.collection).await;
+    let (num_listed, num_sold) = (collection.stats.num_listed, collection.stats.num_sold);
+    assert_eq!((num_listed, num_sold), (1, 1));
+    let last_sale = collection.stats.last_sale;
+    assert_eq!(last_sale.buyer, buyer.pubkey());
+    assert_eq!(last_sale.nft_mint, listing.nft_mint);
+
+    // Tombstoned buckets stay linked until compacted, which refunds their rent
+    let bucket_lamports = test.lamports(&listing.nft_bucket).await;
+    let admin_lamports = test.lamports(&admin.pubkey()).await;
+    test.process(
+        &[ix::compact_list_item(&listing.nft_bucket, &bucket)],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+    assert!(test.account(&listing.nft_bucket).await.is_none());
+    assert_eq!(
+        test.lamports(&admin.pubkey()).await,
+        admin_lamports + bucket_lamports
+    );
+    assert_eq!(
+        list_order(&mut test, &collection).await,
+        vec![second_bucket]
+    );
+
+    test.process(
+        &[ix::withdraw_collection_liquidity(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &listing.payment_mint,
+            PRICE - fee,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &admin.pubkey(),
+            &listing.payment_mint
+        ))
+        .await,
+        Some(PRICE - fee)
+    );
+}
+
+#[tokio::test]
+async fn fee_waived_without_a_fee_account() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let fee_recipient = Pubkey::new_unique();
+    test.process(
+        &[ix::update_protocol_fee(
+            &admin.pubkey(),
+            &fee_recipient,
+            250,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    // Sales go through while the fee recipient has no account for the mint,
+    // and the collection is paid in full
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.purchase(
+        &listing,
+        &listing.nft_bucket,
+        &buyer,
+        &listing.payment_mint,
+        &debit_account,
+    )
+    .await
+    .unwrap();
+    let fee_account = get_associated_token_address(&fee_recipient, &listing.payment_mint);
+    assert!(test.account(&fee_account).await.is_none());
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &listing.collection,
+            &listing.payment_mint
+        ))
+        .await,
+        Some(PRICE)
+    );
+    let last_sale = test.collection(&listing.collection).await.stats.last_sale;
+    let amount = last_sale.amount;
+    assert_eq!(amount, PRICE);
+}
+
+#[tokio::test]
+async fn purchase_rejects_wrong_payment_mint() {
+    let mut test = Test::start().await;
+    let listing = test.listing().await;
+    let other_mint = test.create_mint().await;
+    let (buyer, debit_account) = test.buyer(&other_mint, PRICE).await;
+
+    let result = test
+        .purchase(
+            &listing,
+            &listing.nft_bucket,
+            &buyer,
+            &other_mint,
+            &debit_account,
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InvalidPurchaseMint)
+    );
+    assert_eq!(test.token_balance(&debit_account).await, Some(PRICE));
+    assert_eq!(test.nft_bucket(&listing.nft_bucket).await.tombstoned, 0);
+}
+
+#[tokio::test]
+async fn double_purchase_fails() {
+    let mut test = Test::start().await;
+    let listing = test.listing().await;
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.purchase(
+        &listing,
+        &listing.nft_bucket,
+        &buyer,
+        &listing.payment_mint,
+        &debit_account,
+    )
+    .await
+    .unwrap();
+
+    // Without a protocol fee no fee account is created
+    let fee_recipient = test.global_state().await.fee_recipient;
+    assert!(test
+        .account(&get_associated_token_address(&fee_recipient, &listing.payment_mint))
+        .await
+        .is_none());
+
+    // The bucket is tombstoned and its NFT account closed, the second buyer
+    // keeps their funds
+    let (second_buyer, second_debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    let result = test
+        .purchase(
+            &listing,
+            &listing.nft_bucket,
+            &second_buyer,
+            &listing.payment_mint,
+            &second_debit_account,
+        )
+        .await;
+    assert!(result.is_err());
+    assert_eq!(test.token_balance(&second_debit_account).await, Some(PRICE));
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &buyer.pubkey(),
+            &listing.nft_mint
+        ))
+        .await,
+        Some(1)
+    );
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &listing.collection,
+            &listing.payment_mint
+        ))
+        .await,
+        Some(PRICE)
+    );
+}
+
+#[tokio::test]
+async fn view_instructions_validate_accounts() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let payer = test.payer();
+    let listing = test.listing().await;
+    let (_, second_bucket) = test
+        .list_nft(&listing.market, &listing.collection, &listing.price_model)
+        .await;
+    let collection = test.collection(&listing.collection).await;
+
+    // Return data isn't visible to BanksClient, only whether the views succeed
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let quote = ix::quote_purchase(
+        &listing.market,
+        &listing.collection,
+        &listing.nft_bucket,
+        &bucket,
+    );
+    test.process(std::slice::from_ref(&quote), &[&payer]).await.unwrap();
+    test.process(
+        &[ix::list_page(
+            &listing.collection,
+            &collection.list_head,
+            &[second_bucket, listing.nft_bucket],
+            10,
+        )],
+        &[&payer],
+    )
+    .await
+    .unwrap();
+
+    // Buckets must follow the cursor in list order
+    let result = test
+        .process(
+            &[ix::list_page(
+                &listing.collection,
+                &collection.list_head,
+                &[listing.nft_bucket, second_bucket],
+                10,
+            )],
+            &[&payer],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidListItem));
+
+    test.process(
+        &[ix::set_collection_paused(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            true,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let result = test.process(std::slice::from_ref(&quote), &[&payer]).await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Paused));
+    test.process(
+        &[ix::set_collection_paused(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            false,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.purchase(
+        &listing,
+        &listing.nft_bucket,
+        &buyer,
+        &listing.payment_mint,
+        &debit_account,
+    )
+    .await
+    .unwrap();
+    let result = test.process(&[quote], &[&payer]).await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::ListItemTombstoned)
+    );
+}
+
+#[tokio::test]
+async fn verify_and_repair_list() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let (_, second_bucket) = test
+        .list_nft(&listing.market, &listing.collection, &listing.price_model)
+        .await;
+    let collection = test.collection(&listing.collection).await;
+    let verify = |list_items: &[Pubkey]| {
+        ix::verify_collection(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &collection.list_head,
+            list_items,
+        )
+    };
+    let repair = |prev_list_item: &Pubkey, next_list_item: &Pubkey, skipped: &[Pubkey]| {
+        ix::repair_link(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            prev_list_item,
+            next_list_item,
+            skipped,
+        )
+    };
+
+    test.process(
+        &[verify(&[second_bucket, listing.nft_bucket, collection.list_tail])],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let result = test
+        .process(
+            &[verify(&[listing.nft_bucket, second_bucket])],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidListItem));
+
+    let intruder = test.funded_keypair().await;
+    let result = test
+        .process(
+            &[ix::verify_collection(
+                &intruder.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &collection.list_head,
+                &[],
+            )],
+            &[&intruder],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    let (head, tail) = (collection.list_head, collection.list_tail);
+    let result = test
+        .process(&[repair(&head, &second_bucket, &[])], &[&admin])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::ListLinkIntact));
+    let result = test
+        .process(&[repair(&head, &listing.nft_bucket, &[second_bucket])], &[&admin])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::ListLinkIntact));
+    let result = test
+        .process(&[repair(&tail, &listing.nft_bucket, &[])], &[&admin])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidRepair));
+
+    // A bucket that lost its prev pointer is relinked, it can't be dropped
+    // while it is still listed
+    test.set_list_links(&second_bucket, &Pubkey::new_unique(), &listing.nft_bucket)
+        .await;
+    assert!(test.list(&collection).await.is_err());
+    let result = test
+        .process(&[repair(&head, &listing.nft_bucket, &[second_bucket])], &[&admin])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidRepair));
+    test.process(&[repair(&head, &second_bucket, &[])], &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(
+        list_order(&mut test, &collection).await,
+        vec![second_bucket, listing.nft_bucket]
+    );
+
+    // Once sold it can be dropped, but only by passing it
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.purchase(
+        &listing,
+        &second_bucket,
+        &buyer,
+        &listing.payment_mint,
+        &debit_account,
+    )
+    .await
+    .unwrap();
+    test.set_list_links(&second_bucket, &Pubkey::new_unique(), &listing.nft_bucket)
+        .await;
+    let result = test
+        .process(&[repair(&head, &listing.nft_bucket, &[])], &[&admin])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidRepair));
+    test.process(&[repair(&head, &listing.nft_bucket, &[second_bucket])], &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(
+        list_order(&mut test, &collection).await,
+        vec![listing.nft_bucket]
+    );
+
+    // Compacting a sold bucket its neighbours no longer link to would
+    // relink them around it
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.purchase(
+        &listing,
+        &listing.nft_bucket,
+        &buyer,
+        &listing.payment_mint,
+        &debit_account,
+    )
+    .await
+    .unwrap();
+    let head_state = test.nft_bucket(&head).await;
+    test.set_list_links(&head, &head_state.prev_list_item, &tail)
+        .await;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let result = test
+        .process(
+            &[ix::compact_list_item(&listing.nft_bucket, &bucket)],
+            &[&buyer],
+        )
+        .await;
+    // Anchor's ConstraintRaw
+    assert_eq!(custom_error(result), 143);
+    assert!(test.account(&listing.nft_bucket).await.is_some());
+}
+
+#[tokio::test]
+async fn rejects_buckets_from_another_collection() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let other_collection = test.init_collection(&listing.market).await;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+
+    let result = test
+        .process(
+            &[ix::set_nft_expiry(
+                &admin.pubkey(),
+                &listing.market,
+                &other_collection,
+                &listing.nft_bucket,
+                &bucket,
+                Some(1),
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidListItem));
+
+    let result = test
+        .process(
+            &[ix::set_nft_sale_window(
+                &admin.pubkey(),
+                &listing.market,
+                &other_collection,
+                &listing.nft_bucket,
+                &bucket,
+                None,
+                Some(1),
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidListItem));
+
+    // Inserting after a foreign bucket would splice the two lists together
+    let nft_mint = test.create_mint().await;
+    let admin_nft_account = test.mint_to(&nft_mint, &admin.pubkey(), 1).await;
+    let mut args = test
+        .list_nft_args(
+            &listing.market,
+            &other_collection,
+            &listing.price_model,
+            &nft_mint,
+            &admin_nft_account,
+        )
+        .await;
+    args.prev_list_item = listing.nft_bucket;
+    args.next_list_item = test.nft_bucket(&listing.nft_bucket).await.next_list_item;
+    let result = test
+        .process(&[ix::insert_nft_after(&args)], &[&admin])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidListItem));
+
+    // Listing into another collection's inventory page
+    let mut args = test
+        .list_nft_args(
+            &listing.market,
+            &other_collection,
+            &listing.price_model,
+            &nft_mint,
+            &admin_nft_account,
+        )
+        .await;
+    args.inventory_page = listing.inventory_page;
+    let result = test.process(&[ix::add_nft(&args)], &[&admin]).await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InvalidInventoryPage)
+    );
+
+    // Moving a bucket next to one from another collection
+    let collection = test.collection(&listing.collection).await;
+    let other_nft_bucket = test
+        .list_nft(&listing.market, &other_collection, &listing.price_model)
+        .await
+        .1;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let other_bucket = test.nft_bucket(&other_nft_bucket).await;
+    let result = test
+        .process(
+            &[ix::move_item(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &listing.nft_bucket,
+                &bucket,
+                &other_nft_bucket,
+                &other_bucket.next_list_item,
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidListItem));
+    assert_eq!(
+        list_order(&mut test, &collection).await,
+        vec![listing.nft_bucket]
+    );
+}
+
+#[tokio::test]
+async fn rejects_non_admins() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let stranger = test.funded_keypair().await;
+    let market = test.market(&listing.market).await;
+    let collection = test.collection(&listing.collection).await;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let stranger_nft_mint = test.create_mint().await;
+    let stranger_nft_account = test
+        .mint_to(&stranger_nft_mint, &stranger.pubkey(), 1)
+        .await;
+    let mut list_args = test
+        .list_nft_args(
+            &listing.market,
+            &listing.collection,
+            &listing.price_model,
+            &stranger_nft_mint,
+            &stranger_nft_account,
+        )
+        .await;
+    list_args.admin = stranger.pubkey();
+    // Treasuries must exist for liquidity withdrawals to reach the admin check
+    test.mint_to(&listing.payment_mint, &listing.market, 0)
+        .await;
+    test.mint_to(&listing.payment_mint, &listing.collection, 0)
+        .await;
+
+    let key = stranger.pubkey();
+    let instructions = vec![
+        ix::set_market_paused(&key, &listing.market, true),
+        ix::update_market_metadata(
+            &key,
+            &listing.market,
+            "Mine".to_string(),
+            Metadata::default(),
+        ),
+        ix::init_collection(&key, &listing.market, &market, "Mine".to_string()),
+        ix::set_collection_paused(&key, &listing.market, &listing.collection, true),
+        ix::set_collection_sale_window(&key, &listing.market, &listing.collection, None, Some(1)),
+        ix::set_nft_sale_window(
+            &key,
+            &listing.market,
+            &listing.collection,
+            &listing.nft_bucket,
+            &bucket,
+            None,
+            Some(1),
+        ),
+        ix::set_nft_expiry(
+            &key,
+            &listing.market,
+            &listing.collection,
+            &listing.nft_bucket,
+            &bucket,
+            Some(1),
+        ),
+        ix::set_mystery_price_model(
+            &key,
+            &listing.market,
+            &listing.collection,
+            &listing.price_model,
+            true,
+        ),
+        ix::update_collection_metadata(
+            &key,
+            &listing.market,
+            &listing.collection,
+            "Mine".to_string(),
+            Metadata::default(),
+        ),
+        ix::init_inventory_page(&key, &listing.market, &listing.collection, &collection),
+        ix::close_inventory_page(&key, &listing.market, &listing.collection, 0),
+        ix::init_price_model(&key, &listing.market, &market, Vec::new()),
+        ix::close_price_model(&key, &listing.market, &listing.price_model),
+        ix::add_nft(&list_args),
+        ix::withdraw_nft(
+            &key,
+            &listing.market,
+            &listing.collection,
+            &listing.nft_bucket,
+            &bucket,
+        ),
+        ix::withdraw_liquidity(&key, &listing.market, &listing.payment_mint, 0),
+        ix::withdraw_collection_liquidity(
+            &key,
+            &listing.market,
+            &listing.collection,
+            &listing.payment_mint,
+            0,
+        ),
+        ix::close_market(&key, &listing.market, &key, &[]),
+    ];
+    for instruction in instructions {
+        let result = test.process(&[instruction], &[&stranger]).await;
+        assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+    }
+
+    // close_collection also needs the rent refund signature of the lister
+    let result = test
+        .process(
+            &[ix::close_collection(
+                &key,
+                &listing.market,
+                &listing.collection,
+                &admin.pubkey(),
+                &[],
+            )],
+            &[&stranger, &admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+
+    assert_eq!(test.nft_bucket(&listing.nft_bucket).await.tombstoned, 0);
+    assert_eq!(test.market(&listing.market).await.paused, 0);
+}
+
+#[tokio::test]
+async fn sale_windows_pause_and_expiry() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    let now = test.clock().await.unix_timestamp;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+
+    test.process(
+        &[ix::set_collection_paused(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            true,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let result = test
+        .purchase(
+            &listing,
+            &listing.nft_bucket,
+            &buyer,
+            &listing.payment_mint,
+            &debit_account,
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Paused));
+
+    test.process(
+        &[
+            ix::set_collection_paused(&admin.pubkey(), &listing.market, &listing.collection, false),
+            ix::set_collection_sale_window(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                Some(now + 3600),
+                None,
+            ),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let result = test
+        .purchase(
+            &listing,
+            &listing.nft_bucket,
+            &buyer,
+            &listing.payment_mint,
+            &debit_account,
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::SaleNotStarted));
+
+    test.process(
+        &[
+            ix::set_collection_sale_window(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                None,
+                None,
+            ),
+            ix::set_nft_sale_window(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &listing.nft_bucket,
+                &bucket,
+                Some(now - 3600),
+                Some(now - 1),
+            ),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let result = test
+        .purchase(
+            &listing,
+            &listing.nft_bucket,
+            &buyer,
+            &listing.payment_mint,
+            &debit_account,
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::SaleEnded));
+
+    // Unexpired listings cannot be cranked
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let result = test
+        .process(
+            &[ix::crank_expired(
+                &buyer.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &listing.nft_bucket,
+                &bucket,
+            )],
+            &[&buyer],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::ListingNotExpired)
+    );
+
+    test.process(
+        &[
+            ix::set_nft_sale_window(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &listing.nft_bucket,
+                &bucket,
+                None,
+                None,
+            ),
+            ix::set_nft_expiry(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &listing.nft_bucket,
+                &bucket,
+                Some(now - 1),
+            ),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let result = test
+        .purchase(
+            &listing,
+            &listing.nft_bucket,
+            &buyer,
+            &listing.payment_mint,
+            &debit_account,
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::ListingExpired));
+
+    // Neighbours have to link back to the expired listing
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let head_state = test.nft_bucket(&bucket.prev_list_item).await;
+    test.set_list_links(
+        &bucket.prev_list_item,
+        &head_state.prev_list_item,
+        &bucket.next_list_item,
+    )
+    .await;
+    let crank_expired = ix::crank_expired(
+        &buyer.pubkey(),
+        &listing.market,
+        &listing.collection,
+        &listing.nft_bucket,
+        &bucket,
+    );
+    let result = test
+        .process(std::slice::from_ref(&crank_expired), &[&buyer])
+        .await;
+    // Anchor's ConstraintRaw
+    assert_eq!(custom_error(result), 143);
+    test.set_list_links(
+        &bucket.prev_list_item,
+        &head_state.prev_list_item,
+        &listing.nft_bucket,
+    )
+    .await;
+
+    // Anyone may return an expired listing to its lister
+    test.process(&[crank_expired], &[&buyer])
+        .await
+        .unwrap();
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &admin.pubkey(),
+            &listing.nft_mint
+        ))
+        .await,
+        Some(1)
+    );
+    assert!(test
+        .account(&get_associated_token_address(
+            &listing.collection,
+            &listing.nft_mint
+        ))
+        .await
+        .is_none());
+    assert_eq!(num_listings(&mut test, &listing.price_model).await, 0);
+    assert_eq!(test.token_balance(&debit_account).await, Some(PRICE));
+}
+
+#[tokio::test]
+async fn withdraw_and_close() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+
+    let result = test
+        .process(
+            &[ix::close_collection(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &admin.pubkey(),
+                &[],
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::CollectionNonEmpty)
+    );
+
+    let result = test
+        .process(
+            &[ix::close_price_model(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.price_model,
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::PriceModelInUse));
+
+    let result = test
+        .process(
+            &[ix::close_inventory_page(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                0,
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InventoryPageNonEmpty)
+    );
+
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    test.process(
+        &[
+            ix::withdraw_nft(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &listing.nft_bucket,
+                &bucket,
+            ),
+            ix::compact_list_item(&listing.nft_bucket, &bucket),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &admin.pubkey(),
+            &listing.nft_mint
+        ))
+        .await,
+        Some(1)
+    );
+    assert!(test.account(&listing.nft_bucket).await.is_none());
+
+    // Withdrawing again fails once the bucket is gone
+    let result = test
+        .process(
+            &[ix::withdraw_nft(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &listing.nft_bucket,
+                &bucket,
+            )],
+            &[&admin],
+        )
+        .await;
+    assert!(result.is_err());
+
+    // The list is empty but an inventory page remains
+    let result = test
+        .process(
+            &[ix::close_collection(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &admin.pubkey(),
+                &[],
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::CollectionNonEmpty)
+    );
+
+    let result = test
+        .process(
+            &[ix::close_market(
+                &admin.pubkey(),
+                &listing.market,
+                &admin.pubkey(),
+                &[],
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::MarketNonEmpty));
+
+    // Market liquidity is withdrawn before its treasury is closed
+    let market_treasury = test
+        .mint_to(&listing.payment_mint, &listing.market, 500)
+        .await;
+    test.process(
+        &[ix::withdraw_liquidity(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.payment_mint,
+            500,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&market_treasury).await, Some(0));
+
+    // Any market treasury can be tracked, and then has to be closed with it
+    let other_mint = test.create_mint().await;
+    let other_treasury = test.mint_to(&other_mint, &listing.market, 5).await;
+    let stranger = test.funded_keypair().await;
+    test.process(
+        &[ix::track_market_treasury(&listing.market, &other_mint)],
+        &[&stranger],
+    )
+    .await
+    .unwrap();
+    let num_treasury_accounts = test.market(&listing.market).await.num_treasury_accounts;
+    assert_eq!(num_treasury_accounts, 2);
+
+    let collection = test.collection(&listing.collection).await;
+    test.process(
+        &[
+            ix::close_inventory_page(&admin.pubkey(), &listing.market, &listing.collection, 0),
+            ix::close_collection(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &admin.pubkey(),
+                &[],
+            ),
+            ix::close_price_model(&admin.pubkey(), &listing.market, &listing.price_model),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    for closed in [
+        listing.collection,
+        listing.inventory_page,
+        listing.price_model,
+        collection.list_head,
+        collection.list_tail,
+    ]
+    .iter()
+    {
+        assert!(test.account(closed).await.is_none());
+    }
+
+    // The refund must go to a market admin
+    let result = test
+        .process(
+            &[ix::close_market(
+                &admin.pubkey(),
+                &listing.market,
+                &Pubkey::new_unique(),
+                &[market_treasury],
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::AdminNotFound));
+
+    let close_market = |treasury_accounts: &[Pubkey]| {
+        ix::close_market(
+            &admin.pubkey(),
+            &listing.market,
+            &admin.pubkey(),
+            treasury_accounts,
+        )
+    };
+    let result = test
+        .process(&[close_market(&[market_treasury])], &[&admin])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::TreasuryNotClosed)
+    );
+    let result = test
+        .process(
+            &[close_market(&[market_treasury, other_treasury])],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::TreasuryNonEmpty)
+    );
+
+    test.process(
+        &[
+            ix::return_market_tokens(
+                &admin.pubkey(),
+                &listing.market,
+                &other_mint,
+                &admin.pubkey(),
+                5,
+            ),
+            close_market(&[market_treasury, other_treasury]),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert!(test.account(&listing.market).await.is_none());
+    assert!(test.account(&market_treasury).await.is_none());
+    assert!(test.account(&other_treasury).await.is_none());
+}
+
+#[tokio::test]
+async fn rescue_stray_tokens() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let recipient = Pubkey::new_unique();
+
+    // An NFT sent straight to the collection is listed in place
+    let stray_mint = test.create_mint().await;
+    test.mint_to(&stray_mint, &listing.collection, 1).await;
+    let collection = test.collection(&listing.collection).await;
+    let adopt = ix::adopt_nft(
+        &admin.pubkey(),
+        &listing.market,
+        &listing.collection,
+        &collection.list_head,
+        &listing.nft_bucket,
+        &stray_mint,
+        &listing.inventory_page,
+        &listing.price_model,
+        &[listing.inventory_page],
+    );
+    let set_paused = |paused| {
+        ix::set_collection_paused(&admin.pubkey(), &listing.market, &listing.collection, paused)
+    };
+    let result = test
+        .process(&[set_paused(true), adopt.clone()], &[&admin])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Paused));
+    test.process(std::slice::from_ref(&adopt), &[&admin]).await.unwrap();
+    let stray_bucket = find_nft_bucket_address(&listing.collection, &stray_mint).0;
+    assert_eq!(
+        list_order(&mut test, &collection).await,
+        vec![stray_bucket, listing.nft_bucket]
+    );
+    assert_eq!(num_listings(&mut test, &listing.price_model).await, 2);
+    assert!(test.process(&[adopt], &[&admin]).await.is_err());
+
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.purchase(
+        &listing,
+        &stray_bucket,
+        &buyer,
+        &listing.payment_mint,
+        &debit_account,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &buyer.pubkey(),
+            &stray_mint
+        ))
+        .await,
+        Some(1)
+    );
+
+    // Listed NFTs only leave through withdraw_nft
+    let result = test
+        .process(
+            &[ix::return_collection_tokens(
+                &admin.pubkey(),
+                &listing.market,
+                &listing.collection,
+                &listing.nft_mint,
+                &recipient,
+                1,
+                &[listing.inventory_page],
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::ListItemNotTombstoned)
+    );
+
+    let stray_mint = test.create_mint().await;
+    test.mint_to(&stray_mint, &listing.collection, 1).await;
+    let return_collection_tokens = |amount| {
+        ix::return_collection_tokens(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &stray_mint,
+            &recipient,
+            amount,
+            &[listing.inventory_page],
+        )
+    };
+    let result = test
+        .process(&[return_collection_tokens(2)], &[&admin])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InvalidRescueAmount)
+    );
+    test.process(&[return_collection_tokens(1)], &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(&recipient, &stray_mint))
+            .await,
+        Some(1)
+    );
+
+    // Tokens sent to the market
+    test.mint_to(&listing.payment_mint, &listing.market, PRICE)
+        .await;
+    let intruder = test.funded_keypair().await;
+    let result = test
+        .process(
+            &[ix::return_market_tokens(
+                &intruder.pubkey(),
+                &listing.market,
+                &listing.payment_mint,
+                &intruder.pubkey(),
+                PRICE,
+            )],
+            &[&intruder],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+    test.process(
+        &[ix::return_market_tokens(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.payment_mint,
+            &recipient,
+            PRICE,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &recipient,
+            &listing.payment_mint
+        ))
+        .await,
+        Some(PRICE)
+    );
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &listing.market,
+            &listing.payment_mint
+        ))
+        .await,
+        Some(0)
+    );
+}
+
+#[tokio::test]
+async fn close_collection_refunds_treasuries() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    test.init_global_state().await;
+    let market = test.init_market().await;
+    let collection = test.init_collection(&market).await;
+    let payment_mint = test.create_mint().await;
+    let treasury = test.mint_to(&payment_mint, &collection, 10).await;
+
+    test.process(
+        &[ix::close_inventory_page(
+            &admin.pubkey(),
+            &market,
+            &collection,
+            0,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    // Treasuries holding tokens cannot be closed
+    let result = test
+        .process(
+            &[ix::close_collection(
+                &admin.pubkey(),
+                &market,
+                &collection,
+                &admin.pubkey(),
+                &[treasury],
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::TreasuryNonEmpty)
+    );
+
+    let stranger_account = test.mint_to(&payment_mint, &Pubkey::new_unique(), 0).await;
+    let result = test
+        .process(
+            &[ix::close_collection(
+                &admin.pubkey(),
+                &market,
+                &collection,
+                &admin.pubkey(),
+                &[stranger_account],
+            )],
+            &[&admin],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidTreasury));
+
+    let collection_state = test.collection(&collection).await;
+    let mut refunded = 0;
+    for closed in [
+        treasury,
+        collection,
+        collection_state.list_head,
+        collection_state.list_tail,
+    ]
+    .iter()
+    {
+        refunded += test.lamports(closed).await;
+    }
+    test.mint_to(&payment_mint, &admin.pubkey(), 0).await;
+    let admin_lamports = test.lamports(&admin.pubkey()).await;
+    test.process(
+        &[
+            ix::withdraw_collection_liquidity(
+                &admin.pubkey(),
+                &market,
+                &collection,
+                &payment_mint,
+                10,
+            ),
+            ix::close_collection(
+                &admin.pubkey(),
+                &market,
+                &collection,
+                &admin.pubkey(),
+                &[treasury],
+            ),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    assert!(test.account(&treasury).await.is_none());
+    assert!(test.account(&collection).await.is_none());
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &admin.pubkey(),
+            &payment_mint
+        ))
+        .await,
+        Some(10)
+    );
+
+    // Everything closed is refunded to the admin, less the transaction fee
+    let admin_lamports_after = test.lamports(&admin.pubkey()).await;
+    assert!(admin_lamports_after <= admin_lamports + refunded);
+    assert!(admin_lamports_after > admin_lamports + refunded - SOL / 1000);
+    let num_open_collections = test.market(&market).await.num_open_collections;
+    assert_eq!(num_open_collections, 0);
+}
+
+#[tokio::test]
+async fn close_collection_requires_paid_treasuries() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.purchase(
+        &listing,
+        &listing.nft_bucket,
+        &buyer,
+        &listing.payment_mint,
+        &debit_account,
+    )
+    .await
+    .unwrap();
+    let num_treasury_accounts = test
+        .collection(&listing.collection)
+        .await
+        .num_treasury_accounts;
+    assert_eq!(num_treasury_accounts, 1);
+
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    test.process(
+        &[
+            ix::compact_list_item(&listing.nft_bucket, &bucket),
+            ix::close_inventory_page(&admin.pubkey(), &listing.market, &listing.collection, 0),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    // The treasury holding the sale proceeds can't be left behind
+    let treasury = get_associated_token_address(&listing.collection, &listing.payment_mint);
+    let close = |treasuries: &[Pubkey]| {
+        ix::close_collection(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &admin.pubkey(),
+            treasuries,
+        )
+    };
+    let result = test.process(&[close(&[])], &[&admin]).await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::TreasuryNotClosed)
+    );
+    let result = test.process(&[close(&[treasury])], &[&admin]).await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::TreasuryNonEmpty)
+    );
+
+    let proceeds = test.token_balance(&treasury).await.unwrap();
+    test.mint_to(&listing.payment_mint, &admin.pubkey(), 0).await;
+    let withdraw = ix::withdraw_collection_liquidity(
+        &admin.pubkey(),
+        &listing.market,
+        &listing.collection,
+        &listing.payment_mint,
+        proceeds,
+    );
+    let result = test
+        .process(&[withdraw.clone(), close(&[treasury, treasury])], &[&admin])
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InvalidTreasury));
+
+    test.process(&[withdraw, close(&[treasury])], &[&admin])
+        .await
+        .unwrap();
+    assert!(test.account(&treasury).await.is_none());
+    assert!(test.account(&listing.collection).await.is_none());
+}
+
+async fn enable_mystery_box(test: &mut Test, listing: &Listing) {
+    let admin = test.admin();
+    test.process(
+        &[ix::set_mystery_price_model(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &listing.price_model,
+            true,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+}
+
+async fn commit_mystery_box(
+    test: &mut Test,
+    listing: &Listing,
+    buyer: &Keypair,
+    debit_account: &Pubkey,
+) {
+    let global_state = test.global_state().await;
+    test.process(
+        &[ix::commit_random_purchase(
+            &buyer.pubkey(),
+            &global_state,
+            &listing.market,
+            &listing.collection,
+            &listing.price_model,
+            &listing.payment_mint,
+            debit_account,
+        )],
+        &[buyer],
+    )
+    .await
+    .unwrap();
+}
+
+fn reveal_mystery_box(
+    cranker: &Keypair,
+    buyer: &Keypair,
+    listing: &Listing,
+    bucket: &NftBucket,
+) -> Instruction {
+    ix::reveal_random_purchase(
+        &cranker.pubkey(),
+        &buyer.pubkey(),
+        &listing.market,
+        &listing.collection,
+        &listing.nft_bucket,
+        bucket,
+        &[listing.inventory_page],
+    )
+}
+
+async fn refund_mystery_box(
+    test: &mut Test,
+    listing: &Listing,
+    buyer: &Keypair,
+    debit_account: &Pubkey,
+) {
+    let mystery_commit = find_mystery_commit_address(&listing.collection, &buyer.pubkey()).0;
+    let mystery_commit_state: MysteryCommit = test.decode(&mystery_commit).await;
+    test.process(
+        &[ix::refund_random_purchase(
+            &buyer.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &mystery_commit_state,
+            debit_account,
+            &[listing.inventory_page],
+        )],
+        &[buyer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(debit_account).await, Some(PRICE));
+    assert!(test.account(&mystery_commit).await.is_none());
+}
+
+#[tokio::test]
+async fn mystery_box_reveal_and_refund() {
+    let mut test = Test::start().await;
+    let listing = test.listing().await;
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    let global_state = test.global_state().await;
+
+    let result = test
+        .process(
+            &[ix::commit_random_purchase(
+                &buyer.pubkey(),
+                &global_state,
+                &listing.market,
+                &listing.collection,
+                &listing.price_model,
+                &listing.payment_mint,
+                &debit_account,
+            )],
+            &[&buyer],
+        )
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::MysteryBoxDisabled)
+    );
+
+    enable_mystery_box(&mut test, &listing).await;
+    commit_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+    assert_eq!(test.token_balance(&debit_account).await, Some(0));
+
+    // Anyone can reveal the draw, once the slot after the commit is hashed
+    let cranker = test.funded_keypair().await;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let reveal = reveal_mystery_box(&cranker, &buyer, &listing, &bucket);
+    let result = test.process(std::slice::from_ref(&reveal), &[&cranker]).await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::RevealTooEarly));
+
+    // With a single listing every draw selects it
+    test.advance_slot().await;
+    test.advance_slot().await;
+    test.process(&[reveal], &[&cranker]).await.unwrap();
+    assert_eq!(
+        test.token_balance(&get_associated_token_address(
+            &buyer.pubkey(),
+            &listing.nft_mint
+        ))
+        .await,
+        Some(1)
+    );
+    assert_eq!(test.nft_bucket(&listing.nft_bucket).await.tombstoned, 1);
+    let mystery_commit = find_mystery_commit_address(&listing.collection, &buyer.pubkey()).0;
+    assert!(test.account(&mystery_commit).await.is_none());
+
+    // Nothing is left to draw, so a new commit can be refunded immediately
+    let (second_buyer, second_debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    commit_mystery_box(&mut test, &listing, &second_buyer, &second_debit_account).await;
+    let mystery_commit = find_mystery_commit_address(&listing.collection, &second_buyer.pubkey()).0;
+    let mystery_commit_state: MysteryCommit = test.decode(&mystery_commit).await;
+    let expire = ix::expire_random_purchase(
+        &second_buyer.pubkey(),
+        &listing.market,
+        &listing.collection,
+        &[listing.inventory_page],
+    );
+    test.advance_slot().await;
+    test.advance_slot().await;
+    test.evict_slot_hashes(mystery_commit_state.commit_slot + 1).await;
+    let result = test.process(&[expire], &[&cranker]).await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InventoryEmpty));
+    test.process(
+        &[ix::refund_random_purchase(
+            &second_buyer.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &mystery_commit_state,
+            &second_debit_account,
+            &[listing.inventory_page],
+        )],
+        &[&second_buyer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&second_debit_account).await, Some(PRICE));
+    assert!(test.account(&mystery_commit).await.is_none());
+}
+
+#[tokio::test]
+async fn withheld_reveal_is_forfeited() {
+    let mut test = Test::start().await;
+    let listing = test.listing().await;
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    enable_mystery_box(&mut test, &listing).await;
+    commit_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+
+    let mystery_commit = find_mystery_commit_address(&listing.collection, &buyer.pubkey()).0;
+    let mystery_commit_state: MysteryCommit = test.decode(&mystery_commit).await;
+    let refund = ix::refund_random_purchase(
+        &buyer.pubkey(),
+        &listing.market,
+        &listing.collection,
+        &mystery_commit_state,
+        &debit_account,
+        &[listing.inventory_page],
+    );
+    let result = test.process(std::slice::from_ref(&refund), &[&buyer]).await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::RefundUnavailable)
+    );
+
+    // The buyer can't back out of a draw they dislike by not revealing it
+    let cranker = test.funded_keypair().await;
+    let expire = ix::expire_random_purchase(
+        &buyer.pubkey(),
+        &listing.market,
+        &listing.collection,
+        &[listing.inventory_page],
+    );
+    test.advance_slot().await;
+    test.advance_slot().await;
+    let result = test.process(std::slice::from_ref(&expire), &[&cranker]).await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::RevealWindowOpen));
+
+    test.evict_slot_hashes(mystery_commit_state.commit_slot + 1).await;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let reveal = reveal_mystery_box(&cranker, &buyer, &listing, &bucket);
+    let result = test.process(&[reveal], &[&cranker]).await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::RevealWindowElapsed)
+    );
+    let result = test.process(&[refund], &[&buyer]).await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::RefundUnavailable)
+    );
+
+    // The payment stays with the collection and the listing stays for sale
+    let treasury = get_associated_token_address(&listing.collection, &listing.payment_mint);
+    let treasury_balance = test.token_balance(&treasury).await;
+    test.process(&[expire], &[&cranker]).await.unwrap();
+    assert!(test.account(&mystery_commit).await.is_none());
+    assert_eq!(test.token_balance(&treasury).await, treasury_balance);
+    assert_eq!(test.token_balance(&debit_account).await, Some(0));
+    assert_eq!(test.nft_bucket(&listing.nft_bucket).await.tombstoned, 0);
+}
+
+#[tokio::test]
+async fn reveal_delivers_the_inventory_at_commit() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    enable_mystery_box(&mut test, &listing).await;
+    commit_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+    test.advance_slot().await;
+    test.advance_slot().await;
+
+    // Taking the drawn listing off sale after the commit doesn't block the reveal
+    let now = test.clock().await.unix_timestamp;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    test.process(
+        &[ix::set_nft_sale_window(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &listing.nft_bucket,
+            &bucket,
+            Some(now + 3600),
+            None,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let cranker = test.funded_keypair().await;
+    let reveal = reveal_mystery_box(&cranker, &buyer, &listing, &bucket);
+    test.process(&[reveal], &[&cranker]).await.unwrap();
+    assert_eq!(test.nft_bucket(&listing.nft_bucket).await.tombstoned, 1);
+}
+
+#[tokio::test]
+async fn undeliverable_draws_are_refunded() {
+    let mut test = Test::start().await;
+    let admin = test.admin();
+    let listing = test.listing().await;
+    let cranker = test.funded_keypair().await;
+    let bucket = test.nft_bucket(&listing.nft_bucket).await;
+    let now = test.clock().await.unix_timestamp;
+    enable_mystery_box(&mut test, &listing).await;
+
+    // Listings that aren't on sale at the commit aren't drawn
+    let set_sale_window = |sale_start| {
+        ix::set_nft_sale_window(
+            &admin.pubkey(),
+            &listing.market,
+            &listing.collection,
+            &listing.nft_bucket,
+            &bucket,
+            sale_start,
+            None,
+        )
+    };
+    test.process(&[set_sale_window(Some(now + 3600))], &[&admin])
+        .await
+        .unwrap();
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    commit_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+    test.process(&[set_sale_window(None)], &[&admin])
+        .await
+        .unwrap();
+    test.advance_slot().await;
+    test.advance_slot().await;
+    let reveal = reveal_mystery_box(&cranker, &buyer, &listing, &bucket);
+    let result = test.process(&[reveal], &[&cranker]).await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::InventoryEmpty));
+    refund_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+
+    // A pause after the commit voids the draw, even once it is lifted
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    commit_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+    test.advance_slot().await;
+    test.process(
+        &[
+            ix::set_market_paused(&admin.pubkey(), &listing.market, true),
+            ix::set_market_paused(&admin.pubkey(), &listing.market, false),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    test.advance_slot().await;
+    let reveal = reveal_mystery_box(&cranker, &buyer, &listing, &bucket);
+    let result = test.process(&[reveal], &[&cranker]).await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::DrawVoided));
+    let mystery_commit = find_mystery_commit_address(&listing.collection, &buyer.pubkey()).0;
+    let mystery_commit_state: MysteryCommit = test.decode(&mystery_commit).await;
+    test.evict_slot_hashes(mystery_commit_state.commit_slot + 1).await;
+    let expire = ix::expire_random_purchase(
+        &buyer.pubkey(),
+        &listing.market,
+        &listing.collection,
+        &[listing.inventory_page],
+    );
+    let result = test.process(&[expire], &[&cranker]).await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::DrawVoided));
+    refund_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+
+    // The drawn listing is sold before the reveal, which leaves nothing to
+    // deliver once the draw is known
+    let (buyer, debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    commit_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+    let (other_buyer, other_debit_account) = test.buyer(&listing.payment_mint, PRICE).await;
+    test.purchase(
+        &listing,
+        &listing.nft_bucket,
+        &other_buyer,
+        &listing.payment_mint,
+        &other_debit_account,
+    )
+    .await
+    .unwrap();
+    test.advance_slot().await;
+    test.advance_slot().await;
+    refund_mystery_box(&mut test, &listing, &buyer, &debit_account).await;
+}
+
+// Legacy layouts, serialized the way the unversioned program wrote them
+#[derive(AnchorSerialize)]
+struct LegacyGlobalState {
+    nonce: u8,
+    num_markets: u32,
+}
+
+#[derive(AnchorSerialize)]
+struct LegacyMarket {
+    nonce: u8,
+    index: u32,
+    whitelist: Vec<Pubkey>,
+    num_collections: u32,
+    num_price_models: u32,
+    title: String,
+}
+
+#[derive(AnchorSerialize)]
+struct LegacyCollection {
+    nonce: u8,
+    index: u32,
+    list_head: Pubkey,
+    list_tail: Pubkey,
+    title: String,
+}
+
+#[derive(AnchorSerialize)]
+struct LegacyNftBucket {
+    nonce: u8,
+    nft_mint: Pubkey,
+    price_model: Pubkey,
+    prev_list_item: Pubkey,
+    next_list_item: Pubkey,
+    payer: Pubkey,
+}
+
+#[derive(AnchorSerialize)]
+struct LegacyPriceModel {
+    nonce: u8,
+    index: u32,
+    market: Pubkey,
+    sale_prices: Vec<SalePrice>,
+}
+
+#[tokio::test]
+async fn migrate_legacy_accounts() {
+    let admin = Keypair::new();
+    let payer = Pubkey::new_unique();
+    let nft_mint = Pubkey::new_unique();
+    let payment_mint = Pubkey::new_unique();
+    let admin_key = admin.pubkey();
+
+    let (global_state, global_state_nonce) = find_global_state_address();
+    let (market, market_nonce) = find_market_address(&global_state, 0);
+    let (collection, collection_nonce) = find_collection_address(&market, 0);
+    let (list_head, list_head_nonce) = find_list_head_address(&collection);
+    let (list_tail, list_tail_nonce) = find_list_tail_address(&collection);
+    let (price_model, price_model_nonce) = find_price_model_address(&market, 0);
+    // Buckets listed before PDAs were keypair accounts
+    let nft_bucket = Keypair::new().pubkey();
+
+    let legacy_bucket = |nonce, nft_mint, prev_list_item, next_list_item| LegacyNftBucket {
+        nonce,
+        nft_mint,
+        price_model,
+        prev_list_item,
+        next_list_item,
+        payer,
+    };
+    let accounts = vec![
+        (
+            global_state,
+            legacy_account::<GlobalState>(
+                &LegacyGlobalState {
+                    nonce: global_state_nonce,
+                    num_markets: 1,
+                },
+                13,
+            ),
+        ),
+        (
+            market,
+            legacy_account::<Market>(
+                &LegacyMarket {
+                    nonce: market_nonce,
+                    index: 0,
+                    whitelist: vec![admin.pubkey()],
+                    num_collections: 1,
+                    num_price_models: 1,
+                    title: "Legacy market".to_string(),
+                },
+                575,
+            ),
+        ),
+        (
+            collection,
+            legacy_account::<Collection>(
+                &LegacyCollection {
+                    nonce: collection_nonce,
+                    index: 0,
+                    list_head,
+                    list_tail,
+                    title: "Legacy collection".to_string(),
+                },
+                115,
+            ),
+        ),
+        (
+            list_head,
+            legacy_account::<NftBucket>(
+                &legacy_bucket(
+                    list_head_nonce,
+                    Pubkey::default(),
+                    Pubkey::default(),
+                    nft_bucket,
+                ),
+                169,
+            ),
+        ),
+        (
+            list_tail,
+            legacy_account::<NftBucket>(
+                &legacy_bucket(
+                    list_tail_nonce,
+                    Pubkey::default(),
+                    nft_bucket,
+                    Pubkey::default(),
+                ),
+                169,
+            ),
+        ),
+        (
+            nft_bucket,
+            legacy_account::<NftBucket>(
+                &legacy_bucket(0, nft_mint, list_head, list_tail),
+                169,
+            ),
+        ),
+        (
+            price_model,
+            legacy_account::<PriceModel>(
+                &LegacyPriceModel {
+                    nonce: price_model_nonce,
+                    index: 0,
+                    market,
+                    sale_prices: vec![SalePrice {
+                        mint: payment_mint,
+                        amount: PRICE,
+                    }],
+                },
+                370,
+            ),
+        ),
+        (
+            nft_mint,
+            token_program_account(spl_token::state::Mint {
+                mint_authority: Some(admin_key).into(),
+                decimals: 0,
+                is_initialized: true,
+                ..Default::default()
+            }),
+        ),
+        // The NFT has yet to reach the collection
+        (
+            get_associated_token_address(&collection, &nft_mint),
+            token_program_account(spl_token::state::Account {
+                mint: nft_mint,
+                owner: collection,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            }),
+        ),
+    ];
+    let mut test = Test::start_with_accounts(admin, accounts).await;
+    let admin = test.admin();
+    let stranger = test.funded_keypair().await;
+
+    let result = test
+        .process(
+            &[ix::migrate_global_state(&stranger.pubkey())],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+    test.process(&[ix::migrate_global_state(&admin.pubkey())], &[&admin])
+        .await
+        .unwrap();
+    let global_state_state = test.global_state().await;
+    assert_eq!(global_state_state.version, 1);
+    assert_eq!(global_state_state.num_markets, 1);
+    assert_eq!(global_state_state.authority, admin.pubkey());
+
+    let result = test
+        .process(
+            &[ix::migrate_market(&stranger.pubkey(), &market)],
+            &[&stranger],
+        )
+        .await;
+    assert_eq!(custom_error(result), error_code(ErrorCode::Unauthorized));
+    test.process(
+        &[
+            ix::migrate_market(&admin.pubkey(), &market),
+            ix::migrate_collection(&admin.pubkey(), &market, &collection),
+            ix::migrate_price_model(&admin.pubkey(), &market, &price_model),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    // Migrated accounts cannot be migrated again
+    let result = test
+        .process(&[ix::migrate_market(&admin.pubkey(), &market)], &[&admin])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InvalidAccountVersion)
+    );
+
+    let market_state = test.market(&market).await;
+    assert_eq!(market_state.whitelist(), &[admin.pubkey()]);
+    let (num_collections, num_open_collections) = (
+        market_state.num_collections,
+        market_state.num_open_collections,
+    );
+    assert_eq!((num_collections, num_open_collections), (1, 1));
+    assert_eq!(
+        &market_state.title[..market_state.title_len as usize],
+        b"Legacy market"
+    );
+
+    let collection_state = test.collection(&collection).await;
+    assert_eq!(collection_state.legacy_cursor, nft_bucket);
+    let inventory_page = test.init_inventory_page(&market, &collection).await;
+    let migrate_nft_bucket = ix::migrate_nft_bucket(
+        &admin.pubkey(),
+        &market,
+        &collection,
+        &nft_bucket,
+        &nft_mint,
+        &list_head,
+        &price_model,
+        &inventory_page,
+    );
+
+    // Buckets are migrated once all of them have been counted
+    let result = test
+        .process(std::slice::from_ref(&migrate_nft_bucket), &[&admin])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::LegacyBucketsPending)
+    );
+    test.process(
+        &[ix::count_legacy_buckets(
+            &admin.pubkey(),
+            &market,
+            &collection,
+            &[nft_bucket],
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let collection_state = test.collection(&collection).await;
+    assert_eq!(collection_state.legacy_cursor, Pubkey::default());
+    assert_eq!(collection_state.num_legacy_buckets, 1);
+
+    // The collection must hold the NFT
+    let result = test
+        .process(std::slice::from_ref(&migrate_nft_bucket), &[&admin])
+        .await;
+    assert_eq!(
+        custom_error(result),
+        error_code(ErrorCode::InvalidMigrationAccount)
+    );
+    test.mint_to(&nft_mint, &collection, 1).await;
+    test.process(&[migrate_nft_bucket], &[&admin])
+        .await
+        .unwrap();
+    let collection_state = test.collection(&collection).await;
+    assert_eq!(collection_state.num_legacy_buckets, 0);
+
+    let bucket = test.nft_bucket(&nft_bucket).await;
+    assert_eq!(bucket.version, 1);
+    assert_eq!(bucket.nft_mint, nft_mint);
+    assert_eq!(bucket.payer, payer);
+    assert_eq!(bucket.inventory_page, inventory_page);
+    assert_eq!(bucket.collection, collection);
+    let price_model_state = test.price_model(&price_model).await;
+    let num_listings = price_model_state.num_listings;
+    assert_eq!(num_listings, 1);
+    assert_eq!(price_model_state.sale_prices[0].mint, payment_mint);
+    let num_occupied = test.inventory_page(&inventory_page).await.num_occupied;
+    assert_eq!(num_occupied, 1);
+
+    let collection_state = test.collection(&collection).await;
+    assert_eq!(collection_state.title, "Legacy collection");
+    assert_eq!(
+        list_order(&mut test, &collection_state).await,
+        vec![nft_bucket]
+    );
+
+    // The migrated keypair bucket is accepted as a list item
+    test.process(
+        &[ix::set_nft_expiry(
+            &admin.pubkey(),
+            &market,
+            &collection,
+            &nft_bucket,
+            &bucket,
+            Some(1),
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let expires_at = test.nft_bucket(&nft_bucket).await.expires_at;
+    assert_eq!(expires_at, 1);
+}
+
+async fn list_order(test: &mut Test, collection: &Collection) -> Vec<Pubkey> {
+    test.list(collection)
+        .await
+        .unwrap()
+        .iter()
+        .map(|item| item.address)
+        .collect()
+}
@@ -119,3 +119,6 @@ export const createUserDebitAccount = async (
   const signature = await provider.send(tx, [userKeypair, paymentMint])
   await provider.connection.confirmTransaction(signature)
 }
+
+export const decodeFixedString = (bytes: number[], len: number) =>
+  Buffer.from(bytes.slice(0, len)).toString()
@@ -16,15 +16,20 @@ import { VibeMarket } from "../target/types/vibe_market"
 import {
   getCollectionAddress,
   getGlobalStateAddress,
+  getInventoryPageAddress,
   getListHeadAddress,
   getListTailAddress,
   getMarketAddress,
+  getMysteryCommitAddress,
+  getNftBucketAddress,
   getPriceModelAddress,
+  getProgramDataAddress,
 } from "../utils/seedAddresses"
 import {
   airdropAccount,
   createAdminNftMint,
   createUserDebitAccount,
+  decodeFixedString,
 } from "./testUtils"
 import { vibeMarketProgramId } from "../utils/constants"
 
@@ -38,9 +43,10 @@ describe("vibe-market", () => {
   const admin2 = anchor.web3.Keypair.generate()
   const user = anchor.web3.Keypair.generate()
   const nftMint = anchor.web3.Keypair.generate()
+  const nftMint2 = anchor.web3.Keypair.generate()
   const paymentMint = anchor.web3.Keypair.generate()
-  const nftBucket = anchor.web3.Keypair.generate()
 
+  let programDataAddress: PublicKey
   let globalStateAddress: PublicKey
   let globalStateAddressNonce: number
   let marketAddress: PublicKey
@@ -53,20 +59,43 @@ describe("vibe-market", () => {
   let listTailAddressNonce: number
   let priceModelAddress: PublicKey
   let priceModelAddressNonce: number
+  let inventoryPageAddress: PublicKey
+  let nftBucketAddress: PublicKey
+  let nftBucketAddressNonce: number
 
   before("vibe-market setup", async () => {
     await airdropAccount(connection, user.publicKey)
     await createAdminNftMint(program.provider, nftMint, admin.publicKey)
+    await createAdminNftMint(program.provider, nftMint2, admin.publicKey)
     await createUserDebitAccount(program.provider, user, paymentMint)
   })
 
-  it("Is initialized!", async () => {
+  it("Does not allow non-upgrade authority to initialize", async () => {
+    ;[programDataAddress] = await getProgramDataAddress()
     ;[globalStateAddress, globalStateAddressNonce] =
       await getGlobalStateAddress()
 
-    await program.rpc.initGlobalState(globalStateAddressNonce, {
+    try {
+      await program.rpc.initGlobalState({
+        accounts: {
+          admin: user.publicKey,
+          programData: programDataAddress,
+          globalState: globalStateAddress,
+          systemProgram: SystemProgram.programId,
+        },
+        signers: [user],
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 300)
+    }
+  })
+
+  it("Is initialized!", async () => {
+    await program.rpc.initGlobalState({
       accounts: {
         admin: admin.publicKey,
+        programData: programDataAddress,
         globalState: globalStateAddress,
         systemProgram: SystemProgram.programId,
       },
@@ -78,13 +107,16 @@ describe("vibe-market", () => {
 
     assert.ok(globalState.nonce === globalStateAddressNonce)
     assert.ok(globalState.numMarkets === 0)
+    assert.ok(globalState.authority.toBase58() === admin.publicKey.toBase58())
+    assert.ok(globalState.feeBasisPoints === 0)
   })
 
   it("Cannot initialize twice", async () => {
     try {
-      await program.rpc.initGlobalState(globalStateAddressNonce, {
+      await program.rpc.initGlobalState({
         accounts: {
           admin: admin.publicKey,
+          programData: programDataAddress,
           globalState: globalStateAddress,
           systemProgram: SystemProgram.programId,
         },
@@ -95,6 +127,66 @@ describe("vibe-market", () => {
     }
   })
 
+  it("Allows authority to update protocol fee", async () => {
+    await program.rpc.updateProtocolFee(500, {
+      accounts: {
+        authority: admin.publicKey,
+        globalState: globalStateAddress,
+        feeRecipient: admin2.publicKey,
+      },
+    })
+
+    const globalState = await program.account.globalState.fetch(
+      globalStateAddress
+    )
+    assert.ok(globalState.feeBasisPoints === 500)
+    assert.ok(
+      globalState.feeRecipient.toBase58() === admin2.publicKey.toBase58()
+    )
+  })
+
+  it("Does not allow non-authority to update protocol fee", async () => {
+    try {
+      await program.rpc.updateProtocolFee(0, {
+        accounts: {
+          authority: user.publicKey,
+          globalState: globalStateAddress,
+          feeRecipient: user.publicKey,
+        },
+        signers: [user],
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 300)
+    }
+  })
+
+  it("Does not allow non-authority to create markets when permissioned", async () => {
+    const globalState = await program.account.globalState.fetch(
+      globalStateAddress
+    )
+    const [marketAddress, marketAddressNonce] = await getMarketAddress(
+      globalStateAddress,
+      globalState.numMarkets
+    )
+
+    try {
+      await program.rpc.initMarket([user.publicKey], "User Market", {
+        accounts: {
+          admin: user.publicKey,
+          globalState: globalStateAddress,
+          feeRecipient: admin2.publicKey,
+          market: marketAddress,
+          systemProgram: SystemProgram.programId,
+        },
+        signers: [user],
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 300)
+    }
+  })
+
   it("Initializes a market", async () => {
     let globalState = await program.account.globalState.fetch(
       globalStateAddress
@@ -107,10 +199,11 @@ describe("vibe-market", () => {
     const title = "Vibe Market"
     const whitelist = new Array(1).fill(new PublicKey(admin.publicKey))
 
-    await program.rpc.initMarket(marketAddressNonce, whitelist, title, {
+    await program.rpc.initMarket(whitelist, title, {
       accounts: {
         admin: admin.publicKey,
         globalState: globalStateAddress,
+        feeRecipient: admin2.publicKey,
         market: marketAddress,
         systemProgram: SystemProgram.programId,
       },
@@ -128,8 +221,9 @@ describe("vibe-market", () => {
         .map((publicKey) => publicKey.toBase58())
         .includes(admin.publicKey.toBase58())
     )
-    assert.ok(market.title === title)
+    assert.ok(decodeFixedString(market.title, market.titleLen) === title)
     assert.ok(market.index === 0)
+    assert.ok(market.version === 1)
   })
 
   it("Initializes a max size market", async () => {
@@ -144,10 +238,11 @@ describe("vibe-market", () => {
     const title = new Array(32).fill("x").join("")
     const whitelist = new Array(16).fill(new PublicKey(admin.publicKey))
 
-    await program.rpc.initMarket(marketAddressNonce, whitelist, title, {
+    await program.rpc.initMarket(whitelist, title, {
       accounts: {
         admin: admin.publicKey,
         globalState: globalStateAddress,
+        feeRecipient: admin2.publicKey,
         market: marketAddress,
         systemProgram: SystemProgram.programId,
       },
@@ -160,8 +255,8 @@ describe("vibe-market", () => {
     assert.ok(market.nonce === marketAddressNonce)
     assert.ok(market.numCollections === 0)
     assert.ok(market.numPriceModels === 0)
-    assert.ok(market.whitelist.length === 16)
-    assert.ok(market.title === title)
+    assert.ok(market.whitelistLen === 16)
+    assert.ok(decodeFixedString(market.title, market.titleLen) === title)
     assert.ok(market.index === 1)
 
     // Cannot add admins to max length whitelist
@@ -177,6 +272,92 @@ describe("vibe-market", () => {
     } catch (err) {
       assert.ok(true)
     }
+
+    // Max size metadata fits the fixed market layout
+    const metadata = {
+      description: new Array(256).fill("x").join(""),
+      imageUri: new Array(200).fill("x").join(""),
+      externalUrl: new Array(200).fill("x").join(""),
+      sortPriority: 1,
+    }
+    await program.rpc.updateMarketMetadata(title, metadata, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+      },
+    })
+    const maxMarket = await program.account.market.fetch(marketAddress)
+    assert.ok(
+      decodeFixedString(
+        maxMarket.metadata.description,
+        maxMarket.metadata.descriptionLen
+      ) === metadata.description
+    )
+    assert.ok(
+      decodeFixedString(
+        maxMarket.metadata.externalUrl,
+        maxMarket.metadata.externalUrlLen
+      ) === metadata.externalUrl
+    )
+  })
+
+  it("Allows admin to update market metadata", async () => {
+    const title = "Vibe Market"
+    const metadata = {
+      description: new Array(256).fill("x").join(""),
+      imageUri: "https://vibe.market/logo.png",
+      externalUrl: "https://vibe.market",
+      sortPriority: 1,
+    }
+
+    await program.rpc.updateMarketMetadata(title, metadata, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+      },
+    })
+
+    const market = await program.account.market.fetch(marketAddress)
+    assert.ok(decodeFixedString(market.title, market.titleLen) === title)
+    assert.ok(
+      decodeFixedString(
+        market.metadata.description,
+        market.metadata.descriptionLen
+      ) === metadata.description
+    )
+    assert.ok(
+      decodeFixedString(market.metadata.imageUri, market.metadata.imageUriLen) ===
+        metadata.imageUri
+    )
+    assert.ok(
+      decodeFixedString(
+        market.metadata.externalUrl,
+        market.metadata.externalUrlLen
+      ) === metadata.externalUrl
+    )
+    assert.ok(market.metadata.sortPriority === metadata.sortPriority)
+  })
+
+  it("Does not allow oversized market titles", async () => {
+    const title = new Array(33).fill("x").join("")
+    const metadata = {
+      description: "",
+      imageUri: "",
+      externalUrl: "",
+      sortPriority: 0,
+    }
+
+    try {
+      await program.rpc.updateMarketMetadata(title, metadata, {
+        accounts: {
+          admin: admin.publicKey,
+          market: marketAddress,
+        },
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 314)
+    }
   })
 
   it("Allows admin to add new admin", async () => {
@@ -237,6 +418,22 @@ describe("vibe-market", () => {
     assert.ok(whitelistStr.includes(admin.publicKey.toBase58()))
   })
 
+  it("Does not migrate accounts already at the current version", async () => {
+    try {
+      await program.rpc.migrateMarket({
+        accounts: {
+          admin: admin.publicKey,
+          market: marketAddress,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        },
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 337)
+    }
+  })
+
   it("Allows for collection creation", async () => {
     const market = await program.account.market.fetch(marketAddress)
 
@@ -253,22 +450,16 @@ describe("vibe-market", () => {
 
     const title = new Array(32).fill("x").join("")
 
-    await program.rpc.initCollection(
-      collectionAddressNonce,
-      listHeadAddressNonce,
-      listTailAddressNonce,
-      title,
-      {
-        accounts: {
-          admin: admin.publicKey,
-          market: marketAddress,
-          collection: collectionAddress,
-          listHead: listHeadAddress,
-          listTail: listTailAddress,
-          systemProgram: SystemProgram.programId,
-        },
-      }
-    )
+    await program.rpc.initCollection(title, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        listHead: listHeadAddress,
+        listTail: listTailAddress,
+        systemProgram: SystemProgram.programId,
+      },
+    })
 
     const collection = await program.account.collection.fetch(collectionAddress)
     assert.ok(collection.listHead.toBase58() === listHeadAddress.toBase58())
@@ -276,6 +467,34 @@ describe("vibe-market", () => {
     assert.ok(collection.title === title)
     assert.ok(collection.nonce === collectionAddressNonce)
     assert.ok(collection.index === 0)
+    assert.ok(collection.version === 1)
+  })
+
+  it("Allows for inventory page creation", async () => {
+    ;[inventoryPageAddress] = await getInventoryPageAddress(collectionAddress, 0)
+
+    await program.rpc.initInventoryPage({
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        inventoryPage: inventoryPageAddress,
+        systemProgram: SystemProgram.programId,
+      },
+    })
+
+    const collection = await program.account.collection.fetch(collectionAddress)
+    assert.ok(collection.numInventoryPages === 1)
+
+    const inventoryPage = await program.account.inventoryPage.fetch(
+      inventoryPageAddress
+    )
+    assert.ok(
+      inventoryPage.collection.toBase58() === collectionAddress.toBase58()
+    )
+    assert.ok(inventoryPage.index === 0)
+    assert.ok(inventoryPage.numOccupied === 0)
+    assert.ok(inventoryPage.freeHead === 0)
   })
 
   it("Allows for price model creation", async () => {
@@ -291,7 +510,7 @@ describe("vibe-market", () => {
       amount: new anchor.BN(100),
     })
 
-    await program.rpc.initPriceModel(priceModelAddressNonce, salePrices, {
+    await program.rpc.initPriceModel(salePrices, {
       accounts: {
         admin: admin.publicKey,
         market: marketAddress,
@@ -311,7 +530,87 @@ describe("vibe-market", () => {
     assert.ok(priceModel.salePrices[0].amount.toNumber() === 100)
   })
 
+  it("Allows admin to toggle mystery box purchases", async () => {
+    await program.rpc.setMysteryPriceModel(true, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        priceModel: priceModelAddress,
+      },
+    })
+    let collection = await program.account.collection.fetch(collectionAddress)
+    assert.ok(
+      collection.mysteryPriceModel.toString() === priceModelAddress.toString()
+    )
+
+    await program.rpc.setMysteryPriceModel(false, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        priceModel: priceModelAddress,
+      },
+    })
+    collection = await program.account.collection.fetch(collectionAddress)
+    assert.ok(collection.mysteryPriceModel === null)
+
+    const [mysteryCommitAddress] = await getMysteryCommitAddress(
+      collectionAddress,
+      user.publicKey
+    )
+    const userPaymentAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      paymentMint.publicKey,
+      user.publicKey
+    )
+    const programCreditAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      paymentMint.publicKey,
+      collectionAddress,
+      true
+    )
+    const feeCreditAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      paymentMint.publicKey,
+      admin2.publicKey
+    )
+
+    try {
+      await program.rpc.commitRandomPurchase({
+        accounts: {
+          owner: user.publicKey,
+          globalState: globalStateAddress,
+          market: marketAddress,
+          collection: collectionAddress,
+          priceModel: priceModelAddress,
+          mysteryCommit: mysteryCommitAddress,
+          debitMint: paymentMint.publicKey,
+          debitAccount: userPaymentAccountAddress,
+          programCreditAccount: programCreditAccountAddress,
+          feeRecipient: admin2.publicKey,
+          feeCreditAccount: feeCreditAccountAddress,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        },
+        signers: [user],
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 330)
+    }
+  })
+
   it("Allows for nft addition", async () => {
+    ;[nftBucketAddress, nftBucketAddressNonce] = await getNftBucketAddress(
+      collectionAddress,
+      nftMint.publicKey
+    )
     const adminAssociatedAddress = await Token.getAssociatedTokenAddress(
       ASSOCIATED_TOKEN_PROGRAM_ID,
       TOKEN_PROGRAM_ID,
@@ -340,9 +639,10 @@ describe("vibe-market", () => {
         admin: admin.publicKey,
         market: marketAddress,
         collection: collectionAddress,
-        listHead: listHeadAddress,
+        prevListItem: listHeadAddress,
         nextListItem: listTailAddress,
-        newItem: nftBucket.publicKey,
+        newItem: nftBucketAddress,
+        inventoryPage: inventoryPageAddress,
         priceModel: priceModelAddress,
         adminNftAccount: adminAssociatedAddress,
         adminNftMint: nftMint.publicKey,
@@ -352,7 +652,6 @@ describe("vibe-market", () => {
         systemProgram: SystemProgram.programId,
         rent: SYSVAR_RENT_PUBKEY,
       },
-      signers: [nftBucket],
     })
 
     adminAccount = await token.getAccountInfo(adminAssociatedAddress)
@@ -361,9 +660,9 @@ describe("vibe-market", () => {
     assert.ok(programAccount.amount.toNumber() === 1)
 
     const nftBucketAccount = await program.account.nftBucket.fetch(
-      nftBucket.publicKey
+      nftBucketAddress
     )
-    assert.ok(nftBucketAccount.nonce === 0)
+    assert.ok(nftBucketAccount.nonce === nftBucketAddressNonce)
     assert.ok(
       nftBucketAccount.nftMint.toString() === nftMint.publicKey.toString()
     )
@@ -377,15 +676,31 @@ describe("vibe-market", () => {
       nftBucketAccount.nextListItem.toString() === listTailAddress.toString()
     )
     assert.ok(nftBucketAccount.payer.toString() === admin.publicKey.toString())
+    assert.ok(
+      nftBucketAccount.inventoryPage.toString() ===
+        inventoryPageAddress.toString()
+    )
+
+    const inventoryPage = await program.account.inventoryPage.fetch(
+      inventoryPageAddress
+    )
+    assert.ok(inventoryPage.numOccupied === 1)
+    assert.ok(
+      inventoryPage.slots[nftBucketAccount.inventorySlot].nftBucket.toString() ===
+        nftBucketAddress.toString()
+    )
 
     const listHead = await program.account.nftBucket.fetch(listHeadAddress)
     assert.ok(
-      listHead.nextListItem.toString() === nftBucket.publicKey.toString()
+      listHead.nextListItem.toString() === nftBucketAddress.toString()
     )
     const listTail = await program.account.nftBucket.fetch(listTailAddress)
     assert.ok(
-      listTail.prevListItem.toString() === nftBucket.publicKey.toString()
+      listTail.prevListItem.toString() === nftBucketAddress.toString()
     )
+
+    const collection = await program.account.collection.fetch(collectionAddress)
+    assert.ok(collection.stats.numListed === 1)
   })
 
   it("Does not allow for collection closing while NFTs exist", async () => {
@@ -398,6 +713,7 @@ describe("vibe-market", () => {
           collection: collectionAddress,
           listHead: listHeadAddress,
           listTail: listTailAddress,
+          tokenProgram: TOKEN_PROGRAM_ID,
         },
       })
       assert.ok(false)
@@ -406,13 +722,7 @@ describe("vibe-market", () => {
     }
   })
 
-  it("Allows for nft withdrawal", async () => {
-    const adminNftAccountAddress = await Token.getAssociatedTokenAddress(
-      ASSOCIATED_TOKEN_PROGRAM_ID,
-      TOKEN_PROGRAM_ID,
-      nftMint.publicKey,
-      admin.publicKey
-    )
+  it("Does not allow cranking of unexpired listings", async () => {
     const programNftAccountAddress = await Token.getAssociatedTokenAddress(
       ASSOCIATED_TOKEN_PROGRAM_ID,
       TOKEN_PROGRAM_ID,
@@ -420,46 +730,266 @@ describe("vibe-market", () => {
       collectionAddress,
       true
     )
-
-    const nftBucketAccount = await program.account.nftBucket.fetch(
-      nftBucket.publicKey
+    const payerNftAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      nftMint.publicKey,
+      admin.publicKey
     )
 
-    await program.rpc.withdrawNft({
+    const expiresAt = new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+    await program.rpc.setNftExpiry(expiresAt, {
       accounts: {
         admin: admin.publicKey,
-        rentRefund: admin.publicKey,
-        priceModel: priceModelAddress,
         market: marketAddress,
         collection: collectionAddress,
-        withdrawListItem: nftBucket.publicKey,
-        programNftAccount: programNftAccountAddress,
-        programNftMint: nftMint.publicKey,
-        adminNftAccount: adminNftAccountAddress,
-        prevListItem: nftBucketAccount.prevListItem,
-        nextListItem: nftBucketAccount.nextListItem,
-        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-        tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
-        rent: SYSVAR_RENT_PUBKEY,
+        listItem: nftBucketAddress,
       },
     })
+    const nftBucketAccount = await program.account.nftBucket.fetch(
+      nftBucketAddress
+    )
+    assert.ok(nftBucketAccount.expiresAt.eq(expiresAt))
 
     try {
-      await program.account.nftBucket.fetch(nftBucket.publicKey)
-      assert.ok(false)
-    } catch (err) {
-      assert.ok(true)
-    }
-
-    const nftToken = new Token(
-      connection,
-      nftMint.publicKey,
-      TOKEN_PROGRAM_ID,
-      user
-    )
-    const adminNftAccount = await nftToken.getAccountInfo(
-      adminNftAccountAddress
+      await program.rpc.crankExpired({
+        accounts: {
+          cranker: user.publicKey,
+          rentRefund: admin.publicKey,
+          priceModel: priceModelAddress,
+          market: marketAddress,
+          collection: collectionAddress,
+          expiredListItem: nftBucketAddress,
+          prevListItem: nftBucketAccount.prevListItem,
+          nextListItem: nftBucketAccount.nextListItem,
+          inventoryPage: inventoryPageAddress,
+          programNftAccount: programNftAccountAddress,
+          programNftMint: nftMint.publicKey,
+          payerNftAccount: payerNftAccountAddress,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        },
+        signers: [user],
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 329)
+    }
+
+    await program.rpc.setNftExpiry(null, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        listItem: nftBucketAddress,
+      },
+    })
+  })
+
+  it("Allows for ordered nft insertion and moving", async () => {
+    const [nftBucket2Address] = await getNftBucketAddress(
+      collectionAddress,
+      nftMint2.publicKey
+    )
+    const adminNftAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      nftMint2.publicKey,
+      admin.publicKey
+    )
+    const programNftAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      nftMint2.publicKey,
+      collectionAddress,
+      true
+    )
+
+    await program.rpc.insertNftAfter({
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        prevListItem: nftBucketAddress,
+        nextListItem: listTailAddress,
+        newItem: nftBucket2Address,
+        inventoryPage: inventoryPageAddress,
+        priceModel: priceModelAddress,
+        adminNftAccount: adminNftAccountAddress,
+        adminNftMint: nftMint2.publicKey,
+        programNftAccount: programNftAccountAddress,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      },
+    })
+
+    let nftBucket2Account = await program.account.nftBucket.fetch(
+      nftBucket2Address
+    )
+    assert.ok(
+      nftBucket2Account.prevListItem.toString() === nftBucketAddress.toString()
+    )
+    assert.ok(
+      nftBucket2Account.nextListItem.toString() === listTailAddress.toString()
+    )
+
+    try {
+      await program.rpc.moveItem({
+        accounts: {
+          admin: admin.publicKey,
+          market: marketAddress,
+          collection: collectionAddress,
+          listItem: nftBucket2Address,
+          prevListItem: nftBucketAddress,
+          nextListItem: listTailAddress,
+          newPrevListItem: nftBucketAddress,
+          newNextListItem: nftBucket2Address,
+        },
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 324)
+    }
+
+    await program.rpc.moveItem({
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        listItem: nftBucket2Address,
+        prevListItem: nftBucketAddress,
+        nextListItem: listTailAddress,
+        newPrevListItem: listHeadAddress,
+        newNextListItem: nftBucketAddress,
+      },
+    })
+
+    const listHead = await program.account.nftBucket.fetch(listHeadAddress)
+    assert.ok(
+      listHead.nextListItem.toString() === nftBucket2Address.toString()
+    )
+    nftBucket2Account = await program.account.nftBucket.fetch(
+      nftBucket2Address
+    )
+    assert.ok(
+      nftBucket2Account.prevListItem.toString() === listHeadAddress.toString()
+    )
+    assert.ok(
+      nftBucket2Account.nextListItem.toString() === nftBucketAddress.toString()
+    )
+    const nftBucketAccount = await program.account.nftBucket.fetch(
+      nftBucketAddress
+    )
+    assert.ok(
+      nftBucketAccount.prevListItem.toString() === nftBucket2Address.toString()
+    )
+    assert.ok(
+      nftBucketAccount.nextListItem.toString() === listTailAddress.toString()
+    )
+    const listTail = await program.account.nftBucket.fetch(listTailAddress)
+    assert.ok(
+      listTail.prevListItem.toString() === nftBucketAddress.toString()
+    )
+
+    await program.rpc.withdrawNft({
+      accounts: {
+        admin: admin.publicKey,
+        rentRefund: admin.publicKey,
+        priceModel: priceModelAddress,
+        market: marketAddress,
+        collection: collectionAddress,
+        withdrawListItem: nftBucket2Address,
+        inventoryPage: inventoryPageAddress,
+        programNftAccount: programNftAccountAddress,
+        programNftMint: nftMint2.publicKey,
+        adminNftAccount: adminNftAccountAddress,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      },
+    })
+    await program.rpc.compactListItem({
+      accounts: {
+        rentRefund: admin.publicKey,
+        listItem: nftBucket2Address,
+        prevListItem: listHeadAddress,
+        nextListItem: nftBucketAddress,
+      },
+    })
+  })
+
+  it("Allows for nft withdrawal", async () => {
+    const adminNftAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      nftMint.publicKey,
+      admin.publicKey
+    )
+    const programNftAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      nftMint.publicKey,
+      collectionAddress,
+      true
+    )
+
+    const nftBucketAccount = await program.account.nftBucket.fetch(
+      nftBucketAddress
+    )
+
+    await program.rpc.withdrawNft({
+      accounts: {
+        admin: admin.publicKey,
+        rentRefund: admin.publicKey,
+        priceModel: priceModelAddress,
+        market: marketAddress,
+        collection: collectionAddress,
+        withdrawListItem: nftBucketAddress,
+        inventoryPage: inventoryPageAddress,
+        programNftAccount: programNftAccountAddress,
+        programNftMint: nftMint.publicKey,
+        adminNftAccount: adminNftAccountAddress,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      },
+    })
+
+    const tombstonedBucketAccount = await program.account.nftBucket.fetch(
+      nftBucketAddress
+    )
+    assert.ok(tombstonedBucketAccount.tombstoned)
+
+    await program.rpc.compactListItem({
+      accounts: {
+        rentRefund: admin.publicKey,
+        listItem: nftBucketAddress,
+        prevListItem: nftBucketAccount.prevListItem,
+        nextListItem: nftBucketAccount.nextListItem,
+      },
+    })
+
+    try {
+      await program.account.nftBucket.fetch(nftBucketAddress)
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(true)
+    }
+
+    const nftToken = new Token(
+      connection,
+      nftMint.publicKey,
+      TOKEN_PROGRAM_ID,
+      user
+    )
+    const adminNftAccount = await nftToken.getAccountInfo(
+      adminNftAccountAddress
     )
     assert.ok(adminNftAccount.amount.toNumber() === 1)
     try {
@@ -485,6 +1015,69 @@ describe("vibe-market", () => {
     )
   })
 
+  it("Does not allow nft addition while paused", async () => {
+    const adminAssociatedAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      nftMint.publicKey,
+      admin.publicKey
+    )
+    const programNftAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      nftMint.publicKey,
+      collectionAddress,
+      true
+    )
+
+    await program.rpc.setCollectionPaused(true, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+      },
+    })
+
+    let collection = await program.account.collection.fetch(collectionAddress)
+    assert.ok(collection.paused)
+
+    try {
+      await program.rpc.addNft({
+        accounts: {
+          admin: admin.publicKey,
+          market: marketAddress,
+          collection: collectionAddress,
+          prevListItem: listHeadAddress,
+          nextListItem: listTailAddress,
+          newItem: nftBucketAddress,
+          inventoryPage: inventoryPageAddress,
+          priceModel: priceModelAddress,
+          adminNftAccount: adminAssociatedAddress,
+          adminNftMint: nftMint.publicKey,
+          programNftAccount: programNftAccountAddress,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        },
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 309)
+    }
+
+    await program.rpc.setCollectionPaused(false, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+      },
+    })
+
+    collection = await program.account.collection.fetch(collectionAddress)
+    assert.ok(!collection.paused)
+  })
+
   it("Allows for nft purchasing", async () => {
     const adminAssociatedAddress = await Token.getAssociatedTokenAddress(
       ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -508,7 +1101,7 @@ describe("vibe-market", () => {
       ASSOCIATED_TOKEN_PROGRAM_ID,
       TOKEN_PROGRAM_ID,
       paymentMint.publicKey,
-      marketAddress,
+      collectionAddress,
       true
     )
     const programNftAccountAddress = await Token.getAssociatedTokenAddress(
@@ -518,15 +1111,22 @@ describe("vibe-market", () => {
       collectionAddress,
       true
     )
+    const feeCreditAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      paymentMint.publicKey,
+      admin2.publicKey
+    )
 
     await program.rpc.addNft({
       accounts: {
         admin: admin.publicKey,
         market: marketAddress,
         collection: collectionAddress,
-        listHead: listHeadAddress,
+        prevListItem: listHeadAddress,
         nextListItem: listTailAddress,
-        newItem: nftBucket.publicKey,
+        newItem: nftBucketAddress,
+        inventoryPage: inventoryPageAddress,
         priceModel: priceModelAddress,
         adminNftAccount: adminAssociatedAddress,
         adminNftMint: nftMint.publicKey,
@@ -536,36 +1136,70 @@ describe("vibe-market", () => {
         systemProgram: SystemProgram.programId,
         rent: SYSVAR_RENT_PUBKEY,
       },
-      signers: [nftBucket],
     })
 
     const nftBucketAccount = await program.account.nftBucket.fetch(
-      nftBucket.publicKey
+      nftBucketAddress
     )
 
+    const purchaseAccounts = {
+      owner: user.publicKey,
+      rentRefund: admin.publicKey,
+      globalState: globalStateAddress,
+      priceModel: priceModelAddress,
+      market: marketAddress,
+      collection: collectionAddress,
+      purchaseListItem: nftBucketAddress,
+      inventoryPage: inventoryPageAddress,
+      debitMint: paymentMint.publicKey,
+      debitAccount: userPaymentAccountAddress,
+      programCreditAccount: programCreditAccountAddress,
+      feeRecipient: admin2.publicKey,
+      feeCreditAccount: feeCreditAccountAddress,
+      programNftAccount: programNftAccountAddress,
+      programNftMint: nftMint.publicKey,
+      ownerNftAccount: userNftAccountAddress,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: SYSVAR_RENT_PUBKEY,
+    }
+
+    const saleStart = new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+    await program.rpc.setCollectionSaleWindow(saleStart, null, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+      },
+    })
+    const scheduledCollection = await program.account.collection.fetch(
+      collectionAddress
+    )
+    assert.ok(scheduledCollection.saleStart.eq(saleStart))
+
+    try {
+      await program.rpc.purchaseNft({
+        accounts: purchaseAccounts,
+        signers: [user],
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 325)
+    }
+
+    await program.rpc.setCollectionSaleWindow(null, null, {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+      },
+    })
+
     const tx = new Transaction()
     tx.add(
       await program.instruction.purchaseNft({
-        accounts: {
-          owner: user.publicKey,
-          rentRefund: admin.publicKey,
-          priceModel: priceModelAddress,
-          market: marketAddress,
-          collection: collectionAddress,
-          purchaseListItem: nftBucket.publicKey,
-          debitMint: paymentMint.publicKey,
-          debitAccount: userPaymentAccountAddress,
-          programCreditAccount: programCreditAccountAddress,
-          programNftAccount: programNftAccountAddress,
-          programNftMint: nftMint.publicKey,
-          ownerNftAccount: userNftAccountAddress,
-          prevListItem: nftBucketAccount.prevListItem,
-          nextListItem: nftBucketAccount.nextListItem,
-          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-          tokenProgram: TOKEN_PROGRAM_ID,
-          systemProgram: SystemProgram.programId,
-          rent: SYSVAR_RENT_PUBKEY,
-        },
+        accounts: purchaseAccounts,
       })
     )
 
@@ -577,8 +1211,22 @@ describe("vibe-market", () => {
 
     await userProvider.send(tx)
 
+    const tombstonedBucketAccount = await program.account.nftBucket.fetch(
+      nftBucketAddress
+    )
+    assert.ok(tombstonedBucketAccount.tombstoned)
+
+    await program.rpc.compactListItem({
+      accounts: {
+        rentRefund: admin.publicKey,
+        listItem: nftBucketAddress,
+        prevListItem: nftBucketAccount.prevListItem,
+        nextListItem: nftBucketAccount.nextListItem,
+      },
+    })
+
     try {
-      await program.account.nftBucket.fetch(nftBucket.publicKey)
+      await program.account.nftBucket.fetch(nftBucketAddress)
       assert.ok(false)
     } catch (err) {
       assert.ok(true)
@@ -597,7 +1245,30 @@ describe("vibe-market", () => {
     const programCreditAccount = await paymentToken.getAccountInfo(
       programCreditAccountAddress
     )
-    assert.ok(programCreditAccount.amount.toNumber() === 100)
+    assert.ok(programCreditAccount.amount.toNumber() === 95)
+    const feeCreditAccount = await paymentToken.getAccountInfo(
+      feeCreditAccountAddress
+    )
+    assert.ok(feeCreditAccount.amount.toNumber() === 5)
+
+    const collection = await program.account.collection.fetch(collectionAddress)
+    assert.ok(collection.stats.numListed === 0)
+    assert.ok(collection.stats.numSold === 1)
+    assert.ok(
+      collection.stats.volume[0].mint.toString() ===
+        paymentMint.publicKey.toString()
+    )
+    assert.ok(collection.stats.volume[0].amount.toNumber() === 95)
+    assert.ok(collection.stats.numVolume === 1)
+    assert.ok(
+      collection.stats.lastSale.nftMint.toString() ===
+        nftMint.publicKey.toString()
+    )
+    assert.ok(
+      collection.stats.lastSale.buyer.toString() === user.publicKey.toString()
+    )
+    const market = await program.account.market.fetch(marketAddress)
+    assert.ok(market.stats.numSold === 1)
 
     const nftToken = new Token(
       connection,
@@ -630,12 +1301,12 @@ describe("vibe-market", () => {
     )
   })
 
-  it("Allows admins to withdraw liquidity", async () => {
+  it("Allows admins to withdraw collection liquidity", async () => {
     const programPaymentAccountAddress = await Token.getAssociatedTokenAddress(
       ASSOCIATED_TOKEN_PROGRAM_ID,
       TOKEN_PROGRAM_ID,
       paymentMint.publicKey,
-      marketAddress,
+      collectionAddress,
       true
     )
     const adminPaymentAccountAddress = await Token.getAssociatedTokenAddress(
@@ -644,11 +1315,11 @@ describe("vibe-market", () => {
       paymentMint.publicKey,
       admin.publicKey
     )
-    await program.rpc.withdrawLiquidity(new anchor.BN(75), {
+    await program.rpc.withdrawCollectionLiquidity(new anchor.BN(75), {
       accounts: {
         admin: admin.publicKey,
-        globalState: globalStateAddress,
         market: marketAddress,
+        collection: collectionAddress,
         withdrawMint: paymentMint.publicKey,
         programDebitAccount: programPaymentAccountAddress,
         adminCreditAccount: adminPaymentAccountAddress,
@@ -668,14 +1339,57 @@ describe("vibe-market", () => {
     const programPaymentAccount = await paymentToken.getAccountInfo(
       programPaymentAccountAddress
     )
-    assert.ok(programPaymentAccount.amount.toNumber() === 25)
+    assert.ok(programPaymentAccount.amount.toNumber() === 20)
     const adminPaymentAccount = await paymentToken.getAccountInfo(
       adminPaymentAccountAddress
     )
     assert.ok(adminPaymentAccount.amount.toNumber() === 75)
   })
 
+  it("Allows for closing of inventory pages, when empty.", async () => {
+    await program.rpc.closeInventoryPage({
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        inventoryPage: inventoryPageAddress,
+      },
+    })
+
+    const collection = await program.account.collection.fetch(collectionAddress)
+    assert.ok(collection.numInventoryPages === 0)
+  })
+
   it("Allows for closing of NFT collections, when empty.", async () => {
+    const programPaymentAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      paymentMint.publicKey,
+      collectionAddress,
+      true
+    )
+    const adminPaymentAccountAddress = await Token.getAssociatedTokenAddress(
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      TOKEN_PROGRAM_ID,
+      paymentMint.publicKey,
+      admin.publicKey
+    )
+    await program.rpc.withdrawCollectionLiquidity(new anchor.BN(20), {
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        collection: collectionAddress,
+        withdrawMint: paymentMint.publicKey,
+        programDebitAccount: programPaymentAccountAddress,
+        adminCreditAccount: adminPaymentAccountAddress,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      },
+    })
+
+    // The treasury paid into by purchases must be closed with the collection
     const beforeBalance = await connection.getBalance(admin.publicKey)
     await program.rpc.closeCollection({
       accounts: {
@@ -685,9 +1399,76 @@ describe("vibe-market", () => {
         collection: collectionAddress,
         listHead: listHeadAddress,
         listTail: listTailAddress,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      remainingAccounts: [
+        {
+          pubkey: programPaymentAccountAddress,
+          isWritable: true,
+          isSigner: false,
+        },
+      ],
+    })
+    const afterBalance = await connection.getBalance(admin.publicKey)
+    assert.ok(beforeBalance < afterBalance)
+    assert.ok(
+      (await connection.getAccountInfo(programPaymentAccountAddress)) === null
+    )
+
+    const market = await program.account.market.fetch(marketAddress)
+    assert.ok(market.numOpenCollections === 0)
+    assert.ok(market.numCollections === 1)
+  })
+
+  it("Does not allow market closing while price models exist", async () => {
+    try {
+      await program.rpc.closeMarket({
+        accounts: {
+          admin: admin.publicKey,
+          rentRefund: admin.publicKey,
+          globalState: globalStateAddress,
+          market: marketAddress,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      })
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(err.code === 311)
+    }
+  })
+
+  it("Allows for closing of price models, when unused.", async () => {
+    await program.rpc.closePriceModel({
+      accounts: {
+        admin: admin.publicKey,
+        market: marketAddress,
+        priceModel: priceModelAddress,
+      },
+    })
+
+    const market = await program.account.market.fetch(marketAddress)
+    assert.ok(market.numOpenPriceModels === 0)
+  })
+
+  it("Allows for closing of markets, when empty.", async () => {
+    const beforeBalance = await connection.getBalance(admin.publicKey)
+    await program.rpc.closeMarket({
+      accounts: {
+        admin: admin.publicKey,
+        rentRefund: admin.publicKey,
+        globalState: globalStateAddress,
+        market: marketAddress,
+        tokenProgram: TOKEN_PROGRAM_ID,
       },
     })
     const afterBalance = await connection.getBalance(admin.publicKey)
     assert.ok(beforeBalance < afterBalance)
+
+    try {
+      await program.account.market.fetch(marketAddress)
+      assert.ok(false)
+    } catch (err) {
+      assert.ok(true)
+    }
   })
 })
@@ -2,6 +2,12 @@ import { PublicKey } from "@solana/web3.js"
 import * as anchor from "@project-serum/anchor"
 import { vibeMarketProgramId } from "./constants"
 
+export const getProgramDataAddress = () =>
+  PublicKey.findProgramAddress(
+    [vibeMarketProgramId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  )
+
 export const getGlobalStateAddress = () =>
   PublicKey.findProgramAddress([Buffer.from("global")], vibeMarketProgramId)
 
@@ -36,6 +42,32 @@ export const getListTailAddress = (collectionAddress: PublicKey) =>
     vibeMarketProgramId
   )
 
+export const getInventoryPageAddress = (
+  collectionAddress: PublicKey,
+  index: number
+) =>
+  PublicKey.findProgramAddress(
+    [
+      collectionAddress.toBuffer(),
+      new anchor.BN(index).toBuffer("le", 4),
+      Buffer.from("inventory_page"),
+    ],
+    vibeMarketProgramId
+  )
+
+export const getNftBucketAddress = (
+  collectionAddress: PublicKey,
+  nftMintAddress: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [
+      collectionAddress.toBuffer(),
+      nftMintAddress.toBuffer(),
+      Buffer.from("nft_bucket"),
+    ],
+    vibeMarketProgramId
+  )
+
 export const getPriceModelAddress = (marketAddress: PublicKey, index: number) =>
   PublicKey.findProgramAddress(
     [
@@ -45,3 +77,16 @@ export const getPriceModelAddress = (marketAddress: PublicKey, index: number) =>
     ],
     vibeMarketProgramId
   )
+
+export const getMysteryCommitAddress = (
+  collectionAddress: PublicKey,
+  ownerAddress: PublicKey
+) =>
+  PublicKey.findProgramAddress(
+    [
+      collectionAddress.toBuffer(),
+      ownerAddress.toBuffer(),
+      Buffer.from("mystery_commit"),
+    ],
+    vibeMarketProgramId
+  )